@@ -1,37 +1,102 @@
-use std::{cell::RefCell, collections::HashMap, ops, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    ops,
+    rc::Rc,
+};
 
 use lite_graphics::{Drawable, Offset, Size, color::Rgba};
 
 use crate::{
-    event::{Button, Event, Modifiers, RawEvent, WidgetEvent},
+    WindowOptions,
+    event::{Button, Event, Key, Modifiers, RawEvent, WidgetEvent},
+    shortcut::{Shortcut, Shortcuts},
     sys, themes,
+    widgets::IntoWidget,
 };
 
 thread_local! {
     pub(crate) static FRAMES: RefCell<HashMap<String, themes::FrameFn>> = RefCell::new(themes::get_default_theme());
+    pub(crate) static COLORS: RefCell<HashMap<String, Rgba>> = RefCell::new(themes::get_default_colors());
+    /// Set when reactive state changed the widget tree, every window redraws.
+    pub(crate) static NEEDS_REDRAW: Cell<bool> = const { Cell::new(false) };
 }
 
+type CloseFn = Box<dyn Fn(&crate::Window) -> bool>;
+type ExitFn = Box<dyn FnOnce(&App)>;
+type StartFn = Box<dyn FnOnce(&Rc<App>)>;
+
 pub struct App {
     pub(crate) windows: RefCell<HashMap<u64, Rc<crate::Window>>>,
     pub(crate) inner: sys::app::App,
     pub(crate) font: ab_glyph::FontArc,
+    on_close: Option<CloseFn>,
+    on_exit: Cell<Option<ExitFn>>,
+    shortcuts: Shortcuts,
 }
 
 impl App {
     pub fn new() -> crate::Result<Rc<Self>> {
-        let inner = sys::app::App::new()?;
-        let font: ab_glyph::FontArc = sys::get_default_font()?;
-        Ok(Rc::new(Self {
+        Ok(Rc::new(Self::with_backend(
+            sys::app::App::new()?,
+            sys::get_default_font()?,
+        )))
+    }
+    /// Create an app on the offscreen backend, whatever display is available.
+    ///
+    /// Setting `PARSLAY_BACKEND=headless` does the same for [`App::new`].
+    pub fn new_headless() -> crate::Result<Rc<Self>> {
+        Ok(Rc::new(Self::with_backend(
+            sys::app::App::new_headless(),
+            sys::get_default_font()?,
+        )))
+    }
+    pub fn builder() -> AppBuilder {
+        AppBuilder::new()
+    }
+    fn with_backend(inner: sys::app::App, font: ab_glyph::FontArc) -> Self {
+        Self {
             windows: RefCell::new(HashMap::new()),
             inner,
             font,
-        }))
+            on_close: None,
+            on_exit: Cell::new(None),
+            shortcuts: Shortcuts::default(),
+        }
+    }
+    /// Shortcuts for every window, after the window's own.
+    pub fn shortcuts(&self) -> &Shortcuts {
+        &self.shortcuts
+    }
+    /// Text on the clipboard, `None` if it's empty or doesn't hold text.
+    pub fn clipboard(&self, kind: ClipboardKind) -> Option<String> {
+        self.inner.get_clipboard(kind)
+    }
+    pub fn set_clipboard(&self, kind: ClipboardKind, text: &str) {
+        self.inner.set_clipboard(kind, text.to_owned());
+    }
+    /// Close `window` unless the `on_close` hook refuses.
+    pub fn request_close(&self, window: &crate::Window) {
+        if self.on_close.as_ref().is_none_or(|f| f(window)) {
+            window.close();
+        }
     }
     pub fn run(&self) -> crate::Result<()> {
-        while let Some(ev) = self.inner.get_events()? {
-            let RawEvent { window, event } = ev;
-            let mut windows = self.windows.borrow_mut();
-            let Some(win) = windows.get(&window) else {
+        loop {
+            self.windows.borrow_mut().retain(|_, w| !w.is_closed());
+            if self.windows.borrow().is_empty() {
+                break;
+            }
+            if NEEDS_REDRAW.take() {
+                let windows: Vec<_> = self.windows.borrow().values().cloned().collect();
+                for win in windows {
+                    win.redraw()?;
+                }
+            }
+            let Some(RawEvent { window, event }) = self.inner.get_events()? else {
+                break;
+            };
+            let Some(win) = self.windows.borrow().get(&window).cloned() else {
                 continue;
             };
             match event {
@@ -40,26 +105,70 @@ impl App {
                 )) => {
                     win.redraw()?;
                 }
+                Event::Window(crate::event::WindowEvent::Closing) => {
+                    self.request_close(&win);
+                }
                 Event::Window(crate::event::WindowEvent::Destroyed) => {
-                    windows.remove(&window);
+                    self.windows.borrow_mut().remove(&window);
                 }
                 Event::Window(crate::event::WindowEvent::Resize(w, h)) => {
                     win.resize(w, h);
                 }
                 Event::Window(crate::event::WindowEvent::KeyPress(mods, key)) => {
-                    if mods & (Modifiers::CONTROL | Modifiers::ALT | Modifiers::SUPER)
-                        != Modifiers::NONE
+                    win.drop_lost_focus();
+                    if win.run_key_handlers(true, mods, key) {
+                        win.redraw()?;
+                    }
+                    let shortcut = Shortcut::new(mods, key);
+                    if win.handle_shortcut(shortcut)
+                        || (!(shortcut.is_text() && win.focus.borrow().is_some())
+                            && self.shortcuts.trigger(shortcut))
                     {
-                        // TODO: Shortcuts
+                        win.redraw()?;
+                        continue;
+                    }
+                    if mods & (Modifiers::ALT | Modifiers::SUPER) != Modifiers::NONE {
                         continue;
                     }
                     let char = key.to_string();
                     if char == "\x1b" {
-                        *win.focus.borrow_mut() = None;
+                        win.set_focused(None);
+                        win.redraw()?;
+                        continue;
+                    }
+                    if key == Key::Tab {
+                        win.focus_next(mods & Modifiers::SHIFT == Modifiers::NONE);
+                        win.redraw()?;
                         continue;
                     }
-                    if let Some(w) = win.focus.borrow_mut().as_mut() {
-                        w.handle_key(key);
+                    let input = win.focus.borrow().clone();
+                    if let Some(w) = input {
+                        w.handle_key(&win, mods, key);
+                        win.redraw()?;
+                    } else if mods & Modifiers::CONTROL == Modifiers::NONE
+                        && matches!(key, Key::Enter | Key::NumEnter | Key::Space)
+                        && let Some(w) = win.focused()
+                    {
+                        w.activate();
+                        win.redraw()?;
+                    }
+                }
+                Event::Window(crate::event::WindowEvent::KeyRelease(mods, key)) => {
+                    if win.run_key_handlers(false, mods, key) {
+                        win.redraw()?;
+                    }
+                }
+                Event::Window(crate::event::WindowEvent::Text(text)) => {
+                    let input = win.focus.borrow().clone();
+                    if let Some(w) = input {
+                        w.insert_text(&text);
+                        win.redraw()?;
+                    }
+                }
+                Event::Window(crate::event::WindowEvent::Preedit(text, cursor)) => {
+                    let input = win.focus.borrow().clone();
+                    if let Some(w) = input {
+                        w.set_preedit(&text, cursor);
                         win.redraw()?;
                     }
                 }
@@ -80,7 +189,7 @@ impl App {
                     win.redraw()?;
                 }
                 Event::Widget(WidgetEvent::ButtonRelease(Button::Right, _, _)) => {} // Ignore
-                Event::Widget(WidgetEvent::ButtonPress(_, x, y)) => {
+                Event::Widget(WidgetEvent::ButtonPress(button, x, y)) => {
                     if let Some(offs) = win.rclick_offset.get() {
                         let offs_2 = offs + win.rclick_widget.borrow().get_computed_size();
                         if x >= offs.x && y >= offs.y && x < offs_2.x && y < offs_2.y {
@@ -109,6 +218,13 @@ impl App {
                             .borrow()
                             .clone()
                             .handle_button(Offset::new(x, y), Some(win.clone()));
+                        win.focus_at(Offset::new(x, y));
+                    }
+                    let input = win.focus.borrow().clone();
+                    if let (Button::Middle, Some(input)) = (button, input)
+                        && let Some(text) = self.clipboard(ClipboardKind::Primary)
+                    {
+                        input.insert_text(&text);
                     }
 
                     win.redraw()?;
@@ -142,6 +258,31 @@ impl App {
                         .handle_button(Offset::new(x, y), None);
                     win.redraw()?;
                 }
+                Event::Widget(WidgetEvent::Scroll {
+                    x,
+                    y,
+                    dx,
+                    dy,
+                    discrete,
+                }) => {
+                    let pos = Offset::new(x, y);
+                    let scrolled = match win.rclick_offset.get() {
+                        Some(offs) => win.rclick_widget.borrow().clone().handle_scroll(
+                            pos - offs,
+                            dx,
+                            dy,
+                            discrete,
+                        ),
+                        None => win
+                            .widget
+                            .borrow()
+                            .clone()
+                            .handle_scroll(pos, dx, dy, discrete),
+                    };
+                    if scrolled {
+                        win.redraw()?;
+                    }
+                }
                 Event::Widget(WidgetEvent::Move(x, y)) => {
                     let result = if let Some(offs) = win.rclick_offset.get() {
                         let result = win
@@ -171,9 +312,26 @@ impl App {
             }
         }
 
+        // The headless backend stops whenever it runs out of events, that's not an exit.
+        if self.windows.borrow().is_empty()
+            && let Some(f) = self.on_exit.take()
+        {
+            f(self);
+        }
         Ok(())
     }
 
+    /// Open another window showing `view`.
+    pub fn open_window<V: IntoWidget + 'static>(
+        self: &Rc<Self>,
+        options: WindowOptions,
+        view: impl FnOnce() -> V + 'static,
+    ) -> crate::Result<Rc<crate::Window>> {
+        let window = crate::Window::with_options(self, options)?;
+        window.render(view);
+        Ok(window)
+    }
+
     /// Add a frame. Will not insert if one already exists.
     pub fn add_frame<F: Fn(&dyn Drawable, Size, Rgba) + 'static>(name: String, f: F) {
         FRAMES.with_borrow_mut(|frames| {
@@ -188,6 +346,142 @@ impl App {
     }
 }
 
+enum FontSource {
+    Default,
+    Named(String),
+    Data(Vec<u8>),
+}
+
+/// Configures an [`App`] and its first window.
+pub struct AppBuilder {
+    font: FontSource,
+    frames: Vec<(String, themes::FrameFn)>,
+    colors: Vec<(String, Rgba)>,
+    window: WindowOptions,
+    on_start: Option<StartFn>,
+    on_close: Option<CloseFn>,
+    on_exit: Option<ExitFn>,
+    shortcuts: Shortcuts,
+}
+
+impl Default for AppBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AppBuilder {
+    pub fn new() -> Self {
+        Self {
+            font: FontSource::Default,
+            frames: vec![],
+            colors: vec![],
+            window: WindowOptions::default(),
+            on_start: None,
+            on_close: None,
+            on_exit: None,
+            shortcuts: Shortcuts::default(),
+        }
+    }
+    /// Use an installed font by family name instead of the desktop font.
+    pub fn font(mut self, name: impl Into<String>) -> Self {
+        self.font = FontSource::Named(name.into());
+        self
+    }
+    /// Use a font file loaded into memory, e.g. with `include_bytes!`.
+    pub fn font_data(mut self, data: impl Into<Vec<u8>>) -> Self {
+        self.font = FontSource::Data(data.into());
+        self
+    }
+    /// Replace a frame of the theme, or add a new one for [`FrameType::Custom`](crate::FrameType::Custom).
+    pub fn frame<F: Fn(&dyn Drawable, Size, Rgba) + 'static>(
+        mut self,
+        name: impl Into<String>,
+        f: F,
+    ) -> Self {
+        self.frames.push((name.into(), Rc::new(f)));
+        self
+    }
+    /// Replace a color of the theme, e.g. the one [`FrameType::FocusRing`](crate::FrameType::FocusRing)
+    /// is drawn with.
+    pub fn color(mut self, name: impl Into<String>, color: Rgba) -> Self {
+        self.colors.push((name.into(), color));
+        self
+    }
+    /// Options for the first window.
+    pub fn window(mut self, options: WindowOptions) -> Self {
+        self.window = options;
+        self
+    }
+    /// Run once the first window is open, before any event is handled.
+    pub fn on_start(mut self, f: impl FnOnce(&Rc<App>) + 'static) -> Self {
+        self.on_start = Some(Box::new(f));
+        self
+    }
+    /// Called when the user asks to close a window. Return `false` to keep it open.
+    pub fn on_close(mut self, f: impl Fn(&crate::Window) -> bool + 'static) -> Self {
+        self.on_close = Some(Box::new(f));
+        self
+    }
+    /// Called when [`App::run`] returns because every window is closed.
+    pub fn on_exit(mut self, f: impl FnOnce(&App) + 'static) -> Self {
+        self.on_exit = Some(Box::new(f));
+        self
+    }
+
+    /// Add an app-wide shortcut, see [`Shortcuts::add`].
+    pub fn shortcut<S: Into<Shortcut>>(
+        self,
+        shortcut: S,
+        description: impl Into<String>,
+        f: impl FnMut() + 'static,
+    ) -> Self {
+        self.shortcuts.add(shortcut, description, f);
+        self
+    }
+
+    /// Create the app and its first window showing `view`, without running it.
+    pub fn build<V: IntoWidget + 'static>(
+        self,
+        view: impl FnOnce() -> V + 'static,
+    ) -> crate::Result<Rc<App>> {
+        let font = match self.font {
+            FontSource::Default => sys::get_default_font()?,
+            FontSource::Named(name) => sys::get_named_font(&name)?,
+            FontSource::Data(data) => sys::load_font(&*data)?,
+        };
+        FRAMES.with_borrow_mut(|frames| frames.extend(self.frames));
+        COLORS.with_borrow_mut(|colors| colors.extend(self.colors));
+
+        let app = Rc::new(App {
+            on_close: self.on_close,
+            on_exit: Cell::new(self.on_exit),
+            shortcuts: self.shortcuts,
+            ..App::with_backend(sys::app::App::new()?, font)
+        });
+        app.open_window(self.window, view)?;
+        if let Some(f) = self.on_start {
+            f(&app);
+        }
+        Ok(app)
+    }
+    /// Build the app and run it until every window is closed.
+    pub fn launch<V: IntoWidget + 'static>(
+        self,
+        view: impl FnOnce() -> V + 'static,
+    ) -> crate::Result<()> {
+        self.build(view)?.run()
+    }
+}
+
+/// Which clipboard to use. `Primary` holds the last selected text on Linux, pasted with a
+/// middle click. Other platforms emulate it inside the app.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ClipboardKind {
+    Clipboard,
+    Primary,
+}
+
 pub struct HoverResult {
     pub redraw: bool,
     pub cursor: CursorType,
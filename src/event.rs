@@ -1,24 +1,29 @@
 use std::ops::{BitAnd, BitOr, Not};
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub(crate) struct RawEvent {
     pub(crate) window: u64,
     pub(crate) event: Event,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub enum Event {
     Window(WindowEvent),
     Widget(WidgetEvent),
     Unknown,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub enum WindowEvent {
-    /// Keyboard key press
+    /// Keyboard key press, sent again while the key is held
     KeyPress(Modifiers, Key),
     /// Keyboard key release
     KeyRelease(Modifiers, Key),
+    /// Text typed, after the keyboard layout and dead keys. Follows the `KeyPress` that typed it.
+    Text(String),
+    /// Text being composed by an input method, with its caret as a byte offset. Empty once
+    /// the composition is committed as `Text` or cancelled.
+    Preedit(String, Option<usize>),
     /// Sent when window changes state
     StateChange(WindowState),
     /// Sent when the window is resized
@@ -46,6 +51,15 @@ pub enum WidgetEvent {
     ButtonPress(Button, i32, i32),
     /// Pointer button release. (x,y) is in pixels
     ButtonRelease(Button, i32, i32),
+    /// Scrolled by (dx,dy) wheel notches, positive to the right and down, with the pointer at
+    /// (x,y) in pixels. `discrete` if it came from a wheel rather than a touchpad.
+    Scroll {
+        x: i32,
+        y: i32,
+        dx: f32,
+        dy: f32,
+        discrete: bool,
+    },
 }
 
 #[repr(u8)]
@@ -66,15 +80,15 @@ impl Button {
             1 => Self::Left,
             2 => Self::Middle,
             3 => Self::Right,
-            4 => Self::Forward,
-            5 => Self::Back,
+            8 => Self::Back,
+            9 => Self::Forward,
             _ => Self::Other,
         }
     }
 }
 
 #[allow(non_camel_case_types)]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub enum Key {
     Escape,
     Tab,
@@ -465,7 +479,7 @@ impl ToString for Key {
 }
 
 #[repr(transparent)]
-#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
 pub struct Modifiers(pub(crate) u8);
 
 impl Modifiers {
@@ -478,6 +492,10 @@ impl Modifiers {
     pub const ALT: Self = Self(4);
     /// Super / Windows / Command key
     pub const SUPER: Self = Self(8);
+    /// Caps Lock is on
+    pub const CAPS_LOCK: Self = Self(16);
+    /// Num Lock is on
+    pub const NUM_LOCK: Self = Self(32);
 }
 
 impl BitOr for Modifiers {
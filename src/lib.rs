@@ -1,13 +1,17 @@
 pub mod app;
 pub mod error;
 pub mod event;
+pub mod shortcut;
 mod sys;
+#[cfg(feature = "testing")]
+pub mod testing;
 mod text;
 mod themes;
 pub mod widgets;
-mod window;
+pub mod window;
 
 use app::App;
+pub use app::AppBuilder;
 pub use error::Error;
 pub use error::Result;
 pub use lite_graphics::color::{Color, Rgba};
@@ -15,33 +19,37 @@ pub use themes::FrameType;
 use widgets::IntoWidget;
 
 use lite_graphics::Size as ComputedSize;
+pub use shortcut::Shortcut;
 pub use widgets::{
     WidgetBase, WidgetExt, WidgetGroup,
     button::button,
     drop_down::drop_down,
+    grid::grid,
     input::{dyn_input, input},
     label::{dyn_label, label},
-    stack::hstack,
-    stack::vstack,
+    scroll_view::scroll_view,
+    stack::{dyn_hstack, dyn_stack, flow, hstack, vstack},
+    switch::{either, show, switch},
+    virtual_list::virtual_list,
+    zstack::zstack,
 };
-use window::Window;
+pub use window::{Window, WindowOptions};
 
 pub use floem_reactive as reactive;
 
 pub mod prelude {
     pub use super::reactive::RwSignal;
-    pub use super::{Color, Rgba};
     pub use super::{
-        FrameType, Sizing::*, WidgetBase, WidgetExt, WidgetGroup, button, drop_down, dyn_input,
-        dyn_label, hstack, input, label, launch, vstack,
+        Align, AlignItems, AppBuilder, FrameType, JustifyContent, Shortcut, Sizing::*, WidgetBase,
+        WidgetExt, WidgetGroup, WindowOptions, button, drop_down, dyn_hstack, dyn_input, dyn_label,
+        dyn_stack, either, flow, grid, hstack, input, label, launch, scroll_view, show, switch,
+        virtual_list, vstack, zstack,
     };
+    pub use super::{Color, Rgba};
 }
 
 pub fn launch<V: IntoWidget + 'static>(view: impl FnOnce() -> V + 'static) -> crate::Result<()> {
-    let app = App::new()?;
-    let window = Window::new(&app)?;
-    window.render(view);
-    app.run()
+    AppBuilder::new().launch(view)
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -60,33 +68,132 @@ impl Default for Sizing {
     }
 }
 
-#[derive(Clone, Copy, Default)]
+/// Where a widget goes in more room than it takes.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Start,
+    Center,
+    End,
+}
+
+impl Align {
+    /// Offset of something `len` long in `room`.
+    pub(crate) fn place(self, len: u32, room: u32) -> i32 {
+        match self {
+            Self::Start => 0,
+            Self::Center => (room.saturating_sub(len) / 2) as i32,
+            Self::End => room.saturating_sub(len) as i32,
+        }
+    }
+}
+
+/// Where children of a stack go across it.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum AlignItems {
+    #[default]
+    Start,
+    Center,
+    End,
+    /// Take all the room, unless the size is fixed.
+    Stretch,
+    /// Line up the first lines of text. Only in a row, like `Start` otherwise.
+    Baseline,
+}
+
+/// How the room left along a stack is shared.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum JustifyContent {
+    #[default]
+    Start,
+    Center,
+    End,
+    /// Between the children, none at the ends.
+    SpaceBetween,
+    /// Around each child, half as much at the ends.
+    SpaceAround,
+}
+
+#[derive(Clone, Copy)]
 pub struct Size {
     pub w: Sizing,
     pub h: Sizing,
+    /// Smallest width and height, whatever the content.
+    min: (u32, u32),
+    /// Largest width and height, even if it cuts the content.
+    max: (u32, u32),
+    /// Width over height. The height follows the width.
+    aspect: Option<f32>,
+}
+
+impl Default for Size {
+    fn default() -> Self {
+        Self::new(Sizing::default(), Sizing::default())
+    }
 }
 
 impl Size {
     pub fn new(w: Sizing, h: Sizing) -> Self {
-        Self { w, h }
-    }
-    pub fn fixed(w: u32, h: u32) -> Self {
         Self {
-            w: Sizing::Fixed(w),
-            h: Sizing::Fixed(h),
+            w,
+            h,
+            min: (0, 0),
+            max: (u32::MAX, u32::MAX),
+            aspect: None,
         }
     }
+    pub fn fixed(w: u32, h: u32) -> Self {
+        Self::new(Sizing::Fixed(w), Sizing::Fixed(h))
+    }
     pub fn stretch(w_fac: u32, h_fac: u32) -> Self {
-        Self {
-            w: Sizing::Stretch(w_fac),
-            h: Sizing::Stretch(h_fac),
-        }
+        Self::new(Sizing::Stretch(w_fac), Sizing::Stretch(h_fac))
     }
     pub fn fill() -> Self {
-        Self {
-            w: Sizing::Fill,
-            h: Sizing::Fill,
-        }
+        Self::new(Sizing::Fill, Sizing::Fill)
+    }
+    /// At least `w` wide and `h` high, whatever the content.
+    pub fn min(mut self, w: u32, h: u32) -> Self {
+        self.min = (w, h);
+        self
+    }
+    /// At most `w` wide and `h` high, even if it cuts the content.
+    pub fn max(mut self, w: u32, h: u32) -> Self {
+        self.max = (w, h);
+        self
+    }
+    /// Keep `width / height` at `ratio`.
+    pub fn aspect(mut self, ratio: f32) -> Self {
+        self.aspect = Some(ratio);
+        self
+    }
+    pub(crate) fn get_max(&self) -> (u32, u32) {
+        self.max
+    }
+    pub(crate) fn get_aspect(&self) -> Option<f32> {
+        self.aspect
+    }
+    /// Width `bounds` kept within `min` and `max`.
+    pub(crate) fn limit_width(&self, bounds: (u32, u32)) -> (u32, u32) {
+        let max = self.max.0.max(self.min.0);
+        (
+            bounds.0.clamp(self.min.0, max),
+            bounds.1.clamp(self.min.0, max),
+        )
+    }
+    /// Height `bounds` kept within `min` and `max`, or made to fit `width` with an aspect.
+    pub(crate) fn limit_height(&self, bounds: (u32, u32), width: u32) -> (u32, u32) {
+        let bounds = match self.aspect {
+            Some(ratio) if ratio > 0.0 => {
+                let height = ((width as f32 / ratio).round() as u32).max(bounds.0);
+                (height, height)
+            }
+            _ => bounds,
+        };
+        let max = self.max.1.max(self.min.1);
+        (
+            bounds.0.clamp(self.min.1, max),
+            bounds.1.clamp(self.min.1, max),
+        )
     }
 }
 
@@ -95,3 +202,61 @@ impl From<lite_graphics::Size> for Size {
         Self::new(Sizing::Fixed(value.w), Sizing::Fixed(value.h))
     }
 }
+
+/// Font of the unit tests, so they don't depend on the fonts installed.
+#[cfg(test)]
+pub(crate) fn test_font() -> ab_glyph::FontArc {
+    sys::load_font(&include_bytes!("../tests/fonts/DejaVuSans-subset.ttf")[..]).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::widgets::{WidgetInternal, widget::Widget};
+
+    #[test]
+    fn min_over_content() {
+        let size = Size::default().min(50, 40);
+        assert_eq!(size.limit_width((10, 20)), (50, 50));
+        assert_eq!(size.limit_width((10, 80)), (50, 80));
+        assert_eq!(size.limit_height((5, 8), 50), (40, 40));
+    }
+
+    #[test]
+    fn max_under_content() {
+        let size = Size::default().max(30, 20);
+        assert_eq!(size.limit_width((40, 60)), (30, 30));
+        assert_eq!(size.limit_width((10, 60)), (10, 30));
+        assert_eq!(size.limit_height((25, 50), 30), (20, 20));
+    }
+
+    #[test]
+    fn min_wins_over_max() {
+        let size = Size::default().min(50, 40).max(30, 20);
+        assert_eq!(size.limit_width((10, 60)), (50, 50));
+        assert_eq!(size.limit_height((10, 60), 50), (40, 40));
+    }
+
+    #[test]
+    fn aspect_follows_width() {
+        // Only the width is given, the height comes from it.
+        let size = Size::new(Sizing::Fixed(200), Sizing::Fill).aspect(2.0);
+        assert_eq!(size.limit_width((200, 200)), (200, 200));
+        assert_eq!(size.limit_height((0, 0), 200), (100, 100));
+        assert_eq!(size.limit_height((0, u32::MAX), 300), (150, 150));
+        // Content taller than the ratio allows still fits.
+        assert_eq!(size.limit_height((120, 120), 200), (120, 120));
+        // Limits on the height still apply.
+        assert_eq!(size.min(0, 120).limit_height((0, 0), 200), (120, 120));
+        assert_eq!(size.max(u32::MAX, 80).limit_height((0, 0), 200), (80, 80));
+    }
+
+    #[test]
+    fn aspect_in_widget() {
+        let widget = Widget::new().size(Size::new(Sizing::Stretch(1), Sizing::Fill).aspect(4.0));
+        let stack = vstack(0, (widget.clone(), Widget::new()));
+        stack.set_width(300);
+        assert_eq!(widget.height_bounds(), (75, 75));
+        assert_eq!(stack.height_bounds(), (75, 75));
+    }
+}
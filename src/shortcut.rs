@@ -0,0 +1,159 @@
+//! Keyboard shortcuts.
+//!
+//! A key press first goes to shortcuts of the focused widget and its parents, innermost first, then
+//! to the window's and then to the app's. Key handlers of those widgets see every press and release
+//! before that.
+
+use std::{cell::RefCell, fmt, rc::Rc};
+
+use crate::event::{Key, Modifiers};
+
+type ShortcutFn = Rc<RefCell<dyn FnMut()>>;
+type KeyFn = Rc<RefCell<dyn FnMut(Modifiers, Key)>>;
+
+/// A key combination like `Ctrl+Shift+Z`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Shortcut {
+    /// Never holds the lock modifiers.
+    pub mods: Modifiers,
+    /// Always unshifted, Shift is part of `mods`.
+    pub key: Key,
+}
+
+impl Shortcut {
+    pub fn new(mods: Modifiers, key: Key) -> Self {
+        Self {
+            mods: mods & !(Modifiers::CAPS_LOCK | Modifiers::NUM_LOCK),
+            key: key.unshift(),
+        }
+    }
+    /// Whether a focused text input would take this as text instead.
+    pub(crate) fn is_text(&self) -> bool {
+        self.mods & (Modifiers::CONTROL | Modifiers::ALT | Modifiers::SUPER) == Modifiers::NONE
+            && !self.key.to_string().is_empty()
+    }
+}
+
+impl From<Key> for Shortcut {
+    fn from(key: Key) -> Self {
+        Self::new(Modifiers::NONE, key)
+    }
+}
+
+impl fmt::Display for Shortcut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (mods, name) in [
+            (Modifiers::CONTROL, "Ctrl+"),
+            (Modifiers::ALT, "Alt+"),
+            (Modifiers::SUPER, "Super+"),
+            (Modifiers::SHIFT, "Shift+"),
+        ] {
+            if self.mods & mods != Modifiers::NONE {
+                f.write_str(name)?;
+            }
+        }
+        let text = self.key.to_string();
+        match text.chars().next() {
+            Some(c) if text.len() == c.len_utf8() && c.is_ascii_graphic() => {
+                write!(f, "{}", c.to_ascii_uppercase())
+            }
+            _ => write!(f, "{:?}", self.key),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Entry {
+    shortcut: Shortcut,
+    description: String,
+    f: ShortcutFn,
+}
+
+impl Entry {
+    fn run(&self) {
+        (self.f.borrow_mut())();
+    }
+}
+
+/// Shortcuts of a widget, a window or the whole app.
+#[derive(Default)]
+pub struct Shortcuts {
+    entries: RefCell<Vec<Entry>>,
+}
+
+impl Shortcuts {
+    /// Run `f` on `shortcut`, replacing what was bound to it before.
+    pub fn add<S: Into<Shortcut>>(
+        &self,
+        shortcut: S,
+        description: impl Into<String>,
+        f: impl FnMut() + 'static,
+    ) {
+        let shortcut = shortcut.into();
+        self.remove(shortcut);
+        self.entries.borrow_mut().push(Entry {
+            shortcut,
+            description: description.into(),
+            f: Rc::new(RefCell::new(f)),
+        });
+    }
+    /// Returns `false` if nothing was bound to `shortcut`.
+    pub fn remove<S: Into<Shortcut>>(&self, shortcut: S) -> bool {
+        let shortcut = shortcut.into();
+        let mut entries = self.entries.borrow_mut();
+        let len = entries.len();
+        entries.retain(|e| e.shortcut != shortcut);
+        entries.len() != len
+    }
+    /// Every binding with its description, in the order they were added.
+    pub fn list(&self) -> Vec<(Shortcut, String)> {
+        self.entries
+            .borrow()
+            .iter()
+            .map(|e| (e.shortcut, e.description.clone()))
+            .collect()
+    }
+    /// Return: If a binding ran
+    pub(crate) fn trigger(&self, shortcut: Shortcut) -> bool {
+        // Cloned out, so the callback can change the bindings.
+        let entry = self
+            .entries
+            .borrow()
+            .iter()
+            .find(|e| e.shortcut == shortcut)
+            .cloned();
+        entry.map(|e| e.run()).is_some()
+    }
+}
+
+/// Shortcuts and key handlers of a widget.
+#[derive(Default)]
+pub struct KeyBindings {
+    shortcuts: Shortcuts,
+    key_down: RefCell<Option<KeyFn>>,
+    key_up: RefCell<Option<KeyFn>>,
+}
+
+impl KeyBindings {
+    /// Shortcuts bound with [`WidgetExt::on_shortcut`](crate::WidgetExt::on_shortcut).
+    pub fn shortcuts(&self) -> &Shortcuts {
+        &self.shortcuts
+    }
+    /// Run `f` on presses, or releases, replacing the handler before.
+    pub(crate) fn set_key_handler(&self, pressed: bool, f: impl FnMut(Modifiers, Key) + 'static) {
+        let handler = match pressed {
+            true => &self.key_down,
+            false => &self.key_up,
+        };
+        *handler.borrow_mut() = Some(Rc::new(RefCell::new(f)));
+    }
+    /// Return: If a handler ran
+    pub(crate) fn trigger_key(&self, pressed: bool, mods: Modifiers, key: Key) -> bool {
+        // Cloned out, so the handler can replace itself.
+        let handler = match pressed {
+            true => self.key_down.borrow().clone(),
+            false => self.key_up.borrow().clone(),
+        };
+        handler.map(|f| (f.borrow_mut())(mods, key)).is_some()
+    }
+}
@@ -5,26 +5,52 @@ platform!(
         linux =>
             X11(Rc<super::x11::App>),
             Wayland(Rc<super::wayland::App>);
-        windows => Windows(Rc<super::windows::App>)
+        windows => Windows(Rc<super::windows::App>);
+        any => Headless(Rc<super::headless::App>)
     }
 );
 
 impl App {
     pub(crate) fn new() -> Result<Self, crate::Error> {
+        if super::headless::is_requested() {
+            return Ok(Self::new_headless());
+        }
         platform!(
             linux => if super::wayland::has_wayland() {
                 Ok(Self::Wayland(super::wayland::App::new()?))
-            } else {
+            } else if super::x11::has_x11() {
                 Ok(Self::X11(super::x11::App::new()?))
+            } else {
+                Ok(Self::new_headless())
             };
             windows => Ok(Self::Windows(super::windows::App::new()?))
         );
     }
+    pub(crate) fn new_headless() -> Self {
+        Self::Headless(super::headless::App::new())
+    }
     pub(crate) fn get_events(&self) -> Result<Option<crate::event::RawEvent>, crate::Error> {
         platform!(match self {
             Self::X11(app) if linux => app.get_event(),
             Self::Wayland(app) if linux => app.get_event(),
             Self::Windows(app) if windows => app.get_event(),
+            Self::Headless(app) if any => app.get_event(),
+        })
+    }
+    pub(crate) fn get_clipboard(&self, kind: crate::app::ClipboardKind) -> Option<String> {
+        platform!(match self {
+            Self::X11(app) if linux => app.get_clipboard(kind),
+            Self::Wayland(app) if linux => app.get_clipboard(kind),
+            Self::Windows(app) if windows => app.get_clipboard(kind),
+            Self::Headless(app) if any => app.get_clipboard(kind),
+        })
+    }
+    pub(crate) fn set_clipboard(&self, kind: crate::app::ClipboardKind, text: String) {
+        platform!(match self {
+            Self::X11(app) if linux => app.set_clipboard(kind, text),
+            Self::Wayland(app) if linux => app.set_clipboard(kind, text),
+            Self::Windows(app) if windows => app.set_clipboard(kind, text),
+            Self::Headless(app) if any => app.set_clipboard(kind, text),
         })
     }
 }
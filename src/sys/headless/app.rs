@@ -0,0 +1,60 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, VecDeque},
+    rc::Rc,
+};
+
+use crate::{
+    app::ClipboardKind,
+    event::{Event, RawEvent, WindowEvent},
+};
+
+use super::Window;
+
+/// Offscreen backend. Nothing is shown on screen; events only come from `push_event`.
+pub(crate) struct App {
+    pub(super) windows: RefCell<Vec<Rc<Window>>>,
+    events: RefCell<VecDeque<RawEvent>>,
+    next_id: Cell<u64>,
+    clipboard: RefCell<HashMap<ClipboardKind, String>>,
+}
+
+impl App {
+    pub(crate) fn new() -> Rc<Self> {
+        Rc::new(Self {
+            windows: RefCell::new(vec![]),
+            events: RefCell::new(VecDeque::new()),
+            next_id: Cell::new(1),
+            clipboard: RefCell::new(HashMap::new()),
+        })
+    }
+    /// Returns `None` once the queue is drained, so `App::run` returns instead of blocking.
+    pub(crate) fn get_event(&self) -> crate::Result<Option<RawEvent>> {
+        Ok(self.events.borrow_mut().pop_front())
+    }
+    pub(crate) fn push_event(&self, event: RawEvent) {
+        self.events.borrow_mut().push_back(event);
+    }
+    pub(super) fn next_id(&self) -> u64 {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        id
+    }
+    pub(crate) fn get_clipboard(&self, kind: ClipboardKind) -> Option<String> {
+        self.clipboard.borrow().get(&kind).cloned()
+    }
+    pub(crate) fn set_clipboard(&self, kind: ClipboardKind, text: String) {
+        self.clipboard.borrow_mut().insert(kind, text);
+    }
+    pub(crate) fn destroy_window(&self, window_id: u64) {
+        let mut windows = self.windows.borrow_mut();
+        let len = windows.len();
+        windows.retain(|w| w.id() != window_id);
+        if windows.len() != len {
+            self.push_event(RawEvent {
+                window: window_id,
+                event: Event::Window(WindowEvent::Destroyed),
+            });
+        }
+    }
+}
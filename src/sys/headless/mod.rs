@@ -0,0 +1,12 @@
+mod app;
+mod window;
+
+pub(crate) use app::App;
+pub(crate) use window::Window;
+
+/// Environment variable used to pick a backend explicitly.
+pub(crate) const BACKEND_VAR: &str = "PARSLAY_BACKEND";
+
+pub(crate) fn is_requested() -> bool {
+    std::env::var(BACKEND_VAR).is_ok_and(|b| b.eq_ignore_ascii_case("headless"))
+}
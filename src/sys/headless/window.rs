@@ -0,0 +1,73 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::{Rc, Weak},
+};
+
+use lite_graphics::{Buffer, Size};
+
+use crate::{
+    app::CursorType,
+    event::{Event, RawEvent, WindowEvent, WindowState},
+};
+
+use super::App;
+
+pub(crate) struct Window {
+    app: Weak<App>,
+    id: u64,
+    frame: RefCell<Option<Buffer>>,
+    cursor: Cell<CursorType>,
+    title: RefCell<String>,
+}
+
+impl Window {
+    pub(crate) fn new(app: &Rc<App>, options: &crate::WindowOptions) -> crate::Result<Rc<Self>> {
+        let win = Rc::new(Self {
+            app: Rc::downgrade(app),
+            id: app.next_id(),
+            frame: RefCell::new(None),
+            cursor: Cell::new(CursorType::Arrow),
+            title: RefCell::new(options.title.clone()),
+        });
+        app.windows.borrow_mut().push(win.clone());
+
+        // There is no compositor to activate us, so do it right away to get a first frame.
+        app.push_event(RawEvent {
+            window: win.id,
+            event: Event::Window(WindowEvent::StateChange(WindowState::Activated)),
+        });
+        Ok(win)
+    }
+    pub(crate) fn draw(&self, buf: Buffer) -> crate::Result<()> {
+        *self.frame.borrow_mut() = Some(buf);
+        Ok(())
+    }
+    /// Last buffer passed to `draw`.
+    pub(crate) fn frame(&self) -> Option<Buffer> {
+        self.frame.borrow().clone()
+    }
+    pub(crate) fn id(&self) -> u64 {
+        self.id
+    }
+    pub(crate) fn set_cursor(&self, cursor: CursorType) {
+        self.cursor.set(cursor);
+    }
+    pub(crate) fn set_title(&self, title: &str) -> crate::Result<()> {
+        *self.title.borrow_mut() = title.to_owned();
+        Ok(())
+    }
+    /// Nothing can refuse, so resize right away.
+    pub(crate) fn set_size(&self, size: Size) -> crate::Result<()> {
+        self.push_event(Event::Window(WindowEvent::Resize(size.w, size.h)));
+        Ok(())
+    }
+    pub(crate) fn push_event(&self, event: Event) {
+        self.app.upgrade().unwrap().push_event(RawEvent {
+            window: self.id,
+            event,
+        });
+    }
+    pub(crate) fn destroy(&self) {
+        self.app.upgrade().unwrap().destroy_window(self.id);
+    }
+}
@@ -0,0 +1,66 @@
+use std::{env, ffi::OsString};
+
+use xkbcommon::xkb::{self, compose};
+
+/// Turns sequences like dead keys or `Compose` `e` `'` into the text they type, from the compose
+/// table of the locale.
+pub(crate) struct Compose {
+    /// `None` if the locale has no compose table, keys then type their own text.
+    state: Option<compose::State>,
+}
+
+impl Compose {
+    pub(crate) fn new() -> Self {
+        let context = xkb::Context::new(xkb::CONTEXT_NO_FLAGS);
+        let state = compose::Table::new_from_locale(&context, &locale(), compose::COMPILE_NO_FLAGS)
+            .ok()
+            .map(|table| compose::State::new(&table, compose::STATE_NO_FLAGS));
+        Self { state }
+    }
+
+    /// Feed a pressed keysym with the text it types on its own. Returns the text to insert.
+    pub(crate) fn feed(&mut self, sym: u32, text: Option<String>) -> Option<String> {
+        let sym = xkb::Keysym::new(sym);
+        if sym.is_modifier_key() {
+            return None;
+        }
+        let text = text.filter(|t| !t.is_empty() && !t.chars().any(char::is_control));
+        let Some(state) = &mut self.state else {
+            return text;
+        };
+        if let compose::FeedResult::Ignored = state.feed(sym) {
+            return text;
+        }
+        match state.status() {
+            compose::Status::Nothing => text,
+            compose::Status::Composing => None,
+            compose::Status::Composed => {
+                let text = state
+                    .utf8()
+                    .filter(|t| !t.is_empty())
+                    .or_else(|| state.keysym().and_then(|sym| keysym_to_text(sym.raw())));
+                state.reset();
+                text
+            }
+            // The key that broke the sequence is dropped with it.
+            compose::Status::Cancelled => {
+                state.reset();
+                None
+            }
+        }
+    }
+}
+
+/// Locale whose compose table applies, like `setlocale(LC_CTYPE, "")` would pick.
+fn locale() -> OsString {
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .into_iter()
+        .filter_map(env::var_os)
+        .find(|locale| !locale.is_empty())
+        .unwrap_or_else(|| "C".into())
+}
+
+/// Text typed by `sym` on its own, without any keyboard state.
+pub(crate) fn keysym_to_text(sym: u32) -> Option<String> {
+    xkeysym::Keysym::new(sym).key_char().map(String::from)
+}
@@ -1,34 +1,40 @@
 use std::{
     fs,
-    io::{self, Read},
+    io::{self, ErrorKind},
     process::Command,
 };
 
 use crate::event::Key;
 
-pub(crate) fn get_font(name: Option<String>) -> crate::Result<(fs::File, u8)> {
+mod compose;
+
+pub(crate) use compose::{Compose, keysym_to_text};
+
+/// Font name and size from the desktop settings, if there are any.
+fn desktop_font() -> Option<(String, u8)> {
     let default_font_vec = Command::new("gsettings")
         .args(["get", "org.gnome.desktop.interface", "font-name"])
-        .output()?
+        .output()
+        .ok()?
         .stdout;
     let mut default_font = String::from_utf8_lossy(&default_font_vec).into_owned();
     default_font = default_font.trim().trim_matches('\'').to_owned();
-    let split = default_font.rfind(' ').unwrap();
-    let size = default_font.split_off(split).trim().parse::<u8>().unwrap();
-    let path = fontconfig::Fontconfig::new()
-        .unwrap()
-        .find(&name.unwrap_or(default_font), Some("Regular"))
-        .unwrap()
-        .path;
-    fs::File::open(path).map_err(Into::into).map(|f| (f, size))
+    let split = default_font.rfind(' ')?;
+    let size = default_font.split_off(split).trim().parse::<u8>().ok()?;
+    Some((default_font, size))
 }
 
-pub(crate) fn get_default_font() -> crate::Result<ab_glyph::FontArc> {
-    let mut font = get_font(None)?.0;
-    let mut buf = vec![];
-    font.read_to_end(&mut buf)?;
-    ab_glyph::FontArc::try_from_vec(buf)
-        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Invalid Font Used").into())
+pub(crate) fn get_font(name: Option<String>) -> crate::Result<(fs::File, u8)> {
+    // Headless machines usually have no desktop settings; let fontconfig pick then.
+    let (default_font, size) = desktop_font().unwrap_or(("sans-serif".to_owned(), 12));
+    let name = name.unwrap_or(default_font);
+    let fontconfig = fontconfig::Fontconfig::new()
+        .ok_or_else(|| io::Error::other("Fontconfig couldn't be initialized"))?;
+    let path = fontconfig
+        .find(&name, Some("Regular"))
+        .ok_or_else(|| io::Error::new(ErrorKind::NotFound, format!("No font named {name}")))?
+        .path;
+    fs::File::open(path).map_err(Into::into).map(|f| (f, size))
 }
 
 pub(crate) fn key_from_xkb(sym: u32) -> Key {
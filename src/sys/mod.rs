@@ -27,7 +27,7 @@ macro_rules! platform {
         return $lline;
         #[cfg(target_os = "windows")] return $wline;
     };
-    ($vis:vis enum $name:ident {linux => $($lline:ident $lblock:tt),*; windows => $wline:ident $wblock:tt $(,)? }) => {
+    ($vis:vis enum $name:ident {linux => $($lline:ident $lblock:tt),*; windows => $wline:ident $wblock:tt $(; any => $($aline:ident $ablock:tt),*)? $(,)? }) => {
         $vis enum $name {
             $(#[cfg(all(
                 unix,
@@ -41,8 +41,8 @@ macro_rules! platform {
             ))]
             $lline $lblock,)*
             #[cfg(target_os = "windows")]
-            $wline $wblock
-
+            $wline $wblock,
+            $($($aline $ablock,)*)?
         }
     };
     (match $name:ident { $($pat:pat if $platform:ident => $block:expr,)* }) => {
@@ -50,7 +50,7 @@ macro_rules! platform {
             $($platform: $pat=> $block,)*
         })
     };
-    (@match $name:ident { $(linux: $lpat:pat => $lblock:expr,)* windows: $wpat:pat => $wblock:expr $(,)? }) => {
+    (@match $name:ident { $(linux: $lpat:pat => $lblock:expr,)* windows: $wpat:pat => $wblock:expr, $(any: $apat:pat => $ablock:expr,)* }) => {
         match $name {
             $(#[cfg(all(
                 unix,
@@ -64,7 +64,8 @@ macro_rules! platform {
             ))]
             $lpat => $lblock,)*
             #[cfg(target_os = "windows")]
-            $wpat => $wblock
+            $wpat => $wblock,
+            $($apat => $ablock,)*
         }
     }
 }
@@ -78,12 +79,32 @@ platform!(
 
 );
 
+mod headless;
+
 pub(crate) mod app;
 pub(crate) mod window;
 
 pub(crate) fn get_default_font() -> crate::Result<ab_glyph::FontArc> {
+    load_font(get_font(None)?.0)
+}
+
+fn get_font(name: Option<String>) -> crate::Result<(std::fs::File, u8)> {
     platform!(
-        linux => linux::get_default_font();
-        windows => windows::get_default_font();
+        linux => linux::get_font(name);
+        windows => windows::get_font(name);
     );
 }
+
+/// Load an installed font by family name.
+pub(crate) fn get_named_font(name: &str) -> crate::Result<ab_glyph::FontArc> {
+    load_font(get_font(Some(name.to_owned()))?.0)
+}
+
+/// Parse a font from a file or from memory.
+pub(crate) fn load_font(mut src: impl std::io::Read) -> crate::Result<ab_glyph::FontArc> {
+    let mut buf = vec![];
+    src.read_to_end(&mut buf)?;
+    ab_glyph::FontArc::try_from_vec(buf).map_err(|_| {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, "Invalid Font Used").into()
+    })
+}
@@ -2,12 +2,18 @@ use std::{
     cell::RefCell,
     collections::{HashMap, VecDeque},
     ffi::CStr,
+    io,
     num::NonZero,
     rc::Rc,
+    time::{Duration, Instant},
 };
 
 use lite_graphics::{Offset, Size};
-use nix::sys::mman::{MapFlags, ProtFlags, mmap};
+use nix::{
+    errno::Errno,
+    poll::{PollFd, PollFlags, PollTimeout, poll},
+    sys::mman::{MapFlags, ProtFlags, mmap},
+};
 use wayland_client::{
     Dispatch, Proxy, WEnum, delegate_noop,
     protocol::{
@@ -21,7 +27,7 @@ use xkbcommon_rs::xkb_state::StateComponent;
 use crate::{
     app::CursorType,
     event::{Button, Event, Modifiers, RawEvent, WidgetEvent, WindowEvent, WindowState},
-    sys::{linux, wayland::window::TITLEBAR_HEIGHT},
+    sys::linux,
 };
 
 use super::Window;
@@ -38,10 +44,110 @@ pub(super) struct State {
     pub(super) running: bool,
     pub(super) events: VecDeque<crate::event::RawEvent>,
     pub(super) keymap_state: Option<xkbcommon_rs::State>,
+    compose: linux::Compose,
     pub(super) mouse_event: RawEvent,
+    /// Window with keyboard focus, 0 if none.
+    keyboard_window: u64,
+    repeat: KeyRepeat,
     pub(super) buttons_held: [bool; 6],
     is_framed_pointer: bool,
+    scroll: FrameScroll,
     pub(super) last_move: Offset,
+    pub(super) clipboard: super::clipboard::Clipboard,
+    pub(super) ime: super::ime::Ime,
+}
+
+/// Key repeat is up to us, at the rate the compositor asks for.
+struct KeyRepeat {
+    /// Repeats per second, 0 to never repeat.
+    rate: u32,
+    delay: Duration,
+    /// Key held down, and when it next repeats.
+    held: Option<(u32, Instant)>,
+}
+
+impl Default for KeyRepeat {
+    fn default() -> Self {
+        Self {
+            rate: 25,
+            delay: Duration::from_millis(600),
+            held: None,
+        }
+    }
+}
+
+/// Scroll of the current pointer frame, by axis, vertical first.
+#[derive(Default)]
+struct FrameScroll {
+    active: bool,
+    /// In surface pixels.
+    pixels: [f64; 2],
+    /// In wheel notches, when the compositor counts them.
+    notches: [Option<f64>; 2],
+    wheel: bool,
+}
+
+/// What a wheel notch scrolls in pixels, for compositors not counting notches.
+const PIXELS_PER_NOTCH: f64 = 10.0;
+
+impl State {
+    /// Queue the scroll of the current pointer frame, if any.
+    fn push_scroll(&mut self) {
+        let scroll = std::mem::take(&mut self.scroll);
+        if !scroll.active || self.mouse_event.window == 0 {
+            return;
+        }
+        let [dy, dx] =
+            [0, 1].map(|i| scroll.notches[i].unwrap_or(scroll.pixels[i] / PIXELS_PER_NOTCH) as f32);
+        let titlebar_height = self.windows[&self.mouse_event.window].titlebar_height() as i32;
+        self.events.push_back(RawEvent {
+            window: self.mouse_event.window,
+            event: Event::Widget(WidgetEvent::Scroll {
+                x: self.last_move.x,
+                y: self.last_move.y - titlebar_height,
+                dx,
+                dy,
+                discrete: scroll.wheel || scroll.notches.iter().any(Option::is_some),
+            }),
+        });
+    }
+    /// Queue the events of a key press or release, with the text it types.
+    fn push_key(&mut self, key_code: u32, pressed: bool) {
+        let keymap = self.keymap_state.as_ref().unwrap();
+        let mods = ["Shift", "Control", "Mod1", "Mod4", "Lock", "Mod2"]
+            .iter()
+            .enumerate()
+            .map(|(idx, name)| {
+                let active = keymap.mod_name_is_active(name, StateComponent::MODS_EFFECTIVE);
+                (active.unwrap() as u8) << idx
+            })
+            .fold(Default::default(), |st, id| st | Modifiers(id));
+        let sym = keymap
+            .key_get_one_sym(8 + key_code) // evdev code, but xkb codes are 8 over.
+            .unwrap();
+        let key = linux::key_from_xkb(sym.raw());
+        let text = keymap
+            .key_get_utf8(8 + key_code)
+            .and_then(|t| String::from_utf8(t).ok());
+        let win_evt = if pressed {
+            WindowEvent::KeyPress(mods, key)
+        } else {
+            WindowEvent::KeyRelease(mods, key)
+        };
+        self.events.push_back(RawEvent {
+            window: self.keyboard_window,
+            event: Event::Window(win_evt),
+        });
+        if pressed
+            && mods & (Modifiers::CONTROL | Modifiers::ALT | Modifiers::SUPER) == Modifiers::NONE
+            && let Some(text) = self.compose.feed(sym.raw(), text)
+        {
+            self.events.push_back(RawEvent {
+                window: self.keyboard_window,
+                event: Event::Window(WindowEvent::Text(text)),
+            });
+        }
+    }
 }
 
 delegate_noop!(State: ignore wl_compositor::WlCompositor);
@@ -57,7 +163,9 @@ impl Dispatch<wl_registry::WlRegistry, ()> for State {
         qh: &wayland_client::QueueHandle<Self>,
     ) {
         if let wl_registry::Event::Global {
-            name, interface, ..
+            name,
+            interface,
+            version,
         } = event
         {
             match &interface[..] {
@@ -91,9 +199,19 @@ impl Dispatch<wl_registry::WlRegistry, ()> for State {
                     }
                 }
                 "wl_seat" => {
-                    let seat = registry.bind::<wl_seat::WlSeat, _, _>(name, 1, qh, ());
+                    // Version 4 for the keyboard's repeat info, 8 for high resolution scrolling.
+                    let seat = registry.bind::<wl_seat::WlSeat, _, _>(name, version.min(8), qh, ());
                     state.seat = Some(seat);
                 }
+                "wl_data_device_manager" => {
+                    state.clipboard.manager = Some(registry.bind(name, 3, qh, ()));
+                }
+                "zwp_primary_selection_device_manager_v1" => {
+                    state.clipboard.primary_manager = Some(registry.bind(name, 1, qh, ()));
+                }
+                "zwp_text_input_manager_v3" => {
+                    state.ime.manager = Some(registry.bind(name, 1, qh, ()));
+                }
                 "xdg_wm_base" => {
                     let wm_base = registry.bind::<xdg_wm_base::XdgWmBase, _, _>(name, 1, qh, ());
                     state.wm_base = Some(wm_base);
@@ -142,34 +260,44 @@ impl Dispatch<wl_keyboard::WlKeyboard, ()> for State {
         _: &wayland_client::QueueHandle<Self>,
     ) {
         match event {
-            wl_keyboard::Event::Key { key, state, .. } => {
-                let keymap = this.keymap_state.as_ref().unwrap();
-                let mods = ["Shift", "Control", "Mod1", "Mod4"]
-                    .iter()
-                    .enumerate()
-                    .map(|(idx, name)| {
-                        let active =
-                            keymap.mod_name_is_active(name, StateComponent::MODS_EFFECTIVE);
-                        (active.unwrap() as u8) << idx
-                    })
-                    .fold(Default::default(), |st, id| st | Modifiers(id));
-                let sym = keymap
-                    .key_get_one_sym(8 + key) // `key` is evdev code, but xkb codes are 8 over.
-                    .unwrap();
-                let key = linux::key_from_xkb(sym.raw());
-                let win_evt = match state {
-                    WEnum::Value(wl_keyboard::KeyState::Pressed) => {
-                        WindowEvent::KeyPress(mods, key)
-                    }
-                    WEnum::Value(wl_keyboard::KeyState::Released) => {
-                        WindowEvent::KeyRelease(mods, key)
-                    }
+            wl_keyboard::Event::Key {
+                key: key_code,
+                state,
+                serial,
+                ..
+            } => {
+                this.clipboard.serial = serial;
+                let pressed = match state {
+                    WEnum::Value(wl_keyboard::KeyState::Pressed) => true,
+                    WEnum::Value(wl_keyboard::KeyState::Released) => false,
                     _ => return,
                 };
-                this.events.push_back(RawEvent {
-                    window: 0,
-                    event: Event::Window(win_evt),
-                });
+                this.push_key(key_code, pressed);
+                let keymap = this.keymap_state.as_ref().unwrap().get_keymap();
+                if pressed && this.repeat.rate > 0 && keymap.key_repeats(8 + key_code) {
+                    this.repeat.held = Some((key_code, Instant::now() + this.repeat.delay));
+                } else if !pressed && this.repeat.held.is_some_and(|(held, _)| held == key_code) {
+                    this.repeat.held = None;
+                }
+            }
+            wl_keyboard::Event::RepeatInfo { rate, delay } => {
+                this.repeat.rate = rate.max(0) as u32;
+                this.repeat.delay = Duration::from_millis(delay.max(0) as u64);
+                this.repeat.held = None;
+            }
+            wl_keyboard::Event::Enter {
+                surface, serial, ..
+            } => {
+                this.clipboard.serial = serial;
+                this.keyboard_window = this
+                    .windows
+                    .values()
+                    .find(|w| *w.base_surface.get().unwrap() == surface)
+                    .map_or(0, |w| w.id());
+            }
+            wl_keyboard::Event::Leave { .. } => {
+                this.keyboard_window = 0;
+                this.repeat.held = None;
             }
             wl_keyboard::Event::Modifiers {
                 mods_depressed,
@@ -244,7 +372,7 @@ impl Dispatch<wl_pointer::WlPointer, ()> for State {
             let top_rsz = (surface_y) < 5;
             let left_rsz = (surface_x) < 5;
             let right_rsz = (surface_x) > size.w - 5;
-            let bottom_rsz = (surface_y) > size.h + super::window::TITLEBAR_HEIGHT as u32 - 5;
+            let bottom_rsz = (surface_y) > size.h + window.titlebar_height() as u32 - 5;
             let ns = if top_rsz {
                 "n"
             } else if bottom_rsz {
@@ -259,7 +387,7 @@ impl Dispatch<wl_pointer::WlPointer, ()> for State {
             } else {
                 ""
             };
-            let ty = match (ns, we) {
+            let ty = match if window.resizable { (ns, we) } else { ("", "") } {
                 ("n", "") => CursorType::NResize,
                 ("s", "") => CursorType::SResize,
                 ("", "e") => CursorType::EResize,
@@ -285,8 +413,8 @@ impl Dispatch<wl_pointer::WlPointer, ()> for State {
         ) {
             check_cursor(this, surface_x, surface_y, pointer);
 
-            if this.last_move.y < super::window::TITLEBAR_HEIGHT as i32 {
-                let window = this.windows.get(&this.mouse_event.window).unwrap();
+            let window = this.windows.get(&this.mouse_event.window).unwrap();
+            if this.last_move.y < window.titlebar_height() as i32 {
                 window.titlebar(Offset::new(surface_x as _, surface_y as _), false);
                 window.draw(None).unwrap();
             }
@@ -320,7 +448,7 @@ impl Dispatch<wl_pointer::WlPointer, ()> for State {
                     },
                 );
             }
-            if this.last_move.y < super::window::TITLEBAR_HEIGHT as i32 {
+            if this.last_move.y < window.titlebar_height() as i32 {
                 window.titlebar(this.last_move, true);
                 window.draw(None).unwrap();
                 if this.last_move.x < window.size.borrow().w as i32 - 92 {
@@ -344,16 +472,14 @@ impl Dispatch<wl_pointer::WlPointer, ()> for State {
                 let hot = cursor.set_cursor(CursorType::Arrow).unwrap();
                 pointer.set_cursor(cursor.last_serial, Some(&cursor.surface), hot.x, hot.y);
             }
-            if this.last_move.y < super::window::TITLEBAR_HEIGHT as i32 - 4 && this.last_move.y > 4
-            {
+            if this.last_move.y < window.titlebar_height() as i32 - 4 && this.last_move.y > 4 {
                 let pos = this.last_move;
                 let width = window.size.borrow().w;
                 if pos.x < width as i32 - 4 && pos.x > width as i32 - 28 {
-                    window.base_surface.get().unwrap().destroy();
-                    this.windows.remove(&this.mouse_event.window).unwrap();
-                    if this.windows.is_empty() {
-                        this.running = false;
-                    }
+                    this.events.push_back(RawEvent {
+                        window: this.mouse_event.window,
+                        event: Event::Window(WindowEvent::Closing),
+                    });
                 } else if pos.x < width as i32 - 36 && pos.x > width as i32 - 60 {
                     window.xdg_surface.get().unwrap().1.set_minimized();
                 } else if pos.x < width as i32 - 68 && pos.x > width as i32 - 92 {
@@ -391,12 +517,13 @@ impl Dispatch<wl_pointer::WlPointer, ()> for State {
                 ..
             } => {
                 handle_motion(this, surface_x as u32, surface_y as u32, pointer);
+                let titlebar_height = this.windows[&this.mouse_event.window].titlebar_height();
                 this.mouse_event.event = Event::Widget(WidgetEvent::Move(
                     surface_x as i32,
-                    surface_y as i32 - TITLEBAR_HEIGHT as i32,
+                    surface_y as i32 - titlebar_height as i32,
                 ));
                 if !this.is_framed_pointer {
-                    this.events.push_back(this.mouse_event);
+                    this.events.push_back(this.mouse_event.clone());
                 }
             }
             wl_pointer::Event::Button {
@@ -405,6 +532,9 @@ impl Dispatch<wl_pointer::WlPointer, ()> for State {
                 serial,
                 ..
             } => {
+                let titlebar_height =
+                    this.windows[&this.mouse_event.window].titlebar_height() as i32;
+                this.clipboard.serial = serial;
                 match state {
                     WEnum::Value(wl_pointer::ButtonState::Pressed) => {
                         this.buttons_held[button_from_ev(button) as usize] = true;
@@ -412,7 +542,7 @@ impl Dispatch<wl_pointer::WlPointer, ()> for State {
                         this.mouse_event.event = Event::Widget(WidgetEvent::ButtonPress(
                             button_from_ev(button),
                             this.last_move.x,
-                            this.last_move.y - super::window::TITLEBAR_HEIGHT as i32,
+                            this.last_move.y - titlebar_height,
                         ))
                     }
                     WEnum::Value(wl_pointer::ButtonState::Released) => {
@@ -421,22 +551,51 @@ impl Dispatch<wl_pointer::WlPointer, ()> for State {
                         this.mouse_event.event = Event::Widget(WidgetEvent::ButtonRelease(
                             button_from_ev(button),
                             this.last_move.x,
-                            this.last_move.y - super::window::TITLEBAR_HEIGHT as i32,
+                            this.last_move.y - titlebar_height,
                         ))
                     }
                     _ => {}
                 }
                 if !this.is_framed_pointer {
-                    this.events.push_back(this.mouse_event);
+                    this.events.push_back(this.mouse_event.clone());
                 }
             }
+            wl_pointer::Event::Axis {
+                axis: WEnum::Value(axis),
+                value,
+                ..
+            } => {
+                this.scroll.active = true;
+                this.scroll.pixels[axis as usize] += value;
+                if !this.is_framed_pointer {
+                    this.push_scroll();
+                }
+            }
+            wl_pointer::Event::AxisSource {
+                axis_source: WEnum::Value(source),
+            } => {
+                this.scroll.wheel = matches!(
+                    source,
+                    wl_pointer::AxisSource::Wheel | wl_pointer::AxisSource::WheelTilt
+                );
+            }
+            wl_pointer::Event::AxisDiscrete {
+                axis: WEnum::Value(axis),
+                discrete,
+            } => {
+                *this.scroll.notches[axis as usize].get_or_insert(0.0) += discrete as f64;
+            }
+            wl_pointer::Event::AxisValue120 {
+                axis: WEnum::Value(axis),
+                value120,
+            } => {
+                *this.scroll.notches[axis as usize].get_or_insert(0.0) += value120 as f64 / 120.0;
+            }
             wl_pointer::Event::Frame => {
                 if this.is_framed_pointer {
-                    this.events.push_back(this.mouse_event);
-                    this.mouse_event = RawEvent {
-                        window: 0,
-                        event: Event::Unknown,
-                    }
+                    this.events.push_back(this.mouse_event.clone());
+                    this.mouse_event.event = Event::Unknown;
+                    this.push_scroll();
                 }
             }
             _ => {}
@@ -487,7 +646,7 @@ impl Dispatch<wl_callback::WlCallback, u64> for State {
             let buffer = win.shm.borrow().as_ref().unwrap().0.create_buffer(
                 0,
                 size.w as i32,
-                size.h as i32 + super::window::TITLEBAR_HEIGHT as i32,
+                size.h as i32 + win.titlebar_height() as i32,
                 size.w as i32 * 4,
                 wl_shm::Format::Argb8888,
                 qh,
@@ -578,7 +737,7 @@ impl Dispatch<xdg_toplevel::XdgToplevel, u64> for State {
                             .unwrap();
                         let event = Event::Window(WindowEvent::Resize(
                             width as _,
-                            height as u32 - TITLEBAR_HEIGHT as u32,
+                            height as u32 - win.titlebar_height() as u32,
                         ));
                         if let Event::Window(WindowEvent::Resize(_, _)) = this
                             .events
@@ -616,7 +775,10 @@ impl Dispatch<xdg_toplevel::XdgToplevel, u64> for State {
                 });
             }
             xdg_toplevel::Event::Close => {
-                this.windows.remove(win_id);
+                this.events.push_back(RawEvent {
+                    window: *win_id,
+                    event: Event::Window(WindowEvent::Closing),
+                });
             }
             _ => {}
         }
@@ -646,20 +808,30 @@ impl App {
             wm_base: None,
             events: VecDeque::new(),
             keymap_state: None,
+            compose: linux::Compose::new(),
             mouse_event: RawEvent {
                 window: 0,
                 event: Event::Unknown,
             },
+            keyboard_window: 0,
+            repeat: KeyRepeat::default(),
             pointer: None,
             keyboard: None,
             seat: None,
             buttons_held: [false; 6],
             is_framed_pointer: true,
+            scroll: FrameScroll::default(),
             last_move: Offset::default(),
             cursor: None,
+            clipboard: Default::default(),
+            ime: Default::default(),
         };
 
         event_queue.roundtrip(&mut state)?;
+        if let Some(seat) = state.seat.clone() {
+            state.clipboard.init(&seat, &qh);
+            state.ime.init(&seat, &qh);
+        }
 
         Ok(Rc::new(Self {
             state: RefCell::new(state),
@@ -672,9 +844,21 @@ impl App {
         let mut state = self.state.borrow_mut();
         if state.running {
             if state.events.is_empty() {
-                self.event_queue
-                    .borrow_mut()
-                    .blocking_dispatch(&mut state)?;
+                match state.repeat.held {
+                    Some((key_code, at)) => {
+                        self.dispatch_until(&mut state, at)?;
+                        if state.events.is_empty() && Instant::now() >= at {
+                            state.push_key(key_code, true);
+                            let next = Instant::now() + Duration::from_secs(1) / state.repeat.rate;
+                            state.repeat.held = Some((key_code, next));
+                        }
+                    }
+                    None => {
+                        self.event_queue
+                            .borrow_mut()
+                            .blocking_dispatch(&mut state)?;
+                    }
+                }
             }
             self.event_queue.borrow().flush()?;
             Ok(Some(state.events.pop_front().unwrap_or(RawEvent {
@@ -685,12 +869,35 @@ impl App {
             Ok(None)
         }
     }
+    /// Like `blocking_dispatch`, but gives up at `deadline`.
+    fn dispatch_until(&self, state: &mut State, deadline: Instant) -> crate::Result<()> {
+        let mut queue = self.event_queue.borrow_mut();
+        queue.flush()?;
+        if queue.dispatch_pending(state)? > 0 {
+            return Ok(());
+        }
+        if let Some(guard) = queue.prepare_read() {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            let timeout = PollTimeout::try_from(timeout).unwrap_or(PollTimeout::MAX);
+            let mut fds = [PollFd::new(guard.connection_fd(), PollFlags::POLLIN)];
+            match poll(&mut fds, timeout) {
+                Ok(0) | Err(Errno::EINTR) => {}
+                Ok(_) => {
+                    guard.read()?;
+                }
+                Err(e) => return Err(io::Error::from(e).into()),
+            }
+        }
+        queue.dispatch_pending(state)?;
+        Ok(())
+    }
     pub(crate) fn destroy_window(&self, window_id: u64) {
         let mut state = self.state.borrow_mut();
 
         let window = state.windows.get(&window_id).unwrap();
         window.base_surface.get().unwrap().destroy();
         state.windows.remove(&window_id).unwrap();
+        state.ime.forget(window_id);
         if state.windows.is_empty() {
             state.running = false;
         }
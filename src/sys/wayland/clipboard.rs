@@ -0,0 +1,303 @@
+use std::{
+    fs::File,
+    io::{ErrorKind, Read, Write},
+    os::fd::AsFd,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use nix::{
+    errno::Errno,
+    poll::{PollFd, PollFlags, PollTimeout, poll},
+};
+
+use wayland_client::{
+    Dispatch, Proxy, delegate_noop, event_created_child,
+    protocol::{wl_data_device, wl_data_device_manager, wl_data_offer, wl_data_source, wl_seat},
+};
+use wayland_protocols::wp::primary_selection::zv1::client::{
+    zwp_primary_selection_device_manager_v1 as primary_manager,
+    zwp_primary_selection_device_v1 as primary_device,
+    zwp_primary_selection_offer_v1 as primary_offer,
+    zwp_primary_selection_source_v1 as primary_source,
+};
+
+use crate::app::ClipboardKind;
+
+use super::app::{App, State};
+
+/// Text types, preferred first.
+const MIMES: [&str; 4] = [
+    "text/plain;charset=utf-8",
+    "text/plain",
+    "UTF8_STRING",
+    "STRING",
+];
+
+/// How long to wait for each chunk from the selection owner.
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+/// MIME types announced by an offer.
+type Mimes = Mutex<Vec<String>>;
+
+#[derive(Default)]
+pub(super) struct Clipboard {
+    pub(super) manager: Option<wl_data_device_manager::WlDataDeviceManager>,
+    pub(super) primary_manager: Option<primary_manager::ZwpPrimarySelectionDeviceManagerV1>,
+    device: Option<wl_data_device::WlDataDevice>,
+    primary_device: Option<primary_device::ZwpPrimarySelectionDeviceV1>,
+    offer: Option<wl_data_offer::WlDataOffer>,
+    primary_offer: Option<primary_offer::ZwpPrimarySelectionOfferV1>,
+    /// Our own selections, with their text.
+    source: Option<(wl_data_source::WlDataSource, String)>,
+    primary_source: Option<(primary_source::ZwpPrimarySelectionSourceV1, String)>,
+    /// Serial of the last input event, needed to take a selection.
+    pub(super) serial: u32,
+}
+
+impl Clipboard {
+    /// Needs the managers and the seat to be bound.
+    pub(super) fn init(&mut self, seat: &wl_seat::WlSeat, qh: &wayland_client::QueueHandle<State>) {
+        if let Some(manager) = &self.manager {
+            self.device = Some(manager.get_data_device(seat, qh, ()));
+        }
+        if let Some(manager) = &self.primary_manager {
+            self.primary_device = Some(manager.get_device(seat, qh, ()));
+        }
+    }
+}
+
+fn pick_mime(mimes: Option<&Mimes>) -> Option<&'static str> {
+    let mimes = mimes?.lock().ok()?;
+    MIMES.into_iter().find(|m| mimes.iter().any(|o| o == m))
+}
+
+fn send(text: &str, fd: impl Into<File>) {
+    let _ = fd.into().write_all(text.as_bytes());
+}
+
+impl App {
+    pub(crate) fn set_clipboard(&self, kind: ClipboardKind, text: String) {
+        let mut state = self.state.borrow_mut();
+        let clipboard = &mut state.clipboard;
+        match kind {
+            ClipboardKind::Clipboard => {
+                let (Some(manager), Some(device)) = (&clipboard.manager, &clipboard.device) else {
+                    return;
+                };
+                let source = manager.create_data_source(&self.qh, ());
+                for mime in MIMES {
+                    source.offer(mime.to_owned());
+                }
+                device.set_selection(Some(&source), clipboard.serial);
+                if let Some((old, _)) = clipboard.source.replace((source, text)) {
+                    old.destroy();
+                }
+            }
+            ClipboardKind::Primary => {
+                let (Some(manager), Some(device)) =
+                    (&clipboard.primary_manager, &clipboard.primary_device)
+                else {
+                    return;
+                };
+                let source = manager.create_source(&self.qh, ());
+                for mime in MIMES {
+                    source.offer(mime.to_owned());
+                }
+                device.set_selection(Some(&source), clipboard.serial);
+                if let Some((old, _)) = clipboard.primary_source.replace((source, text)) {
+                    old.destroy();
+                }
+            }
+        }
+        drop(state);
+        let _ = self.conn.flush();
+    }
+    pub(crate) fn get_clipboard(&self, kind: ClipboardKind) -> Option<String> {
+        let (read, write) = nix::unistd::pipe().ok()?;
+        {
+            let state = self.state.borrow();
+            let clipboard = &state.clipboard;
+            match kind {
+                ClipboardKind::Clipboard => {
+                    if let Some((_, text)) = &clipboard.source {
+                        return Some(text.clone());
+                    }
+                    let offer = clipboard.offer.as_ref()?;
+                    let mime = pick_mime(offer.data::<Mimes>())?;
+                    offer.receive(mime.to_owned(), write.as_fd());
+                }
+                ClipboardKind::Primary => {
+                    if let Some((_, text)) = &clipboard.primary_source {
+                        return Some(text.clone());
+                    }
+                    let offer = clipboard.primary_offer.as_ref()?;
+                    let mime = pick_mime(offer.data::<Mimes>())?;
+                    offer.receive(mime.to_owned(), write.as_fd());
+                }
+            }
+        }
+        // Only the other client may hold the write end, or we'd never see the end of the data.
+        drop(write);
+        self.conn.flush().ok()?;
+
+        let mut file = File::from(read);
+        let mut data = vec![];
+        let mut buf = [0; 4096];
+        loop {
+            let deadline = Instant::now() + TIMEOUT;
+            loop {
+                let timeout = deadline.checked_duration_since(Instant::now())?;
+                let timeout = PollTimeout::try_from(timeout).unwrap_or(PollTimeout::MAX);
+                let mut fds = [PollFd::new(file.as_fd(), PollFlags::POLLIN)];
+                match poll(&mut fds, timeout) {
+                    Ok(0) | Err(Errno::EINTR) => {}
+                    Ok(_) => break,
+                    Err(_) => return None,
+                }
+            }
+            match file.read(&mut buf) {
+                Ok(0) => return Some(String::from_utf8_lossy(&data).into_owned()),
+                Ok(n) => data.extend_from_slice(&buf[..n]),
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(_) => return None,
+            }
+        }
+    }
+}
+
+delegate_noop!(State: ignore wl_data_device_manager::WlDataDeviceManager);
+delegate_noop!(State: ignore primary_manager::ZwpPrimarySelectionDeviceManagerV1);
+
+impl Dispatch<wl_data_device::WlDataDevice, ()> for State {
+    fn event(
+        this: &mut Self,
+        _: &wl_data_device::WlDataDevice,
+        event: wl_data_device::Event,
+        _: &(),
+        _: &wayland_client::Connection,
+        _: &wayland_client::QueueHandle<Self>,
+    ) {
+        match event {
+            wl_data_device::Event::Selection { id } => {
+                if let Some(old) = std::mem::replace(&mut this.clipboard.offer, id) {
+                    old.destroy();
+                }
+            }
+            // Drag and drop isn't supported.
+            wl_data_device::Event::Enter { id: Some(id), .. } => id.destroy(),
+            _ => {}
+        }
+    }
+
+    event_created_child!(State, wl_data_device::WlDataDevice, [
+        wl_data_device::EVT_DATA_OFFER_OPCODE => (wl_data_offer::WlDataOffer, Mimes::default()),
+    ]);
+}
+
+impl Dispatch<wl_data_offer::WlDataOffer, Mimes> for State {
+    fn event(
+        _: &mut Self,
+        _: &wl_data_offer::WlDataOffer,
+        event: wl_data_offer::Event,
+        mimes: &Mimes,
+        _: &wayland_client::Connection,
+        _: &wayland_client::QueueHandle<Self>,
+    ) {
+        if let wl_data_offer::Event::Offer { mime_type } = event
+            && let Ok(mut mimes) = mimes.lock()
+        {
+            mimes.push(mime_type);
+        }
+    }
+}
+
+impl Dispatch<wl_data_source::WlDataSource, ()> for State {
+    fn event(
+        this: &mut Self,
+        source: &wl_data_source::WlDataSource,
+        event: wl_data_source::Event,
+        _: &(),
+        _: &wayland_client::Connection,
+        _: &wayland_client::QueueHandle<Self>,
+    ) {
+        let Some((current, text)) = &this.clipboard.source else {
+            return;
+        };
+        if current != source {
+            return;
+        }
+        match event {
+            wl_data_source::Event::Send { fd, .. } => send(text, fd),
+            wl_data_source::Event::Cancelled => {
+                source.destroy();
+                this.clipboard.source = None;
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<primary_device::ZwpPrimarySelectionDeviceV1, ()> for State {
+    fn event(
+        this: &mut Self,
+        _: &primary_device::ZwpPrimarySelectionDeviceV1,
+        event: primary_device::Event,
+        _: &(),
+        _: &wayland_client::Connection,
+        _: &wayland_client::QueueHandle<Self>,
+    ) {
+        if let primary_device::Event::Selection { id } = event
+            && let Some(old) = std::mem::replace(&mut this.clipboard.primary_offer, id)
+        {
+            old.destroy();
+        }
+    }
+
+    event_created_child!(State, primary_device::ZwpPrimarySelectionDeviceV1, [
+        primary_device::EVT_DATA_OFFER_OPCODE => (primary_offer::ZwpPrimarySelectionOfferV1, Mimes::default()),
+    ]);
+}
+
+impl Dispatch<primary_offer::ZwpPrimarySelectionOfferV1, Mimes> for State {
+    fn event(
+        _: &mut Self,
+        _: &primary_offer::ZwpPrimarySelectionOfferV1,
+        event: primary_offer::Event,
+        mimes: &Mimes,
+        _: &wayland_client::Connection,
+        _: &wayland_client::QueueHandle<Self>,
+    ) {
+        if let primary_offer::Event::Offer { mime_type } = event
+            && let Ok(mut mimes) = mimes.lock()
+        {
+            mimes.push(mime_type);
+        }
+    }
+}
+
+impl Dispatch<primary_source::ZwpPrimarySelectionSourceV1, ()> for State {
+    fn event(
+        this: &mut Self,
+        source: &primary_source::ZwpPrimarySelectionSourceV1,
+        event: primary_source::Event,
+        _: &(),
+        _: &wayland_client::Connection,
+        _: &wayland_client::QueueHandle<Self>,
+    ) {
+        let Some((current, text)) = &this.clipboard.primary_source else {
+            return;
+        };
+        if current != source {
+            return;
+        }
+        match event {
+            primary_source::Event::Send { fd, .. } => send(text, fd),
+            primary_source::Event::Cancelled => {
+                source.destroy();
+                this.clipboard.primary_source = None;
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+use lite_graphics::Rect;
+use wayland_client::{Dispatch, QueueHandle, delegate_noop, protocol::wl_seat};
+use wayland_protocols::wp::text_input::zv3::client::{
+    zwp_text_input_manager_v3 as manager, zwp_text_input_v3 as text_input,
+};
+
+use crate::event::{Event, RawEvent, WindowEvent};
+
+use super::{Window, app::State};
+
+/// Input method state, through `zwp_text_input_v3`.
+#[derive(Default)]
+pub(super) struct Ime {
+    pub(super) manager: Option<manager::ZwpTextInputManagerV3>,
+    input: Option<text_input::ZwpTextInputV3>,
+    /// Window the input method is on, 0 if none.
+    window: u64,
+    /// Caret of every window with a focused input, in surface coordinates.
+    areas: HashMap<u64, Rect>,
+    /// Caret last sent, `None` while disabled.
+    sent: Option<(i32, i32, u32, u32)>,
+    preedit: Option<(String, Option<usize>)>,
+    commit: Option<String>,
+}
+
+impl Ime {
+    /// Needs the manager and the seat to be bound.
+    pub(super) fn init(&mut self, seat: &wl_seat::WlSeat, qh: &QueueHandle<State>) {
+        if let Some(manager) = &self.manager {
+            self.input = Some(manager.get_text_input(seat, qh, ()));
+        }
+    }
+    pub(super) fn forget(&mut self, window: u64) {
+        self.areas.remove(&window);
+        if self.window == window {
+            self.window = 0;
+            self.sent = None;
+        }
+    }
+    /// Enable, move or disable the input method to match the window it's on.
+    fn update(&mut self) {
+        let Some(input) = &self.input else {
+            return;
+        };
+        let area = self.areas.get(&self.window).map(|r| (r.x, r.y, r.w, r.h));
+        if area == self.sent {
+            return;
+        }
+        match area {
+            Some((x, y, w, h)) => {
+                if self.sent.is_none() {
+                    input.enable();
+                    input.set_content_type(
+                        text_input::ContentHint::None,
+                        text_input::ContentPurpose::Normal,
+                    );
+                }
+                input.set_cursor_rectangle(x, y, w as _, h as _);
+            }
+            None => input.disable(),
+        }
+        input.commit();
+        self.sent = area;
+    }
+}
+
+impl Window {
+    pub(crate) fn set_ime_area(&self, area: Option<Rect>) {
+        let Some(app) = self.app.upgrade() else {
+            return;
+        };
+        let mut state = app.state.borrow_mut();
+        let ime = &mut state.ime;
+        match area {
+            Some(mut area) => {
+                area.y += self.titlebar_height() as i32;
+                ime.areas.insert(self.id(), area);
+            }
+            None => {
+                ime.areas.remove(&self.id());
+            }
+        }
+        ime.update();
+        drop(state);
+        let _ = app.conn.flush();
+    }
+}
+
+delegate_noop!(State: ignore manager::ZwpTextInputManagerV3);
+
+impl Dispatch<text_input::ZwpTextInputV3, ()> for State {
+    fn event(
+        this: &mut Self,
+        _: &text_input::ZwpTextInputV3,
+        event: text_input::Event,
+        _: &(),
+        _: &wayland_client::Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let ime = &mut this.ime;
+        match event {
+            text_input::Event::Enter { surface } => {
+                ime.window = this
+                    .windows
+                    .values()
+                    .find(|w| w.base_surface.get() == Some(&surface))
+                    .map_or(0, |w| w.id());
+                // Entering starts disabled.
+                ime.sent = None;
+                ime.update();
+            }
+            text_input::Event::Leave { .. } => {
+                // Leaving disables it too.
+                ime.window = 0;
+                ime.sent = None;
+            }
+            text_input::Event::PreeditString {
+                text, cursor_begin, ..
+            } => {
+                let cursor = usize::try_from(cursor_begin).ok();
+                ime.preedit = Some((text.unwrap_or_default(), cursor));
+            }
+            text_input::Event::CommitString { text } => {
+                ime.commit = text;
+            }
+            text_input::Event::Done { .. } => {
+                if ime.window == 0 {
+                    return;
+                }
+                // The old preedit goes away, then the commit is inserted and the new preedit shown.
+                if let Some(text) = ime.commit.take() {
+                    this.events.push_back(RawEvent {
+                        window: ime.window,
+                        event: Event::Window(WindowEvent::Text(text)),
+                    });
+                }
+                let (text, cursor) = ime.preedit.take().unwrap_or_default();
+                this.events.push_back(RawEvent {
+                    window: ime.window,
+                    event: Event::Window(WindowEvent::Preedit(text, cursor)),
+                });
+            }
+            _ => {}
+        }
+    }
+}
@@ -1,6 +1,8 @@
 mod app;
+mod clipboard;
 mod cursor;
 mod error;
+mod ime;
 mod window;
 
 pub(crate) use app::App;
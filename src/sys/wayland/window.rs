@@ -21,7 +21,11 @@ use nix::{
 use wayland_client::protocol::{wl_buffer, wl_shm, wl_shm_pool, wl_surface};
 use wayland_protocols::xdg::shell::client::{xdg_surface, xdg_toplevel};
 
-use crate::{sys, text::Text};
+use crate::{
+    event::{Event, RawEvent, WindowEvent},
+    sys,
+    text::Text,
+};
 
 use super::{App, Shm};
 
@@ -38,15 +42,21 @@ pub(crate) struct Window {
     text: RefCell<Text>,
     pub(super) size: RefCell<Size>,
     buffer_size: Cell<usize>,
+    decorations: bool,
+    pub(super) resizable: bool,
+    min_size: Cell<Option<Size>>,
+    max_size: Cell<Option<Size>>,
 }
 
-pub(super) const TITLEBAR_HEIGHT: usize = 32;
-pub(super) const fn data_size(size: Size) -> usize {
-    size.w as usize * (TITLEBAR_HEIGHT + size.h as usize) * 4
-}
+const TITLEBAR_HEIGHT: usize = 32;
 
 impl Window {
-    pub(crate) fn new(app: &Rc<App>) -> crate::Result<Rc<Self>> {
+    pub(crate) fn new(app: &Rc<App>, options: &crate::WindowOptions) -> crate::Result<Rc<Self>> {
+        let titlebar_height = if options.decorations {
+            TITLEBAR_HEIGHT
+        } else {
+            0
+        };
         let window = Rc::new(Window {
             app: Rc::downgrade(app),
             qh: app.qh.clone(),
@@ -56,13 +66,20 @@ impl Window {
             buffer: RefCell::new(None),
             buffer_data: RefCell::new(None),
             xdg_surface: OnceCell::new(),
-            titlebar_buf: RefCell::new(Buffer::new(800, TITLEBAR_HEIGHT)),
-            text: RefCell::new(Text::new("Hello, World!", 12.0)),
-            size: RefCell::new(Size::new(800, 600)),
-            buffer_size: Cell::new(800 * 632 * 4),
+            titlebar_buf: RefCell::new(Buffer::new(options.size.w as _, TITLEBAR_HEIGHT)),
+            text: RefCell::new(Text::new(&options.title, 12.0)),
+            size: RefCell::new(options.size),
+            buffer_size: Cell::new(
+                options.size.w as usize * (options.size.h as usize + titlebar_height) * 4,
+            ),
+            decorations: options.decorations,
+            resizable: options.resizable,
+            min_size: Cell::new(options.min_size),
+            max_size: Cell::new(options.max_size),
         });
 
         let size = *window.size.borrow();
+        let data_size = window.data_size(size);
 
         window.text.borrow_mut().set_font(sys::get_default_font()?);
 
@@ -86,9 +103,11 @@ impl Window {
             id,
         );
         let toplevel = xdg_surface.get_toplevel(&app.qh, id);
-        toplevel.set_title("Example window".into());
+        toplevel.set_title(options.title.clone());
+        toplevel.set_app_id(options.app_id.clone());
 
         let _ = window.xdg_surface.set((xdg_surface, toplevel));
+        window.update_size_limits();
 
         let name = env!("CARGO_PKG_NAME").to_string() + "_wayland";
         let _ = nix::sys::mman::shm_unlink(&*name);
@@ -98,33 +117,33 @@ impl Window {
             Mode::S_IRUSR | Mode::S_IWUSR,
         )?;
 
-        nix::unistd::ftruncate(file.as_fd(), data_size(size) as i64).unwrap();
+        nix::unistd::ftruncate(file.as_fd(), data_size as i64).unwrap();
         unsafe {
             let ptr = nix::sys::mman::mmap(
                 None,
-                NonZeroUsize::new(data_size(size)).unwrap(),
+                NonZeroUsize::new(data_size).unwrap(),
                 ProtFlags::PROT_READ | ProtFlags::PROT_WRITE,
                 MapFlags::MAP_SHARED,
                 file.as_fd(),
                 0,
             )
             .unwrap();
-            let addr = std::slice::from_raw_parts_mut(ptr.as_ptr() as *mut u8, data_size(size));
+            let addr = std::slice::from_raw_parts_mut(ptr.as_ptr() as *mut u8, data_size);
             for i in addr {
                 *i = 255;
             }
-            nix::sys::mman::munmap(ptr, data_size(size)).unwrap();
+            nix::sys::mman::munmap(ptr, data_size).unwrap();
         };
-        let pool = app_st.shm.as_ref().unwrap().create_pool(
-            file.as_fd(),
-            data_size(size) as i32,
-            &app.qh,
-            id,
-        );
+        let pool =
+            app_st
+                .shm
+                .as_ref()
+                .unwrap()
+                .create_pool(file.as_fd(), data_size as i32, &app.qh, id);
         let buffer = pool.create_buffer(
             0,
             size.w as i32,
-            size.h as i32 + TITLEBAR_HEIGHT as i32,
+            size.h as i32 + titlebar_height as i32,
             size.w as i32 * 4,
             wl_shm::Format::Argb8888,
             &app.qh,
@@ -146,11 +165,11 @@ impl Window {
         Ok(window)
     }
     pub(super) fn resize(&self, app_st: &super::app::State, size: Size) -> crate::Result<()> {
-        let size = Size::new(size.w, size.h - TITLEBAR_HEIGHT as u32);
+        let size = Size::new(size.w, size.h - self.titlebar_height() as u32);
         let app = self.app.upgrade().unwrap();
         let name = env!("CARGO_PKG_NAME").to_string() + "_wayland";
-        if data_size(size) > self.buffer_size.get() {
-            let new_size = (self.buffer_size.get() * 2).max(data_size(size));
+        if self.data_size(size) > self.buffer_size.get() {
+            let new_size = (self.buffer_size.get() * 2).max(self.data_size(size));
             self.buffer_size.set(new_size);
             let _ = nix::sys::mman::shm_unlink(&*name);
             let file = nix::sys::mman::shm_open(
@@ -182,7 +201,7 @@ impl Window {
 
         let pool = app_st.shm.as_ref().unwrap().create_pool(
             self.buffer_data.borrow().as_ref().unwrap().as_fd(),
-            data_size(size) as i32,
+            self.data_size(size) as i32,
             &app.qh,
             self.id(),
         );
@@ -210,7 +229,70 @@ impl Window {
 
         Ok(())
     }
+    /// Height of the client-side titlebar, 0 without decorations.
+    pub(super) fn titlebar_height(&self) -> usize {
+        if self.decorations { TITLEBAR_HEIGHT } else { 0 }
+    }
+    fn data_size(&self, size: Size) -> usize {
+        size.w as usize * (self.titlebar_height() + size.h as usize) * 4
+    }
+    /// Send the size limits for the whole surface, pinning the size if the window isn't resizable.
+    fn update_size_limits(&self) {
+        let titlebar = self.titlebar_height() as i32;
+        let (min, max) = if self.resizable {
+            (self.min_size.get(), self.max_size.get())
+        } else {
+            let size = *self.size.borrow();
+            (Some(size), Some(size))
+        };
+        let toplevel = &self.xdg_surface.get().unwrap().1;
+        let (w, h) = min.map_or((0, 0), |s| (s.w as i32, s.h as i32 + titlebar));
+        toplevel.set_min_size(w, h);
+        let (w, h) = max.map_or((0, 0), |s| (s.w as i32, s.h as i32 + titlebar));
+        toplevel.set_max_size(w, h);
+    }
+    pub(crate) fn set_title(&self, title: &str) -> crate::Result<()> {
+        self.xdg_surface
+            .get()
+            .unwrap()
+            .1
+            .set_title(title.to_owned());
+        self.text.borrow_mut().set_text(title);
+        if self.decorations {
+            self.titlebar(Offset::default(), false);
+            self.draw(None)?;
+        }
+        Ok(())
+    }
+    /// Sizes are up to the client outside of maximized and fullscreen states, so just take it.
+    pub(crate) fn set_size(&self, size: Size) -> crate::Result<()> {
+        let app = self.app.upgrade().unwrap();
+        let mut state = app.state.borrow_mut();
+        let surface_size = Size::new(size.w, size.h + self.titlebar_height() as u32);
+        self.resize(&state, surface_size)?;
+        if !self.resizable {
+            self.update_size_limits();
+        }
+        state.events.push_back(RawEvent {
+            window: self.id(),
+            event: Event::Window(WindowEvent::Resize(size.w, size.h)),
+        });
+        Ok(())
+    }
+    pub(crate) fn set_min_size(&self, size: Option<Size>) -> crate::Result<()> {
+        self.min_size.set(size);
+        self.update_size_limits();
+        Ok(())
+    }
+    pub(crate) fn set_max_size(&self, size: Option<Size>) -> crate::Result<()> {
+        self.max_size.set(size);
+        self.update_size_limits();
+        Ok(())
+    }
     pub(crate) fn titlebar(&self, pos: Offset, pressed: bool) {
+        if !self.decorations {
+            return;
+        }
         let mut titlebar_buf = self.titlebar_buf.borrow_mut();
         let mut text = self.text.borrow_mut();
         let size = self.size.borrow();
@@ -265,20 +347,22 @@ impl Window {
     pub(crate) fn draw(&self, buf: Option<Buffer>) -> crate::Result<()> {
         let file = self.buffer_data.borrow();
         let size = *self.size.borrow();
+        let titlebar_height = self.titlebar_height();
         unsafe {
             let ptr = nix::sys::mman::mmap(
                 None,
-                NonZeroUsize::new(data_size(size)).unwrap(),
+                NonZeroUsize::new(self.data_size(size)).unwrap(),
                 ProtFlags::PROT_READ | ProtFlags::PROT_WRITE,
                 MapFlags::MAP_SHARED,
                 file.as_ref().unwrap().as_fd(),
                 0,
             )?;
-            let addr = std::slice::from_raw_parts_mut(ptr.as_ptr() as *mut u8, data_size(size));
+            let addr =
+                std::slice::from_raw_parts_mut(ptr.as_ptr() as *mut u8, self.data_size(size));
             let titlebar = self.titlebar_buf.borrow();
             let titlebar_src = &**titlebar.data();
 
-            for i in 0..size.w as usize * TITLEBAR_HEIGHT {
+            for i in 0..size.w as usize * titlebar_height {
                 addr[i * 4] = titlebar_src[i * 3];
                 addr[i * 4 + 1] = titlebar_src[i * 3 + 1];
                 addr[i * 4 + 2] = titlebar_src[i * 3 + 2];
@@ -287,13 +371,13 @@ impl Window {
             if let Some(buf) = buf {
                 let src = &**buf.data();
                 for i in 0..(size.w * size.h) as usize {
-                    let base = size.w as usize * TITLEBAR_HEIGHT;
+                    let base = size.w as usize * titlebar_height;
                     addr[(base + i) * 4] = src[i * 3];
                     addr[(base + i) * 4 + 1] = src[i * 3 + 1];
                     addr[(base + i) * 4 + 2] = src[i * 3 + 2];
                 }
             }
-            nix::sys::mman::munmap(ptr, data_size(size))?;
+            nix::sys::mman::munmap(ptr, self.data_size(size))?;
         };
 
         let surface = self.base_surface.get().unwrap();
@@ -1,6 +1,6 @@
 use std::rc::Rc;
 
-use lite_graphics::Buffer;
+use lite_graphics::{Buffer, Rect, Size};
 
 platform!(
     pub(crate) enum Window {
@@ -8,18 +8,26 @@ platform!(
             X11(Rc<super::x11::Window>),
             Wayland(Rc<super::wayland::Window>);
         windows =>
-            Windows(Rc<super::windows::Window>)
+            Windows(Rc<super::windows::Window>);
+        any =>
+            Headless(Rc<super::headless::Window>)
     }
 );
 
 impl Window {
-    pub(crate) fn new(app: &super::app::App) -> Result<Self, crate::Error> {
+    pub(crate) fn new(
+        app: &super::app::App,
+        options: &crate::WindowOptions,
+    ) -> Result<Self, crate::Error> {
         platform!(match app {
-            super::app::App::X11(app) if linux => Ok(Self::X11(super::x11::Window::new(app)?)),
+            super::app::App::X11(app) if linux =>
+                Ok(Self::X11(super::x11::Window::new(app, options)?)),
             super::app::App::Wayland(app) if linux =>
-                Ok(Self::Wayland(super::wayland::Window::new(app)?)),
+                Ok(Self::Wayland(super::wayland::Window::new(app, options)?)),
             super::app::App::Windows(app) if windows =>
-                Ok(Self::Windows(super::windows::Window::new(app)?)),
+                Ok(Self::Windows(super::windows::Window::new(app, options)?)),
+            super::app::App::Headless(app) if any =>
+                Ok(Self::Headless(super::headless::Window::new(app, options)?)),
         })
     }
     pub(crate) fn draw(&self, buf: Buffer) -> crate::Result<()> {
@@ -27,6 +35,7 @@ impl Window {
             Self::Wayland(window) if linux => window.draw(Some(buf)),
             Self::X11(window) if linux => window.draw(buf),
             Self::Windows(window) if windows => window.draw(buf),
+            Self::Headless(window) if any => window.draw(buf),
         })
     }
     #[allow(unused)]
@@ -35,6 +44,7 @@ impl Window {
             Self::Wayland(window) if linux => window.id(),
             Self::X11(window) if linux => window.id(),
             Self::Windows(window) if windows => window.id(),
+            Self::Headless(window) if any => window.id(),
         })
     }
     pub(crate) fn set_cursor(&self, cursor: crate::app::CursorType) {
@@ -42,13 +52,75 @@ impl Window {
             Self::X11(window) if linux => window.set_cursor(cursor),
             Self::Wayland(window) if linux => window.set_cursor(cursor),
             Self::Windows(window) if windows => window.set_cursor(cursor),
+            Self::Headless(window) if any => window.set_cursor(cursor),
         })
     }
+    pub(crate) fn set_title(&self, title: &str) -> crate::Result<()> {
+        platform!(match self {
+            Self::X11(window) if linux => window.set_title(title),
+            Self::Wayland(window) if linux => window.set_title(title),
+            Self::Windows(window) if windows => window.set_title(title),
+            Self::Headless(window) if any => window.set_title(title),
+        })
+    }
+    pub(crate) fn set_size(&self, size: Size) -> crate::Result<()> {
+        platform!(match self {
+            Self::X11(window) if linux => window.set_size(size),
+            Self::Wayland(window) if linux => window.set_size(size),
+            Self::Windows(window) if windows => window.set_size(size),
+            Self::Headless(window) if any => window.set_size(size),
+        })
+    }
+    pub(crate) fn set_min_size(&self, size: Option<Size>) -> crate::Result<()> {
+        platform!(match self {
+            Self::X11(window) if linux => window.set_min_size(size),
+            Self::Wayland(window) if linux => window.set_min_size(size),
+            Self::Windows(window) if windows => window.set_min_size(size),
+            Self::Headless(_) if any => Ok(()),
+        })
+    }
+    pub(crate) fn set_max_size(&self, size: Option<Size>) -> crate::Result<()> {
+        platform!(match self {
+            Self::X11(window) if linux => window.set_max_size(size),
+            Self::Wayland(window) if linux => window.set_max_size(size),
+            Self::Windows(window) if windows => window.set_max_size(size),
+            Self::Headless(_) if any => Ok(()),
+        })
+    }
+    /// Where input method popups go, in window coordinates. `None` turns the input method off.
+    pub(crate) fn set_ime_area(&self, area: Option<Rect>) {
+        platform!(match self {
+            Self::X11(window) if linux => window.set_ime_area(area),
+            Self::Wayland(window) if linux => window.set_ime_area(area),
+            Self::Windows(_) if windows => (),
+            Self::Headless(_) if any => (),
+        })
+    }
+    /// Only the headless backend accepts injected events. Returns whether it was queued.
+    pub(crate) fn push_event(&self, event: crate::event::Event) -> bool {
+        match self {
+            Self::Headless(window) => {
+                window.push_event(event);
+                true
+            }
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+    /// Last frame drawn by the headless backend.
+    pub(crate) fn frame(&self) -> Option<Buffer> {
+        match self {
+            Self::Headless(window) => window.frame(),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
     pub(crate) fn destroy(&self) {
         platform!(match self {
             Self::X11(window) if linux => window.destroy(),
             Self::Wayland(window) if linux => window.destroy(),
             Self::Windows(window) if windows => window.destroy(),
+            Self::Headless(window) if any => window.destroy(),
         })
     }
 }
@@ -59,6 +131,7 @@ impl Clone for Window {
             Self::Wayland(window) if linux => Self::Wayland(window.clone()),
             Self::X11(window) if linux => Self::X11(window.clone()),
             Self::Windows(window) if windows => Self::Windows(window.clone()),
+            Self::Headless(window) if any => Self::Headless(window.clone()),
         })
     }
 }
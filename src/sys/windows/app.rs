@@ -3,17 +3,17 @@ use std::{cell::RefCell, mem::MaybeUninit, os::raw::c_void, rc::Rc};
 use windows::{
     core::{BOOL, PCWSTR},
     Win32::{
-        Foundation::{GetLastError, HINSTANCE, HMODULE, HWND, LPARAM, LRESULT, WPARAM},
+        Foundation::{GetLastError, HINSTANCE, HMODULE, HWND, LPARAM, LRESULT, POINT, WPARAM},
         Graphics::Gdi::{
             BeginPaint, CreateCompatibleBitmap, DrawStateW, EndPaint, SetDIBits, BITMAPINFO,
             BITMAPINFOHEADER, BI_RGB, COLOR_WINDOW, DIB_RGB_COLORS, DST_BITMAP, HBRUSH, RGBQUAD,
         },
         System::LibraryLoader::GetModuleHandleW,
         UI::WindowsAndMessaging::{
-            DefWindowProcW, DestroyWindow, DispatchMessageW, GetMessageW, GetWindowLongPtrW,
-            LoadCursorW, LoadIconW, PostQuitMessage, RegisterClassExW, SetWindowLongPtrW,
-            TranslateMessage, CREATESTRUCTW, CS_HREDRAW, CS_VREDRAW, GWLP_USERDATA, IDC_ARROW,
-            IDI_APPLICATION, WM_ACTIVATE, WM_CLOSE, WM_CREATE, WM_DESTROY, WM_LBUTTONDOWN,
+            DefWindowProcW, DispatchMessageW, GetMessageW, GetWindowLongPtrW, LoadCursorW,
+            LoadIconW, RegisterClassExW, SetWindowLongPtrW, TranslateMessage, CREATESTRUCTW,
+            CS_HREDRAW, CS_VREDRAW, GWLP_USERDATA, IDC_ARROW, IDI_APPLICATION, MINMAXINFO,
+            WM_ACTIVATE, WM_CLOSE, WM_CREATE, WM_DESTROY, WM_GETMINMAXINFO, WM_LBUTTONDOWN,
             WM_LBUTTONUP, WM_MBUTTONDOWN, WM_MBUTTONUP, WM_MOUSEMOVE, WM_PAINT, WM_RBUTTONDOWN,
             WM_RBUTTONUP, WM_SIZE, WM_XBUTTONDOWN, WM_XBUTTONUP, WNDCLASSEXW,
         },
@@ -22,12 +22,17 @@ use windows::{
 
 use crate::event::{Button, Event, RawEvent, WidgetEvent, WindowEvent, WindowState};
 
-use super::{window::WindowData, Window};
+use super::{
+    window::{outer_size, WindowData},
+    Window,
+};
 
 pub(crate) struct App {
     pub(super) module: HMODULE,
     pub(super) class: u16,
     pub(super) windows: RefCell<Vec<Rc<Window>>>,
+    /// Windows has no primary selection, it's only shared inside the app.
+    pub(super) primary: RefCell<Option<String>>,
 }
 
 impl App {
@@ -59,6 +64,7 @@ impl App {
             module,
             class,
             windows: RefCell::new(vec![]),
+            primary: RefCell::new(None),
         }))
     }
     pub(crate) fn get_event(&self) -> crate::Result<Option<crate::event::RawEvent>> {
@@ -264,12 +270,36 @@ unsafe extern "system" fn wnd_proc(
                 .push_back(Event::Widget(WidgetEvent::Move(x, y)));
             LRESULT(0)
         }
+        WM_GETMINMAXINFO => {
+            // Sent before `WM_CREATE`, when there is no data yet.
+            if let Some(window_data) = window_data {
+                let info = &mut *(lparam.0 as *mut MINMAXINFO);
+                if let Some(size) = window_data.min_size.get() {
+                    let (w, h) = outer_size(hwnd, size);
+                    info.ptMinTrackSize = POINT { x: w, y: h };
+                }
+                if let Some(size) = window_data.max_size.get() {
+                    let (w, h) = outer_size(hwnd, size);
+                    info.ptMaxTrackSize = POINT { x: w, y: h };
+                }
+            }
+            LRESULT(0)
+        }
         WM_CLOSE => {
-            let _ = DestroyWindow(hwnd);
+            let window_data = window_data.unwrap();
+            window_data
+                .events
+                .borrow_mut()
+                .push_back(Event::Window(WindowEvent::Closing));
             LRESULT(0)
         }
         WM_DESTROY => {
-            PostQuitMessage(0);
+            if let Some(window_data) = window_data {
+                window_data
+                    .events
+                    .borrow_mut()
+                    .push_back(Event::Window(WindowEvent::Destroyed));
+            }
             LRESULT(0)
         }
         _ => DefWindowProcW(hwnd, msg, wparam, lparam),
@@ -0,0 +1,72 @@
+use windows::Win32::{
+    Foundation::{HANDLE, HGLOBAL, HWND},
+    System::{
+        DataExchange::{
+            CloseClipboard, EmptyClipboard, GetClipboardData, OpenClipboard, SetClipboardData,
+        },
+        Memory::{GMEM_MOVEABLE, GlobalAlloc, GlobalFree, GlobalLock, GlobalUnlock},
+        Ole::CF_UNICODETEXT,
+    },
+};
+
+use crate::app::ClipboardKind;
+
+use super::App;
+
+impl App {
+    /// The clipboard needs an owner window, or `SetClipboardData` fails.
+    fn owner(&self) -> Option<HWND> {
+        self.windows.borrow().first().map(|w| w.hwnd)
+    }
+    pub(crate) fn set_clipboard(&self, kind: ClipboardKind, text: String) {
+        if kind == ClipboardKind::Primary {
+            *self.primary.borrow_mut() = Some(text);
+            return;
+        }
+        let wide: Vec<u16> = text.encode_utf16().chain([0]).collect();
+        unsafe {
+            if OpenClipboard(self.owner()).is_err() {
+                return;
+            }
+            if EmptyClipboard().is_ok()
+                && let Ok(mem) = GlobalAlloc(GMEM_MOVEABLE, wide.len() * size_of::<u16>())
+            {
+                let ptr = GlobalLock(mem) as *mut u16;
+                if !ptr.is_null() {
+                    ptr.copy_from_nonoverlapping(wide.as_ptr(), wide.len());
+                    let _ = GlobalUnlock(mem);
+                }
+                // The system owns the memory once it's set.
+                if ptr.is_null()
+                    || SetClipboardData(CF_UNICODETEXT.0 as u32, Some(HANDLE(mem.0))).is_err()
+                {
+                    let _ = GlobalFree(Some(mem));
+                }
+            }
+            let _ = CloseClipboard();
+        }
+    }
+    pub(crate) fn get_clipboard(&self, kind: ClipboardKind) -> Option<String> {
+        if kind == ClipboardKind::Primary {
+            return self.primary.borrow().clone();
+        }
+        unsafe {
+            OpenClipboard(self.owner()).ok()?;
+            let text = GetClipboardData(CF_UNICODETEXT.0 as u32)
+                .ok()
+                .and_then(|data| {
+                    let mem = HGLOBAL(data.0);
+                    let ptr = GlobalLock(mem) as *const u16;
+                    if ptr.is_null() {
+                        return None;
+                    }
+                    let len = (0..).take_while(|&i| *ptr.add(i) != 0).count();
+                    let text = String::from_utf16_lossy(std::slice::from_raw_parts(ptr, len));
+                    let _ = GlobalUnlock(mem);
+                    Some(text)
+                });
+            let _ = CloseClipboard();
+            text
+        }
+    }
+}
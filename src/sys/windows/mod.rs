@@ -1,11 +1,11 @@
 mod app;
+mod clipboard;
 mod error;
 mod window;
 
 use std::{
     ffi::OsString,
     fs,
-    io::{self, Read as _},
     mem,
     os::windows::ffi::OsStringExt,
 };
@@ -65,11 +65,3 @@ pub(crate) fn get_font(name: Option<String>) -> crate::Result<(fs::File, u8)> {
     }
     Ok((fs::File::open("NUL")?, 0))
 }
-
-pub(crate) fn get_default_font() -> crate::Result<ab_glyph::FontArc> {
-    let mut font = get_font(None)?.0;
-    let mut buf = vec![];
-    font.read_to_end(&mut buf)?;
-    ab_glyph::FontArc::try_from_vec(buf)
-        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Invalid Font Used").into())
-}
@@ -1,14 +1,22 @@
-use std::{cell::RefCell, collections::VecDeque, os::raw::c_void, ptr, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    os::raw::c_void,
+    ptr,
+    rc::{Rc, Weak},
+};
 
-use lite_graphics::draw::Buffer;
+use lite_graphics::{draw::Buffer, Size};
 use windows::{
-    core::{w, PCWSTR},
+    core::{HSTRING, PCWSTR},
     Win32::{
-        Foundation::{GetLastError, HINSTANCE, HWND},
+        Foundation::{GetLastError, HINSTANCE, HWND, RECT},
         Graphics::Gdi::{RedrawWindow, HBITMAP, RDW_ERASE, RDW_INVALIDATE},
         UI::WindowsAndMessaging::{
-            CreateWindowExW, CW_USEDEFAULT, WINDOW_EX_STYLE, WS_CAPTION, WS_MAXIMIZEBOX,
-            WS_MINIMIZEBOX, WS_SYSMENU, WS_THICKFRAME, WS_VISIBLE,
+            AdjustWindowRectEx, CreateWindowExW, DestroyWindow, GetWindowLongPtrW, SetWindowPos,
+            SetWindowTextW, CW_USEDEFAULT, GWL_EXSTYLE, GWL_STYLE, SWP_NOACTIVATE, SWP_NOMOVE,
+            SWP_NOZORDER, WINDOW_EX_STYLE, WINDOW_STYLE, WS_CAPTION, WS_MAXIMIZEBOX,
+            WS_MINIMIZEBOX, WS_POPUP, WS_SYSMENU, WS_THICKFRAME, WS_VISIBLE,
         },
     },
 };
@@ -18,6 +26,7 @@ use crate::event::Event;
 use super::App;
 
 pub(crate) struct Window {
+    app: Weak<App>,
     pub(super) hwnd: HWND,
     pub(super) data: Rc<WindowData>,
 }
@@ -26,30 +35,72 @@ pub(super) struct WindowData {
     pub(super) buffer: RefCell<Buffer>,
     pub(super) hbm: RefCell<HBITMAP>,
     pub(super) events: RefCell<VecDeque<Event>>,
+    pub(super) min_size: Cell<Option<Size>>,
+    pub(super) max_size: Cell<Option<Size>>,
+}
+
+/// Outer size of a window with `size` as its client area.
+pub(super) fn outer_size(hwnd: HWND, size: Size) -> (i32, i32) {
+    let mut rect = RECT {
+        left: 0,
+        top: 0,
+        right: size.w as i32,
+        bottom: size.h as i32,
+    };
+    unsafe {
+        let style = WINDOW_STYLE(GetWindowLongPtrW(hwnd, GWL_STYLE) as u32);
+        let ex_style = WINDOW_EX_STYLE(GetWindowLongPtrW(hwnd, GWL_EXSTYLE) as u32);
+        let _ = AdjustWindowRectEx(&mut rect, style, false, ex_style);
+    }
+    (rect.right - rect.left, rect.bottom - rect.top)
 }
 
 impl Window {
-    pub(crate) fn new(app: &Rc<App>) -> crate::Result<Rc<Self>> {
+    pub(crate) fn new(app: &Rc<App>, options: &crate::WindowOptions) -> crate::Result<Rc<Self>> {
+        let (min_size, max_size) = if options.resizable {
+            (options.min_size, options.max_size)
+        } else {
+            (Some(options.size), Some(options.size))
+        };
         let data = Rc::new(WindowData {
-            buffer: RefCell::new(Buffer::new(800, 600)),
+            buffer: RefCell::new(Buffer::new(options.size.w as _, options.size.h as _)),
             hbm: RefCell::new(HBITMAP(ptr::null_mut())),
             events: RefCell::new(VecDeque::new()),
+            min_size: Cell::new(min_size),
+            max_size: Cell::new(max_size),
         });
+        let mut style = WS_VISIBLE;
+        if options.decorations {
+            style |= WS_CAPTION | WS_SYSMENU | WS_MINIMIZEBOX;
+        } else {
+            style |= WS_POPUP;
+        }
+        if options.resizable {
+            style |= WS_THICKFRAME;
+            if options.decorations {
+                style |= WS_MAXIMIZEBOX;
+            }
+        }
+        let mut rect = RECT {
+            left: 0,
+            top: 0,
+            right: options.size.w as i32,
+            bottom: options.size.h as i32,
+        };
+        unsafe { AdjustWindowRectEx(&mut rect, style, false, WINDOW_EX_STYLE(0)) }?;
+        let (x, y) = options
+            .position
+            .map_or((CW_USEDEFAULT, CW_USEDEFAULT), |p| (p.x, p.y));
         let hwnd = unsafe {
             CreateWindowExW(
                 WINDOW_EX_STYLE(0),
                 PCWSTR(app.class as usize as *const u16),
-                w!("Hello!"),
-                WS_CAPTION
-                    | WS_SYSMENU
-                    | WS_THICKFRAME
-                    | WS_MINIMIZEBOX
-                    | WS_MAXIMIZEBOX
-                    | WS_VISIBLE,
-                CW_USEDEFAULT,
-                CW_USEDEFAULT,
-                800,
-                600,
+                &HSTRING::from(&options.title),
+                style,
+                x,
+                y,
+                rect.right - rect.left,
+                rect.bottom - rect.top,
                 None,
                 None,
                 Some(HINSTANCE(app.module.0)),
@@ -60,7 +111,11 @@ impl Window {
             Err(unsafe { GetLastError() })?
         }
 
-        let this = Rc::new(Window { hwnd, data });
+        let this = Rc::new(Window {
+            app: Rc::downgrade(app),
+            hwnd,
+            data,
+        });
         app.windows.borrow_mut().push(this.clone());
 
         Ok(this)
@@ -73,4 +128,37 @@ impl Window {
     pub(crate) fn id(&self) -> u64 {
         self.hwnd.0 as usize as u64
     }
+    pub(crate) fn set_title(&self, title: &str) -> crate::Result<()> {
+        unsafe { SetWindowTextW(self.hwnd, &HSTRING::from(title))? };
+        Ok(())
+    }
+    pub(crate) fn set_size(&self, size: Size) -> crate::Result<()> {
+        let (w, h) = outer_size(self.hwnd, size);
+        unsafe {
+            SetWindowPos(
+                self.hwnd,
+                None,
+                0,
+                0,
+                w,
+                h,
+                SWP_NOMOVE | SWP_NOZORDER | SWP_NOACTIVATE,
+            )?
+        };
+        Ok(())
+    }
+    pub(crate) fn set_min_size(&self, size: Option<Size>) -> crate::Result<()> {
+        self.data.min_size.set(size);
+        Ok(())
+    }
+    pub(crate) fn set_max_size(&self, size: Option<Size>) -> crate::Result<()> {
+        self.data.max_size.set(size);
+        Ok(())
+    }
+    pub(crate) fn destroy(&self) {
+        let _ = unsafe { DestroyWindow(self.hwnd) };
+        if let Some(app) = self.app.upgrade() {
+            app.windows.borrow_mut().retain(|w| w.hwnd != self.hwnd);
+        }
+    }
 }
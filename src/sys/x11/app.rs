@@ -1,28 +1,50 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    io,
+    rc::Rc,
+};
 
 use lite_graphics::Size;
 use x11rb::{
     connection::Connection as _,
     protocol::{
         Event,
-        xproto::{Atom, AtomEnum, ConnectionExt as _, GetPropertyReply, KeyButMask, Screen},
+        xkb::{self, ConnectionExt as _},
+        xproto::{
+            Atom, AtomEnum, ConnectionExt as _, CreateWindowAux, EventMask, GetPropertyReply,
+            KEY_PRESS_EVENT, KeyPressEvent, Screen, WindowClass,
+        },
     },
     rust_connection::RustConnection,
 };
 
+use xkbcommon_rs::xkb_state::StateComponent;
+
 use crate::{
     event::{Button, Modifiers, RawEvent, WidgetEvent, WindowEvent, WindowState},
     sys::linux,
 };
 
-use super::Window;
+use super::{Window, keymap::load_keymap};
 
 pub(crate) struct App {
     pub(super) conn: Rc<RustConnection>,
     pub(super) screen: Screen,
     pub(super) atoms: Atoms,
     pub(super) windows: RefCell<Vec<Rc<Window>>>,
-    keymap: Vec<u32>,
+    /// Keymap of the core keyboard, following the server's modifiers and group.
+    keymap: RefCell<xkbcommon_rs::State>,
+    compose: RefCell<linux::Compose>,
+    /// Events made up here, returned before reading the connection.
+    pub(super) queued: RefCell<VecDeque<RawEvent>>,
+    /// Invisible window owning our selections, also talking to the input method.
+    pub(super) selection_window: u32,
+    pub(super) selections: RefCell<HashMap<Atom, String>>,
+    /// Events read while waiting for something else.
+    pub(super) pending: RefCell<VecDeque<Event>>,
+    pub(super) xim: RefCell<super::xim::Xim>,
+    pub(super) scroll: RefCell<super::scroll::Scroll>,
 }
 
 x11rb::atom_manager! {
@@ -36,6 +58,15 @@ x11rb::atom_manager! {
         _NET_WM_STATE_FULLSCREEN,
         _NET_WM_STATE_FOCUSED,
         UTF8_STRING,
+        _MOTIF_WM_HINTS,
+        CLIPBOARD,
+        TARGETS,
+        PARSLAY_SELECTION,
+        INCR,
+        _XIM_XCONNECT,
+        _XIM_PROTOCOL,
+        _XIM_MOREDATA,
+        PARSLAY_XIM,
     }
 }
 
@@ -47,26 +78,122 @@ impl App {
         let atoms_cookie = Atoms::new(&conn)?;
         let atoms = atoms_cookie.reply()?;
 
-        let mapping_reply = conn.get_keyboard_mapping(8, 247)?.reply()?;
-        let keymap = mapping_reply
-            .keysyms
-            .iter()
-            .enumerate()
-            .filter_map(|(idx, item)| {
-                (idx % mapping_reply.keysyms_per_keycode as usize == 0).then_some(*item)
-            })
-            .collect();
+        if !conn.xkb_use_extension(1, 0)?.reply()?.supported {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "The X server doesn't support XKB 1.0",
+            )
+            .into());
+        }
+        let all_parts = xkb::MapPart::from(0xffu8);
+        conn.xkb_select_events(
+            xkb::ID::USE_CORE_KBD.into(),
+            0u8.into(),
+            xkb::EventType::NEW_KEYBOARD_NOTIFY
+                | xkb::EventType::MAP_NOTIFY
+                | xkb::EventType::STATE_NOTIFY,
+            all_parts,
+            all_parts,
+            &xkb::SelectEventsAux::new(),
+        )?;
+        // Held keys repeat as presses only, without a release before each.
+        let repeat = xkb::PerClientFlag::DETECTABLE_AUTO_REPEAT;
+        conn.xkb_per_client_flags(
+            xkb::ID::USE_CORE_KBD.into(),
+            repeat,
+            repeat,
+            0u32.into(),
+            0u32.into(),
+            0u32.into(),
+        )?
+        .reply()?;
+        let keymap = RefCell::new(load_keymap(&conn)?);
+
+        let selection_window = conn.generate_id()?;
+        conn.create_window(
+            x11rb::COPY_DEPTH_FROM_PARENT,
+            selection_window,
+            screen.root,
+            0,
+            0,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_ONLY,
+            x11rb::COPY_FROM_PARENT,
+            &CreateWindowAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+        )?;
 
-        Ok(Rc::new(Self {
+        let app = Rc::new(Self {
             conn: Rc::new(conn),
             screen,
             atoms,
             windows: RefCell::new(vec![]),
             keymap,
-        }))
+            compose: RefCell::new(linux::Compose::new()),
+            queued: RefCell::new(VecDeque::new()),
+            selection_window,
+            selections: RefCell::new(HashMap::new()),
+            pending: RefCell::new(VecDeque::new()),
+            xim: RefCell::default(),
+            scroll: RefCell::default(),
+        });
+        app.connect_xim();
+        app.init_scroll()?;
+        Ok(app)
+    }
+    /// Reload the keymap after the server changed it, keeping up with its state.
+    fn reload_keymap(&self) -> crate::Result<()> {
+        *self.keymap.borrow_mut() = load_keymap(&self.conn)?;
+        Ok(())
+    }
+    /// Turn a key press or release into ours, queueing the text it types.
+    pub(super) fn key_event(&self, event: &KeyPressEvent, pressed: bool) -> RawEvent {
+        let keymap = self.keymap.borrow();
+        let mods = ["Shift", "Control", "Mod1", "Mod4", "Lock", "Mod2"]
+            .iter()
+            .enumerate()
+            .map(|(idx, name)| {
+                let active = keymap.mod_name_is_active(name, StateComponent::MODS_EFFECTIVE);
+                (active.unwrap_or(false) as u8) << idx
+            })
+            .fold(Default::default(), |st, id| st | Modifiers(id));
+        let sym = keymap
+            .key_get_one_sym(event.detail as u32)
+            .map_or(0, |sym| sym.raw());
+        let key = linux::key_from_xkb(sym);
+        if !pressed {
+            return RawEvent {
+                window: event.event as u64,
+                event: crate::event::Event::Window(WindowEvent::KeyRelease(mods, key)),
+            };
+        }
+
+        let text = keymap
+            .key_get_utf8(event.detail as u32)
+            .and_then(|t| String::from_utf8(t).ok());
+        if mods & (Modifiers::CONTROL | Modifiers::ALT | Modifiers::SUPER) == Modifiers::NONE
+            && let Some(text) = self.compose.borrow_mut().feed(sym, text)
+        {
+            self.queued.borrow_mut().push_back(RawEvent {
+                window: event.event as u64,
+                event: crate::event::Event::Window(WindowEvent::Text(text)),
+            });
+        }
+        RawEvent {
+            window: event.event as u64,
+            event: crate::event::Event::Window(WindowEvent::KeyPress(mods, key)),
+        }
     }
     pub(crate) fn get_event(self: &Rc<Self>) -> crate::Result<Option<crate::event::RawEvent>> {
-        let event = self.conn.wait_for_event()?;
+        if let Some(event) = self.queued.borrow_mut().pop_front() {
+            return Ok(Some(event));
+        }
+        let pending = self.pending.borrow_mut().pop_front();
+        let event = match pending {
+            Some(event) => event,
+            None => self.conn.wait_for_event()?,
+        };
         fn get_property(
             conn: &RustConnection,
             win: u32,
@@ -85,65 +212,53 @@ impl App {
             event: crate::event::Event::Unknown,
         }));
         let ev = match event {
+            Event::ClientMessage(event) if event.window == self.selection_window => {
+                self.handle_xim(event);
+                unknown
+            }
             Event::ClientMessage(event) => {
                 let data = event.data.as_data32();
                 if event.format == 32 && data[0] == self.atoms.WM_DELETE_WINDOW {
-                    let mut windows = self.windows.borrow_mut();
-                    windows.retain(|w| w.id() != event.window as _);
-                    if windows.is_empty() {
-                        return Ok(None);
-                    }
+                    Ok(Some(RawEvent {
+                        window: event.window as _,
+                        event: crate::event::Event::Window(WindowEvent::Closing),
+                    }))
+                } else {
+                    unknown
+                }
+            }
+            Event::KeyPress(event) | Event::KeyRelease(event) => {
+                if self.forward_to_xim(&event) {
+                    return unknown;
                 }
+                let pressed = event.response_type & 0x7f == KEY_PRESS_EVENT;
+                Ok(Some(self.key_event(&event, pressed)))
+            }
+            Event::XkbStateNotify(event) => {
+                self.keymap.borrow_mut().update_mask(
+                    event.base_mods.into(),
+                    event.latched_mods.into(),
+                    event.locked_mods.into(),
+                    event.base_group as _,
+                    event.latched_group as _,
+                    u32::from(event.locked_group) as _,
+                );
                 unknown
             }
-            Event::KeyPress(event) => {
-                let keysym = self.keymap[event.detail as usize - 8];
-                let mods = [
-                    KeyButMask::SHIFT,
-                    KeyButMask::CONTROL,
-                    KeyButMask::MOD1,
-                    KeyButMask::MOD4,
-                ]
-                .iter()
-                .enumerate()
-                .map(|(idx, &name)| ((event.state & name == name) as u8) << idx)
-                .fold(Default::default(), |st, i| Modifiers(i) | st);
-
-                let key = if mods & Modifiers::SHIFT == Modifiers::SHIFT {
-                    linux::key_from_xkb(keysym).shift()
-                } else {
-                    linux::key_from_xkb(keysym)
-                };
-
-                Ok(Some(RawEvent {
-                    window: event.event as u64,
-                    event: crate::event::Event::Window(WindowEvent::KeyPress(mods, key)),
-                }))
+            Event::XkbNewKeyboardNotify(_) | Event::XkbMapNotify(_) | Event::MappingNotify(_) => {
+                self.reload_keymap()?;
+                unknown
             }
-            Event::KeyRelease(event) => {
-                let keysym = self.keymap[event.detail as usize - 8];
-                let mods = [
-                    KeyButMask::SHIFT,
-                    KeyButMask::CONTROL,
-                    KeyButMask::MOD1,
-                    KeyButMask::MOD4,
-                ]
-                .iter()
-                .enumerate()
-                .map(|(idx, &name)| ((event.state & name == name) as u8) << idx)
-                .fold(Default::default(), |st, i| Modifiers(i) | st);
-
-                let key = if mods & Modifiers::SHIFT == Modifiers::SHIFT {
-                    linux::key_from_xkb(keysym).shift()
-                } else {
-                    linux::key_from_xkb(keysym)
-                };
-
-                Ok(Some(RawEvent {
-                    window: event.event as u64,
-                    event: crate::event::Event::Window(WindowEvent::KeyRelease(mods, key)),
-                }))
+            Event::ButtonPress(event) if (4..=7).contains(&event.detail) => {
+                match self.wheel_button(&event) {
+                    Some(ev) => Ok(Some(RawEvent {
+                        window: event.event as u64,
+                        event: crate::event::Event::Widget(ev),
+                    })),
+                    None => unknown,
+                }
             }
+            Event::ButtonRelease(event) if (4..=7).contains(&event.detail) => unknown,
             Event::ButtonPress(event) => {
                 let ev = crate::event::Event::Widget(WidgetEvent::ButtonPress(
                     Button::from_code(event.detail),
@@ -176,6 +291,23 @@ impl App {
                     event: ev,
                 }))
             }
+            Event::XinputMotion(event) => Ok(Some(self.xi_motion(&event))),
+            Event::XinputEnter(_) => {
+                self.reset_scroll();
+                unknown
+            }
+            Event::XinputHierarchy(_) | Event::XinputDeviceChanged(_) => {
+                self.load_scroll_valuators()?;
+                unknown
+            }
+            Event::SelectionRequest(event) => {
+                self.send_selection(event);
+                unknown
+            }
+            Event::SelectionClear(event) => {
+                self.selections.borrow_mut().remove(&event.selection);
+                unknown
+            }
             Event::Error(e) => Err(e.into()),
             Event::ConfigureNotify(event) => {
                 let windows = self.windows.borrow();
@@ -222,22 +354,18 @@ impl App {
             }
             _ => unknown,
         };
-        if self
-            .windows
-            .borrow()
-            .iter()
-            .any(|w| w.id() == ev.as_ref().unwrap().unwrap().window)
-            || ev.as_ref().unwrap().unwrap().window == 0
-        {
+        let window = ev.as_ref().unwrap().as_ref().unwrap().window;
+        if self.windows.borrow().iter().any(|w| w.id() == window) || window == 0 {
             ev
         } else {
-            Ok(self.windows.borrow().len().gt(&0).then(|| RawEvent {
-                window: ev.as_ref().unwrap().unwrap().window,
+            Ok(self.windows.borrow().len().gt(&0).then_some(RawEvent {
+                window,
                 event: crate::event::Event::Window(WindowEvent::Destroyed),
             }))
         }
     }
     pub(crate) fn destroy_window(&self, window_id: u64) {
+        self.forget_xim_window(window_id as u32);
         let mut windows = self.windows.borrow_mut();
         windows.retain(|w| w.id() != window_id);
     }
@@ -0,0 +1,201 @@
+use std::{
+    os::fd::AsFd,
+    time::{Duration, Instant},
+};
+
+use nix::{
+    errno::Errno,
+    poll::{PollFd, PollFlags, PollTimeout, poll},
+};
+
+use x11rb::{
+    CURRENT_TIME, NONE,
+    connection::Connection as _,
+    protocol::{
+        Event,
+        xproto::{
+            Atom, AtomEnum, ConnectionExt as _, EventMask, GetPropertyReply, PropMode, Property,
+            SELECTION_NOTIFY_EVENT, SelectionNotifyEvent, SelectionRequestEvent,
+        },
+    },
+    wrapper::ConnectionExt as _,
+};
+
+use crate::app::ClipboardKind;
+
+use super::App;
+
+/// How long to wait for each answer of the selection owner.
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+impl App {
+    fn selection_atom(&self, kind: ClipboardKind) -> Atom {
+        match kind {
+            ClipboardKind::Clipboard => self.atoms.CLIPBOARD,
+            ClipboardKind::Primary => AtomEnum::PRIMARY.into(),
+        }
+    }
+    pub(crate) fn set_clipboard(&self, kind: ClipboardKind, text: String) {
+        let selection = self.selection_atom(kind);
+        self.selections.borrow_mut().insert(selection, text);
+        let _ = self
+            .conn
+            .set_selection_owner(self.selection_window, selection, CURRENT_TIME);
+        let _ = self.conn.flush();
+    }
+    pub(crate) fn get_clipboard(&self, kind: ClipboardKind) -> Option<String> {
+        let selection = self.selection_atom(kind);
+        let owner = self
+            .conn
+            .get_selection_owner(selection)
+            .ok()?
+            .reply()
+            .ok()?
+            .owner;
+        if owner == self.selection_window {
+            return self.selections.borrow().get(&selection).cloned();
+        } else if owner == NONE {
+            return None;
+        }
+
+        self.conn
+            .convert_selection(
+                self.selection_window,
+                selection,
+                self.atoms.UTF8_STRING,
+                self.atoms.PARSLAY_SELECTION,
+                CURRENT_TIME,
+            )
+            .ok()?;
+        self.conn.flush().ok()?;
+
+        let deadline = Instant::now() + TIMEOUT;
+        let property = self.wait_for(deadline, |event| match event {
+            Event::SelectionNotify(event) if event.requestor == self.selection_window => {
+                Some(event.property)
+            }
+            _ => None,
+        })?;
+        if property == NONE {
+            return None;
+        }
+        let reply = self.take_property(property)?;
+        if reply.type_ != self.atoms.INCR {
+            return Some(String::from_utf8_lossy(&reply.value).into_owned());
+        }
+
+        // Deleting the INCR property asked the owner for the first chunk,
+        // every chunk comes as a new value and an empty one ends the transfer.
+        let mut data = vec![];
+        loop {
+            let deadline = Instant::now() + TIMEOUT;
+            self.wait_for(deadline, |event| match event {
+                Event::PropertyNotify(event)
+                    if event.window == self.selection_window
+                        && event.atom == property
+                        && event.state == Property::NEW_VALUE =>
+                {
+                    Some(())
+                }
+                _ => None,
+            })?;
+            let reply = self.take_property(property)?;
+            if reply.value.is_empty() {
+                return Some(String::from_utf8_lossy(&data).into_owned());
+            }
+            data.extend(reply.value);
+        }
+    }
+    /// Read and delete a property of the selection window.
+    fn take_property(&self, property: Atom) -> Option<GetPropertyReply> {
+        self.conn
+            .get_property(
+                true,
+                self.selection_window,
+                property,
+                AtomEnum::ANY,
+                0,
+                u32::MAX / 4,
+            )
+            .ok()?
+            .reply()
+            .ok()
+    }
+    /// Wait until `deadline` for an event `f` picks.
+    ///
+    /// Everything else that arrives meanwhile is kept for `get_event`.
+    fn wait_for<T>(&self, deadline: Instant, mut f: impl FnMut(&Event) -> Option<T>) -> Option<T> {
+        loop {
+            while let Some(event) = self.conn.poll_for_event().ok()? {
+                if let Some(found) = f(&event) {
+                    return Some(found);
+                }
+                match event {
+                    Event::SelectionRequest(event) => self.send_selection(event),
+                    event => self.pending.borrow_mut().push_back(event),
+                }
+            }
+            let timeout = deadline.checked_duration_since(Instant::now())?;
+            let timeout = PollTimeout::try_from(timeout).unwrap_or(PollTimeout::MAX);
+            let mut fds = [PollFd::new(self.conn.stream().as_fd(), PollFlags::POLLIN)];
+            match poll(&mut fds, timeout) {
+                Ok(_) | Err(Errno::EINTR) => {}
+                Err(_) => return None,
+            }
+        }
+    }
+    /// Answer another client asking for a selection we own.
+    pub(super) fn send_selection(&self, event: SelectionRequestEvent) {
+        // Obsolete clients leave the property empty.
+        let property = if event.property == NONE {
+            event.target
+        } else {
+            event.property
+        };
+        let text = self.selections.borrow().get(&event.selection).cloned();
+        let property = match text {
+            Some(_) if event.target == self.atoms.TARGETS => self
+                .conn
+                .change_property32(
+                    PropMode::REPLACE,
+                    event.requestor,
+                    property,
+                    AtomEnum::ATOM,
+                    &[
+                        self.atoms.TARGETS,
+                        self.atoms.UTF8_STRING,
+                        AtomEnum::STRING.into(),
+                    ],
+                )
+                .map_or(NONE, |_| property),
+            Some(text)
+                if event.target == self.atoms.UTF8_STRING
+                    || event.target == u32::from(AtomEnum::STRING) =>
+            {
+                self.conn
+                    .change_property8(
+                        PropMode::REPLACE,
+                        event.requestor,
+                        property,
+                        event.target,
+                        text.as_bytes(),
+                    )
+                    .map_or(NONE, |_| property)
+            }
+            _ => NONE,
+        };
+        let notify = SelectionNotifyEvent {
+            response_type: SELECTION_NOTIFY_EVENT,
+            sequence: 0,
+            time: event.time,
+            requestor: event.requestor,
+            selection: event.selection,
+            target: event.target,
+            property,
+        };
+        let _ = self
+            .conn
+            .send_event(false, event.requestor, EventMask::NO_EVENT, notify);
+        let _ = self.conn.flush();
+    }
+}
@@ -0,0 +1,128 @@
+use std::{fmt::Write as _, io};
+
+use x11rb::{
+    protocol::xkb::{self, ConnectionExt as _, GetMapReply},
+    rust_connection::RustConnection,
+};
+
+/// Names of the real modifiers, by bit.
+const MODS: [&str; 8] = [
+    "Shift", "Lock", "Control", "Mod1", "Mod2", "Mod3", "Mod4", "Mod5",
+];
+
+/// Keymap of the core keyboard as the server has it, at its current state.
+pub(super) fn load_keymap(conn: &RustConnection) -> crate::Result<xkbcommon_rs::State> {
+    let device = xkb::ID::USE_CORE_KBD.into();
+    let parts = xkb::MapPart::KEY_TYPES | xkb::MapPart::KEY_SYMS;
+    let map = conn
+        .xkb_get_map(
+            device,
+            parts,
+            0u16.into(),
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0u16.into(),
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+        )?
+        .reply()?;
+    let context = xkbcommon_rs::Context::new(0).map_err(|e| io::Error::other(e.to_string()))?;
+    let keymap = xkbcommon_rs::Keymap::new_from_string(
+        context,
+        &keymap_string(&map),
+        xkbcommon_rs::KeymapFormat::TextV1,
+        0,
+    )
+    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+    let mut state = xkbcommon_rs::State::new(keymap);
+
+    let current = conn.xkb_get_state(device)?.reply()?;
+    state.update_mask(
+        current.base_mods.into(),
+        current.latched_mods.into(),
+        current.locked_mods.into(),
+        current.base_group as _,
+        current.latched_group as _,
+        u32::from(current.locked_group) as _,
+    );
+    Ok(state)
+}
+
+/// `mask` as written in a keymap, like `Shift+Mod2`.
+fn mods(mask: u16) -> String {
+    let names: Vec<_> = (0..8)
+        .filter(|bit| mask & 1 << bit != 0)
+        .map(|bit| MODS[bit])
+        .collect();
+    match names.is_empty() {
+        true => "none".to_owned(),
+        false => names.join("+"),
+    }
+}
+
+/// The types and symbols of `map` as a keymap to compile. The server already resolved virtual
+/// modifiers, and its state is followed as is, so neither those nor actions are needed.
+fn keymap_string(map: &GetMapReply) -> String {
+    let mut out = String::from("xkb_keymap {\nxkb_keycodes {\n");
+    let _ = writeln!(out, "minimum = {};", map.min_key_code);
+    let _ = writeln!(out, "maximum = {};", map.max_key_code);
+    for code in map.min_key_code..=map.max_key_code {
+        let _ = writeln!(out, "<K{code}> = {code};");
+    }
+
+    out.push_str("};\nxkb_types {\n");
+    let types = map.map.types_rtrn.as_deref().unwrap_or_default();
+    for (idx, ty) in types.iter().enumerate() {
+        let _ = writeln!(out, "type \"T{idx}\" {{");
+        let _ = writeln!(out, "modifiers = {};", mods(ty.mods_mask.into()));
+        for entry in ty.map.iter().filter(|e| e.active) {
+            let _ = writeln!(
+                out,
+                "map[{}] = {};",
+                mods(entry.mods_mask.into()),
+                entry.level + 1
+            );
+        }
+        out.push_str("};\n");
+    }
+
+    out.push_str("};\nxkb_compat {\n};\nxkb_symbols {\n");
+    let syms = map.map.syms_rtrn.as_deref().unwrap_or_default();
+    for (code, key) in (map.first_key_sym..).zip(syms) {
+        let width = key.width as usize;
+        let groups: Vec<_> = (0..(key.group_info & 0x0f) as usize)
+            .filter_map(|group| {
+                let ty = key.kt_index[group];
+                let levels = types.get(ty as usize)?.num_levels as usize;
+                let syms = key.syms.get(group * width..)?;
+                let syms: Vec<_> = syms[..levels.min(width)]
+                    .iter()
+                    .map(|&sym| match sym {
+                        0 => "NoSymbol".to_owned(),
+                        sym => format!("{sym:#x}"),
+                    })
+                    .collect();
+                Some(format!(
+                    "type[{0}] = \"T{ty}\", symbols[{0}] = [ {1} ]",
+                    group + 1,
+                    syms.join(", ")
+                ))
+            })
+            .collect();
+        if !groups.is_empty() {
+            let _ = writeln!(out, "key <K{code}> {{ {} }};", groups.join(", "));
+        }
+    }
+    out.push_str("};\n};\n");
+    out
+}
@@ -1,6 +1,14 @@
 mod app;
+mod clipboard;
 mod cursor;
 pub(super) mod error;
+mod keymap;
+mod scroll;
 mod window;
+mod xim;
 pub(super) use app::App;
 pub(super) use window::Window;
+
+pub(super) fn has_x11() -> bool {
+    !std::env::var("DISPLAY").unwrap_or_default().is_empty()
+}
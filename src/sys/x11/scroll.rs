@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+
+use x11rb::protocol::{
+    xinput::{self, ConnectionExt as _},
+    xproto::ButtonPressEvent,
+};
+
+use crate::event::{Event, RawEvent, WidgetEvent};
+
+use super::App;
+
+/// A scroll axis of a pointer, from XInput 2.1.
+struct Valuator {
+    horizontal: bool,
+    /// Distance of one wheel notch.
+    increment: f64,
+    /// Value at the last motion, `None` until one is seen.
+    last: Option<f64>,
+}
+
+/// Smooth scrolling through XInput 2.1, with the core wheel buttons as fallback.
+#[derive(Default)]
+pub(super) struct Scroll {
+    /// Whether XInput 2.1 is there.
+    smooth: bool,
+    /// By source device and valuator number.
+    valuators: HashMap<(u16, u16), Valuator>,
+    /// Device behind the last XInput motion, which the core events come from too.
+    source: Option<u16>,
+}
+
+impl Scroll {
+    /// Whether the core wheel buttons are emulated from valuators we follow, for that axis.
+    fn emulated(&self, horizontal: bool) -> bool {
+        let Some(source) = self.source.filter(|_| self.smooth) else {
+            return false;
+        };
+        self.valuators
+            .iter()
+            .any(|(&(device, _), valuator)| device == source && valuator.horizontal == horizontal)
+    }
+}
+
+fn fp3232(value: xinput::Fp3232) -> f64 {
+    value.integral as f64 + value.frac as f64 / (1u64 << 32) as f64
+}
+
+impl App {
+    pub(super) fn init_scroll(&self) -> crate::Result<()> {
+        let Some(version) = self
+            .conn
+            .xinput_xi_query_version(2, 1)
+            .ok()
+            .and_then(|c| c.reply().ok())
+        else {
+            return Ok(());
+        };
+        if (version.major_version, version.minor_version) < (2, 1) {
+            return Ok(());
+        }
+        self.scroll.borrow_mut().smooth = true;
+        // Devices come and go, and change their axes.
+        self.conn.xinput_xi_select_events(
+            self.screen.root,
+            &[xinput::EventMask {
+                deviceid: xinput::Device::ALL.into(),
+                mask: vec![xinput::XIEventMask::HIERARCHY | xinput::XIEventMask::DEVICE_CHANGED],
+            }],
+        )?;
+        self.load_scroll_valuators()
+    }
+    pub(super) fn load_scroll_valuators(&self) -> crate::Result<()> {
+        let devices = self
+            .conn
+            .xinput_xi_query_device(xinput::Device::ALL)?
+            .reply()?;
+        let mut scroll = self.scroll.borrow_mut();
+        scroll.valuators.clear();
+        for device in devices.infos {
+            for class in device.classes {
+                if let xinput::DeviceClassData::Scroll(class) = class.data {
+                    let increment = fp3232(class.increment);
+                    scroll.valuators.insert(
+                        (device.deviceid, class.number),
+                        Valuator {
+                            horizontal: class.scroll_type == xinput::ScrollType::HORIZONTAL,
+                            increment: if increment == 0.0 { 1.0 } else { increment },
+                            last: None,
+                        },
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Pointer motion of `window` comes through XInput then, core motion stops.
+    pub(super) fn select_scroll(&self, window: u32) -> crate::Result<()> {
+        if self.scroll.borrow().smooth {
+            self.conn.xinput_xi_select_events(
+                window,
+                &[xinput::EventMask {
+                    deviceid: xinput::Device::ALL_MASTER.into(),
+                    mask: vec![xinput::XIEventMask::MOTION | xinput::XIEventMask::ENTER],
+                }],
+            )?;
+        }
+        Ok(())
+    }
+    /// The valuators may have moved while the pointer was away.
+    pub(super) fn reset_scroll(&self) {
+        for valuator in self.scroll.borrow_mut().valuators.values_mut() {
+            valuator.last = None;
+        }
+    }
+    /// Move to the pointer, followed by a queued scroll if a scroll valuator changed.
+    pub(super) fn xi_motion(&self, event: &xinput::MotionEvent) -> RawEvent {
+        let (x, y) = (event.event_x >> 16, event.event_y >> 16);
+        let mut scroll = self.scroll.borrow_mut();
+        scroll.source = Some(event.sourceid);
+        let (mut dx, mut dy) = (0.0, 0.0);
+        let mut scrolled = false;
+        let set = (0..event.valuator_mask.len() as u16 * 32)
+            .filter(|&i| event.valuator_mask[i as usize / 32] & (1 << (i % 32)) != 0);
+        for (number, value) in set.zip(&event.axisvalues) {
+            let Some(valuator) = scroll.valuators.get_mut(&(event.sourceid, number)) else {
+                continue;
+            };
+            let value = fp3232(*value);
+            if let Some(last) = valuator.last.replace(value) {
+                let delta = ((value - last) / valuator.increment) as f32;
+                if valuator.horizontal {
+                    dx += delta;
+                } else {
+                    dy += delta;
+                }
+                scrolled = true;
+            }
+        }
+        if scrolled {
+            self.queued.borrow_mut().push_back(RawEvent {
+                window: event.event as u64,
+                event: Event::Widget(WidgetEvent::Scroll {
+                    x,
+                    y,
+                    dx,
+                    dy,
+                    // Wheels move a whole notch at a time, touchpads anything.
+                    discrete: dx.fract() == 0.0 && dy.fract() == 0.0,
+                }),
+            });
+        }
+        RawEvent {
+            window: event.event as u64,
+            event: Event::Widget(WidgetEvent::Move(x, y)),
+        }
+    }
+    /// Scroll of a press of the core buttons 4 to 7, the wheel. `None` if they're emulated from
+    /// valuators of the device, which scroll already.
+    pub(super) fn wheel_button(&self, event: &ButtonPressEvent) -> Option<WidgetEvent> {
+        let (dx, dy) = match event.detail {
+            4 => (0.0, -1.0),
+            5 => (0.0, 1.0),
+            6 => (-1.0, 0.0),
+            _ => (1.0, 0.0),
+        };
+        if self.scroll.borrow().emulated(dx != 0.0) {
+            return None;
+        }
+        Some(WidgetEvent::Scroll {
+            x: event.event_x as i32,
+            y: event.event_y as i32,
+            dx,
+            dy,
+            discrete: true,
+        })
+    }
+}
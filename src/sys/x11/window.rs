@@ -1,6 +1,6 @@
 use std::{
     borrow::Cow,
-    cell::RefCell,
+    cell::{Cell, RefCell},
     rc::{Rc, Weak},
 };
 
@@ -9,9 +9,10 @@ use x11rb::{
     COPY_DEPTH_FROM_PARENT, COPY_FROM_PARENT,
     connection::Connection as _,
     image::{BitsPerPixel, Image, ImageOrder, ScanlinePad},
+    properties::{WmSizeHints, WmSizeHintsSpecification},
     protocol::xproto::{
-        AtomEnum, ChangeWindowAttributesAux, ConnectionExt as _, CreateGCAux, CreateWindowAux,
-        EventMask, GcontextWrapper, PropMode, WindowClass, WindowWrapper,
+        AtomEnum, ChangeWindowAttributesAux, ConfigureWindowAux, ConnectionExt as _, CreateGCAux,
+        CreateWindowAux, EventMask, GcontextWrapper, PropMode, WindowClass, WindowWrapper,
     },
     rust_connection::RustConnection,
     wrapper::ConnectionExt as _,
@@ -21,26 +22,29 @@ use crate::event::WindowState;
 
 use super::App;
 pub(crate) struct Window {
-    app: Weak<App>,
+    pub(super) app: Weak<App>,
     pub(super) window: WindowWrapper<Rc<RustConnection>>,
     pub(super) gc: GcontextWrapper<Rc<RustConnection>>,
     pub(super) size: RefCell<Size>,
     pub(super) state: RefCell<WindowState>,
     cursor: RefCell<super::cursor::Cursor>,
+    hints: Cell<WmSizeHints>,
+    resizable: bool,
 }
 
 impl Window {
-    pub(crate) fn new(app: &Rc<App>) -> crate::Result<Rc<Self>> {
+    pub(crate) fn new(app: &Rc<App>, options: &crate::WindowOptions) -> crate::Result<Rc<Self>> {
         let conn = app.conn.clone();
         let screen = &app.screen;
+        let pos = options.position.unwrap_or_default();
         let window = WindowWrapper::create_window(
             conn.clone(),
             COPY_DEPTH_FROM_PARENT,
             screen.root,
-            0,
-            0,
-            800,
-            600,
+            pos.x as _,
+            pos.y as _,
+            options.size.w as _,
+            options.size.h as _,
             0,
             WindowClass::COPY_FROM_PARENT,
             COPY_FROM_PARENT,
@@ -57,23 +61,9 @@ impl Window {
                 )),
         )?;
 
-        let gc = GcontextWrapper::create_gc(conn.clone(), window.window(), &CreateGCAux::new())?;
+        app.select_scroll(window.window())?;
 
-        let title = "Simple Window";
-        conn.change_property8(
-            PropMode::REPLACE,
-            window.window(),
-            AtomEnum::WM_NAME,
-            AtomEnum::STRING,
-            title.as_bytes(),
-        )?;
-        conn.change_property8(
-            PropMode::REPLACE,
-            window.window(),
-            app.atoms._NET_WM_NAME,
-            app.atoms.UTF8_STRING,
-            title.as_bytes(),
-        )?;
+        let gc = GcontextWrapper::create_gc(conn.clone(), window.window(), &CreateGCAux::new())?;
 
         conn.change_property32(
             PropMode::REPLACE,
@@ -82,28 +72,115 @@ impl Window {
             AtomEnum::ATOM,
             &[app.atoms.WM_DELETE_WINDOW],
         )?;
+        // Instance and class name, both null-terminated.
+        let class = [options.app_id.as_bytes(), options.app_id.as_bytes(), b""].join(&0);
         conn.change_property8(
             PropMode::REPLACE,
             window.window(),
             AtomEnum::WM_CLASS,
             AtomEnum::STRING,
-            b"simple_gui window\0",
+            &class,
         )?;
+        if !options.decorations {
+            // flags, functions, decorations, input mode, status. Only decorations are set.
+            conn.change_property32(
+                PropMode::REPLACE,
+                window.window(),
+                app.atoms._MOTIF_WM_HINTS,
+                app.atoms._MOTIF_WM_HINTS,
+                &[2, 0, 0, 0, 0],
+            )?;
+        }
 
-        conn.map_window(window.window())?;
-        conn.flush()?;
+        let mut hints = WmSizeHints::new();
+        hints.position = options
+            .position
+            .map(|p| (WmSizeHintsSpecification::ProgramSpecified, p.x, p.y));
+        hints.size = Some((
+            WmSizeHintsSpecification::ProgramSpecified,
+            options.size.w as _,
+            options.size.h as _,
+        ));
+        hints.min_size = options.min_size.map(|s| (s.w as _, s.h as _));
+        hints.max_size = options.max_size.map(|s| (s.w as _, s.h as _));
 
         let win = Rc::new(Self {
             app: Rc::downgrade(app),
             window,
             gc,
-            size: RefCell::new(Size::new(800, 600)),
+            size: RefCell::new(options.size),
             state: RefCell::new(WindowState::Suspended),
             cursor: RefCell::new(super::cursor::Cursor::new(conn.clone(), app.screen.root)?),
+            hints: Cell::new(hints),
+            resizable: options.resizable,
         });
+        win.set_title(&options.title)?;
+        win.update_hints()?;
+
+        conn.map_window(win.window.window())?;
+        conn.flush()?;
+
         app.windows.borrow_mut().push(win.clone());
         Ok(win)
     }
+    /// Send the size hints, pinning the size if the window isn't resizable.
+    fn update_hints(&self) -> crate::Result<()> {
+        let mut hints = self.hints.get();
+        if !self.resizable {
+            let size = *self.size.borrow();
+            hints.min_size = Some((size.w as _, size.h as _));
+            hints.max_size = hints.min_size;
+        }
+        let conn = &self.app.upgrade().unwrap().conn;
+        hints.set_normal_hints(&**conn, self.window.window())?;
+        conn.flush()?;
+        Ok(())
+    }
+    pub(crate) fn set_title(&self, title: &str) -> crate::Result<()> {
+        let app = self.app.upgrade().unwrap();
+        let conn = &app.conn;
+        conn.change_property8(
+            PropMode::REPLACE,
+            self.window.window(),
+            AtomEnum::WM_NAME,
+            AtomEnum::STRING,
+            title.as_bytes(),
+        )?;
+        conn.change_property8(
+            PropMode::REPLACE,
+            self.window.window(),
+            app.atoms._NET_WM_NAME,
+            app.atoms.UTF8_STRING,
+            title.as_bytes(),
+        )?;
+        conn.flush()?;
+        Ok(())
+    }
+    pub(crate) fn set_size(&self, size: Size) -> crate::Result<()> {
+        let conn = &self.app.upgrade().unwrap().conn;
+        conn.configure_window(
+            self.window.window(),
+            &ConfigureWindowAux::new().width(size.w).height(size.h),
+        )?;
+        if !self.resizable {
+            *self.size.borrow_mut() = size;
+            self.update_hints()?;
+        }
+        conn.flush()?;
+        Ok(())
+    }
+    pub(crate) fn set_min_size(&self, size: Option<Size>) -> crate::Result<()> {
+        let mut hints = self.hints.get();
+        hints.min_size = size.map(|s| (s.w as _, s.h as _));
+        self.hints.set(hints);
+        self.update_hints()
+    }
+    pub(crate) fn set_max_size(&self, size: Option<Size>) -> crate::Result<()> {
+        let mut hints = self.hints.get();
+        hints.max_size = size.map(|s| (s.w as _, s.h as _));
+        self.hints.set(hints);
+        self.update_hints()
+    }
     pub(crate) fn draw(&self, buf: Buffer) -> crate::Result<()> {
         let data = &**buf.data();
         let img = Image::new(
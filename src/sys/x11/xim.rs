@@ -0,0 +1,581 @@
+use std::collections::{HashMap, VecDeque};
+
+use lite_graphics::Rect;
+use x11rb::{
+    NONE,
+    connection::Connection as _,
+    protocol::xproto::{
+        AtomEnum, ClientMessageEvent, ConnectionExt as _, EventMask, KEY_PRESS_EVENT,
+        KeyPressEvent, PropMode,
+    },
+    wrapper::ConnectionExt as _,
+    x11_utils::TryParse,
+};
+
+use crate::{
+    event::{Event, RawEvent, WindowEvent},
+    sys::linux,
+};
+
+use super::{App, Window};
+
+// Request opcodes of the XIM protocol.
+const XIM_CONNECT: u8 = 1;
+const XIM_CONNECT_REPLY: u8 = 2;
+const XIM_ERROR: u8 = 20;
+const XIM_OPEN: u8 = 30;
+const XIM_OPEN_REPLY: u8 = 31;
+const XIM_SET_EVENT_MASK: u8 = 37;
+const XIM_ENCODING_NEGOTIATION: u8 = 38;
+const XIM_ENCODING_NEGOTIATION_REPLY: u8 = 39;
+const XIM_CREATE_IC: u8 = 50;
+const XIM_CREATE_IC_REPLY: u8 = 51;
+const XIM_DESTROY_IC: u8 = 52;
+const XIM_SET_IC_VALUES: u8 = 54;
+const XIM_SET_IC_FOCUS: u8 = 58;
+const XIM_UNSET_IC_FOCUS: u8 = 59;
+const XIM_FORWARD_EVENT: u8 = 60;
+const XIM_SYNC: u8 = 61;
+const XIM_SYNC_REPLY: u8 = 62;
+const XIM_COMMIT: u8 = 63;
+const XIM_PREEDIT_START: u8 = 73;
+const XIM_PREEDIT_START_REPLY: u8 = 74;
+const XIM_PREEDIT_DRAW: u8 = 75;
+const XIM_PREEDIT_CARET: u8 = 76;
+const XIM_PREEDIT_CARET_REPLY: u8 = 77;
+const XIM_PREEDIT_DONE: u8 = 78;
+
+/// `XIMPreeditCallbacks | XIMStatusNothing`: we draw the preedit ourselves.
+const STYLE_CALLBACKS: u32 = 0x0002 | 0x0400;
+/// `XIMPreeditNothing | XIMStatusNothing`: the input method shows it in its own window.
+const STYLE_NOTHING: u32 = 0x0008 | 0x0400;
+/// `XIMAbsolutePosition` for `XIM_PREEDIT_CARET`.
+const CARET_ABSOLUTE: u32 = 10;
+
+/// Input context of a window.
+struct Ic {
+    id: u16,
+    /// Spot sent to the server, `None` while unfocused.
+    spot: Option<(i16, i16)>,
+    preedit: Vec<char>,
+    caret: usize,
+}
+
+/// Connection to an XIM server, made step by step as its replies arrive.
+#[derive(Default)]
+pub(super) struct Xim {
+    /// Communication window of the server, 0 if there's no input method.
+    server: u32,
+    /// Input method ID, 0 until it's open.
+    im: u16,
+    /// IC attribute IDs by name.
+    attrs: HashMap<String, u16>,
+    /// Whether the server agreed to send UTF-8 rather than compound text.
+    utf8: bool,
+    /// Events the server wants forwarded, as an X event mask.
+    forward_mask: u32,
+    /// Caret spot wanted by every window with a focused input.
+    spots: HashMap<u32, (i16, i16)>,
+    ics: HashMap<u32, Ic>,
+    /// Windows waiting for `XIM_CREATE_IC_REPLY`, in order.
+    creating: VecDeque<u32>,
+    /// Whether the server refused callbacks, so the preedit is its business.
+    style_nothing: bool,
+    /// Start of a message split over `_XIM_MOREDATA`.
+    partial: Vec<u8>,
+}
+
+fn read16(data: &[u8], at: usize) -> u16 {
+    data.get(at..at + 2)
+        .map_or(0, |b| u16::from_ne_bytes([b[0], b[1]]))
+}
+
+fn read32(data: &[u8], at: usize) -> u32 {
+    data.get(at..at + 4)
+        .map_or(0, |b| u32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn pad(len: usize) -> usize {
+    (4 - len % 4) % 4
+}
+
+/// Append an attribute ID with its value, padded.
+fn push_attr(out: &mut Vec<u8>, id: u16, value: &[u8]) {
+    out.extend(id.to_ne_bytes());
+    out.extend((value.len() as u16).to_ne_bytes());
+    out.extend(value);
+    out.resize(out.len() + pad(value.len()), 0);
+}
+
+/// The parts of compound text we can read: ASCII, Latin-1 and UTF-8 segments.
+fn from_compound_text(data: &[u8]) -> String {
+    let mut text = String::new();
+    let mut known = true;
+    let mut rest = data;
+    while let Some(&byte) = rest.first() {
+        if let Some(utf8) = rest.strip_prefix(b"\x1b%G") {
+            let end = utf8
+                .windows(3)
+                .position(|w| w == b"\x1b%@")
+                .unwrap_or(utf8.len());
+            text.push_str(&String::from_utf8_lossy(&utf8[..end]));
+            rest = utf8.get(end + 3..).unwrap_or_default();
+        } else if byte == 0x1b {
+            // Intermediate bytes, then a final byte naming the charset.
+            let len = rest[1..]
+                .iter()
+                .position(|b| !(0x20..0x30).contains(b))
+                .map_or(rest.len(), |i| i + 2);
+            let seq = &rest[..len.min(rest.len())];
+            known = matches!(seq, b"\x1b(B" | b"\x1b-A");
+            rest = &rest[seq.len()..];
+        } else {
+            if known || byte < 0x20 {
+                text.push(byte as char);
+            }
+            rest = &rest[1..];
+        }
+    }
+    text
+}
+
+impl App {
+    /// Start talking to the input method named by `XMODIFIERS`, if any. Replies come with the
+    /// other events.
+    pub(super) fn connect_xim(&self) -> Option<()> {
+        let modifiers = std::env::var("XMODIFIERS").ok()?;
+        let name = modifiers.split('@').find_map(|m| m.strip_prefix("im="))?;
+        if name.is_empty() || name == "none" {
+            return None;
+        }
+        let atom = self
+            .conn
+            .intern_atom(true, format!("@server={name}").as_bytes())
+            .ok()?
+            .reply()
+            .ok()?
+            .atom;
+        if atom == NONE {
+            return None;
+        }
+        let owner = self
+            .conn
+            .get_selection_owner(atom)
+            .ok()?
+            .reply()
+            .ok()?
+            .owner;
+        if owner == NONE {
+            return None;
+        }
+        let event = ClientMessageEvent::new(
+            32,
+            owner,
+            self.atoms._XIM_XCONNECT,
+            [self.selection_window, 0, 0, 0, 0],
+        );
+        self.conn
+            .send_event(false, owner, EventMask::NO_EVENT, event)
+            .ok()?;
+        self.conn.flush().ok()
+    }
+    fn xim_send(&self, server: u32, opcode: u8, body: &[u8]) {
+        let mut msg = vec![opcode, 0];
+        msg.extend((body.len().div_ceil(4) as u16).to_ne_bytes());
+        msg.extend(body);
+        msg.resize(msg.len() + pad(msg.len()), 0);
+        // Short messages fit in the event, longer ones go through a property.
+        let event = if msg.len() <= 20 {
+            let mut data = [0; 20];
+            data[..msg.len()].copy_from_slice(&msg);
+            ClientMessageEvent::new(8, server, self.atoms._XIM_PROTOCOL, data)
+        } else {
+            let _ = self.conn.change_property8(
+                PropMode::APPEND,
+                server,
+                self.atoms.PARSLAY_XIM,
+                AtomEnum::STRING,
+                &msg,
+            );
+            ClientMessageEvent::new(
+                32,
+                server,
+                self.atoms._XIM_PROTOCOL,
+                [msg.len() as u32, self.atoms.PARSLAY_XIM, 0, 0, 0],
+            )
+        };
+        let _ = self
+            .conn
+            .send_event(false, server, EventMask::NO_EVENT, event);
+        let _ = self.conn.flush();
+    }
+    /// Handle a client message sent to our window by the input method.
+    pub(super) fn handle_xim(&self, event: ClientMessageEvent) {
+        if event.type_ == self.atoms._XIM_XCONNECT {
+            let server = event.data.as_data32()[0];
+            self.xim.borrow_mut().server = server;
+            let order = if cfg!(target_endian = "little") {
+                b'l'
+            } else {
+                b'B'
+            };
+            let mut body = vec![order, 0];
+            body.extend(1u16.to_ne_bytes());
+            body.extend(0u16.to_ne_bytes());
+            body.extend(0u16.to_ne_bytes());
+            self.xim_send(server, XIM_CONNECT, &body);
+            return;
+        }
+        let data = if event.format == 32 {
+            let [len, property, ..] = event.data.as_data32();
+            let Ok(reply) = self
+                .conn
+                .get_property(
+                    true,
+                    self.selection_window,
+                    property,
+                    AtomEnum::ANY,
+                    0,
+                    len.div_ceil(4),
+                )
+                .map_err(drop)
+                .and_then(|c| c.reply().map_err(drop))
+            else {
+                return;
+            };
+            reply.value
+        } else {
+            let mut xim = self.xim.borrow_mut();
+            xim.partial.extend(event.data.as_data8());
+            if event.type_ == self.atoms._XIM_MOREDATA {
+                return;
+            }
+            std::mem::take(&mut xim.partial)
+        };
+
+        let mut rest = &data[..];
+        while rest.len() >= 4 && rest[0] != 0 {
+            let len = read16(rest, 2) as usize * 4;
+            let Some(body) = rest.get(4..4 + len) else {
+                break;
+            };
+            self.handle_xim_message(rest[0], body);
+            rest = &rest[4 + len..];
+        }
+    }
+    fn handle_xim_message(&self, opcode: u8, body: &[u8]) {
+        let mut xim = self.xim.borrow_mut();
+        let server = xim.server;
+        let (im, ic) = (read16(body, 0), read16(body, 2));
+        let ids = body.get(..4).unwrap_or_default();
+        let window = xim
+            .ics
+            .iter()
+            .find(|(_, i)| i.id == ic)
+            .map_or(0, |(&w, _)| w);
+        match opcode {
+            XIM_CONNECT_REPLY => {
+                let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+                    .into_iter()
+                    .filter_map(|v| std::env::var(v).ok())
+                    .find(|v| !v.is_empty())
+                    .unwrap_or_else(|| "C".to_owned());
+                let locale = locale.split(['.', '@']).next().unwrap_or("C");
+                let mut body = vec![locale.len() as u8];
+                body.extend(locale.as_bytes());
+                self.xim_send(server, XIM_OPEN, &body);
+            }
+            XIM_OPEN_REPLY => {
+                xim.im = im;
+                // IM attributes, then IC attributes: ID, type, name length, name, padding.
+                let start = 8 + read16(body, 2) as usize;
+                let end = (start + read16(body, start - 4) as usize).min(body.len());
+                let mut pos = start;
+                while pos + 6 <= end {
+                    let len = read16(body, pos + 4) as usize;
+                    let Some(name) = body.get(pos + 6..pos + 6 + len) else {
+                        break;
+                    };
+                    let name = String::from_utf8_lossy(name).into_owned();
+                    xim.attrs.insert(name, read16(body, pos));
+                    pos += 6 + len + pad(2 + len);
+                }
+                // Key presses and releases until told otherwise.
+                xim.forward_mask = 0b11;
+
+                let encodings: &[&str] = &["UTF-8", "COMPOUND_TEXT"];
+                let mut names = vec![];
+                for name in encodings {
+                    names.push(name.len() as u8);
+                    names.extend(name.as_bytes());
+                }
+                let mut body = im.to_ne_bytes().to_vec();
+                body.extend((names.len() as u16).to_ne_bytes());
+                body.extend(&names);
+                body.resize(body.len() + pad(names.len()), 0);
+                body.extend([0; 4]);
+                self.xim_send(server, XIM_ENCODING_NEGOTIATION, &body);
+
+                let windows: Vec<_> = xim.spots.keys().copied().collect();
+                for window in windows {
+                    self.sync_xim(&mut xim, window);
+                }
+            }
+            XIM_ENCODING_NEGOTIATION_REPLY => {
+                xim.utf8 = read16(body, 2) == 0 && read16(body, 4) == 0;
+            }
+            XIM_SET_EVENT_MASK => xim.forward_mask = read32(body, 4),
+            XIM_CREATE_IC_REPLY => {
+                let Some(window) = xim.creating.pop_front() else {
+                    return;
+                };
+                xim.ics.insert(
+                    window,
+                    Ic {
+                        id: ic,
+                        spot: None,
+                        preedit: vec![],
+                        caret: 0,
+                    },
+                );
+                self.sync_xim(&mut xim, window);
+            }
+            XIM_ERROR => {
+                // Most likely our input style, retry once with the simplest one.
+                let ic_valid = read16(body, 4) & 2 != 0;
+                if !ic_valid
+                    && !xim.style_nothing
+                    && let Some(window) = xim.creating.pop_front()
+                {
+                    xim.style_nothing = true;
+                    self.sync_xim(&mut xim, window);
+                }
+            }
+            XIM_FORWARD_EVENT => {
+                if let Some(data) = body.get(8..40)
+                    && let Ok((event, _)) = KeyPressEvent::try_parse(data)
+                {
+                    // Before the text it types.
+                    let at = self.queued.borrow().len();
+                    let pressed = event.response_type & 0x7f == KEY_PRESS_EVENT;
+                    let event = self.key_event(&event, pressed);
+                    self.queued.borrow_mut().insert(at, event);
+                }
+                if read16(body, 4) & 1 != 0 {
+                    self.xim_send(server, XIM_SYNC_REPLY, ids);
+                }
+            }
+            XIM_SYNC => self.xim_send(server, XIM_SYNC_REPLY, ids),
+            XIM_COMMIT => {
+                let flag = read16(body, 4);
+                let text = if flag & 2 != 0 {
+                    let at = if flag & 4 != 0 { 12 } else { 6 };
+                    let len = read16(body, at) as usize;
+                    body.get(at + 2..at + 2 + len).map(|s| {
+                        if xim.utf8 {
+                            String::from_utf8_lossy(s).into_owned()
+                        } else {
+                            from_compound_text(s)
+                        }
+                    })
+                } else if flag & 4 != 0 {
+                    linux::keysym_to_text(read32(body, 8))
+                } else {
+                    None
+                };
+                if let Some(text) = text {
+                    self.queued.borrow_mut().push_back(RawEvent {
+                        window: window as u64,
+                        event: Event::Window(WindowEvent::Text(text)),
+                    });
+                }
+                if flag & 1 != 0 {
+                    self.xim_send(server, XIM_SYNC_REPLY, ids);
+                }
+            }
+            XIM_PREEDIT_START => {
+                let mut reply = ids.to_vec();
+                reply.extend((-1i32).to_ne_bytes());
+                self.xim_send(server, XIM_PREEDIT_START_REPLY, &reply);
+            }
+            XIM_PREEDIT_DRAW => {
+                let utf8 = xim.utf8;
+                let Some(ic) = xim.ics.get_mut(&window) else {
+                    return;
+                };
+                let first = read32(body, 8) as usize;
+                let len = read32(body, 12) as usize;
+                let text = if read32(body, 16) & 1 != 0 {
+                    String::new()
+                } else {
+                    let n = read16(body, 20) as usize;
+                    let raw = body.get(22..22 + n).unwrap_or_default();
+                    if utf8 {
+                        String::from_utf8_lossy(raw).into_owned()
+                    } else {
+                        from_compound_text(raw)
+                    }
+                };
+                let first = first.min(ic.preedit.len());
+                let end = (first + len).min(ic.preedit.len());
+                ic.preedit.splice(first..end, text.chars());
+                ic.caret = (read32(body, 4) as usize).min(ic.preedit.len());
+                self.queue_preedit(window, ic);
+            }
+            XIM_PREEDIT_CARET => {
+                let Some(ic) = xim.ics.get_mut(&window) else {
+                    return;
+                };
+                if read32(body, 8) == CARET_ABSOLUTE {
+                    ic.caret = (read32(body, 4) as usize).min(ic.preedit.len());
+                    self.queue_preedit(window, ic);
+                }
+                let mut reply = ids.to_vec();
+                reply.extend((ic.caret as u32).to_ne_bytes());
+                self.xim_send(server, XIM_PREEDIT_CARET_REPLY, &reply);
+            }
+            XIM_PREEDIT_DONE => {
+                let Some(ic) = xim.ics.get_mut(&window) else {
+                    return;
+                };
+                ic.preedit.clear();
+                ic.caret = 0;
+                self.queue_preedit(window, ic);
+            }
+            _ => {}
+        }
+    }
+    fn queue_preedit(&self, window: u32, ic: &Ic) {
+        let text: String = ic.preedit.iter().collect();
+        let cursor = ic.preedit[..ic.caret].iter().map(|c| c.len_utf8()).sum();
+        self.queued.borrow_mut().push_back(RawEvent {
+            window: window as u64,
+            event: Event::Window(WindowEvent::Preedit(text, Some(cursor))),
+        });
+    }
+    /// Create, focus, move or unfocus the input context of `window` to match its caret.
+    fn sync_xim(&self, xim: &mut Xim, window: u32) {
+        if xim.im == 0 {
+            return;
+        }
+        let (server, im) = (xim.server, xim.im);
+        let spot = xim.spots.get(&window).copied();
+        let preedit_attrs = |(x, y): (i16, i16)| {
+            let mut point = x.to_ne_bytes().to_vec();
+            point.extend(y.to_ne_bytes());
+            let mut nested = vec![];
+            if let Some(&id) = xim.attrs.get("spotLocation") {
+                push_attr(&mut nested, id, &point);
+            }
+            let mut attrs = vec![];
+            if let Some(&id) = xim.attrs.get("preeditAttributes") {
+                push_attr(&mut attrs, id, &nested);
+            }
+            attrs
+        };
+
+        let Some(ic) = xim.ics.get(&window) else {
+            let Some(spot) = spot.filter(|_| !xim.creating.contains(&window)) else {
+                return;
+            };
+            let style = if xim.style_nothing {
+                STYLE_NOTHING
+            } else {
+                STYLE_CALLBACKS
+            };
+            let mut attrs = vec![];
+            for (name, value) in [
+                ("inputStyle", style),
+                ("clientWindow", window),
+                ("focusWindow", window),
+            ] {
+                if let Some(&id) = xim.attrs.get(name) {
+                    push_attr(&mut attrs, id, &value.to_ne_bytes());
+                }
+            }
+            attrs.extend(preedit_attrs(spot));
+            let mut body = im.to_ne_bytes().to_vec();
+            body.extend((attrs.len() as u16).to_ne_bytes());
+            body.extend(attrs);
+            self.xim_send(server, XIM_CREATE_IC, &body);
+            xim.creating.push_back(window);
+            return;
+        };
+        if ic.spot == spot {
+            return;
+        }
+        let mut ids = im.to_ne_bytes().to_vec();
+        ids.extend(ic.id.to_ne_bytes());
+        match spot {
+            Some(spot) => {
+                if ic.spot.is_none() {
+                    self.xim_send(server, XIM_SET_IC_FOCUS, &ids);
+                }
+                let attrs = preedit_attrs(spot);
+                let mut body = ids;
+                body.extend((attrs.len() as u16).to_ne_bytes());
+                body.extend([0; 2]);
+                body.extend(attrs);
+                self.xim_send(server, XIM_SET_IC_VALUES, &body);
+            }
+            None => self.xim_send(server, XIM_UNSET_IC_FOCUS, &ids),
+        }
+        if let Some(ic) = xim.ics.get_mut(&window) {
+            ic.spot = spot;
+        }
+    }
+    /// Send a key event to the input method if it's listening. Returns whether it took it.
+    pub(super) fn forward_to_xim(&self, event: &KeyPressEvent) -> bool {
+        let xim = self.xim.borrow();
+        let Some(ic) = xim.ics.get(&event.event).filter(|ic| ic.spot.is_some()) else {
+            return false;
+        };
+        let mask = if event.response_type & 0x7f == KEY_PRESS_EVENT {
+            1
+        } else {
+            2
+        };
+        if xim.forward_mask & mask == 0 {
+            return false;
+        }
+        let mut body = xim.im.to_ne_bytes().to_vec();
+        body.extend(ic.id.to_ne_bytes());
+        // Synchronous, and the high bits of the sequence number.
+        body.extend(1u16.to_ne_bytes());
+        body.extend(0u16.to_ne_bytes());
+        body.extend(<[u8; 32]>::from(event));
+        self.xim_send(xim.server, XIM_FORWARD_EVENT, &body);
+        true
+    }
+    pub(super) fn set_xim_spot(&self, window: u32, spot: Option<(i16, i16)>) {
+        let mut xim = self.xim.borrow_mut();
+        if xim.server == 0 {
+            return;
+        }
+        match spot {
+            Some(spot) => xim.spots.insert(window, spot),
+            None => xim.spots.remove(&window),
+        };
+        self.sync_xim(&mut xim, window);
+    }
+    pub(super) fn forget_xim_window(&self, window: u32) {
+        let mut xim = self.xim.borrow_mut();
+        xim.spots.remove(&window);
+        if let Some(ic) = xim.ics.remove(&window) {
+            let mut body = xim.im.to_ne_bytes().to_vec();
+            body.extend(ic.id.to_ne_bytes());
+            self.xim_send(xim.server, XIM_DESTROY_IC, &body);
+        }
+    }
+}
+
+impl Window {
+    pub(crate) fn set_ime_area(&self, area: Option<Rect>) {
+        // The spot is on the baseline, so the popup goes under the caret.
+        let spot = area.map(|r| (r.x as i16, (r.y + r.h as i32) as i16));
+        self.app
+            .upgrade()
+            .unwrap()
+            .set_xim_spot(self.window.window(), spot);
+    }
+}
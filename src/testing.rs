@@ -0,0 +1,242 @@
+//! Drive a UI without a display server.
+//!
+//! ```no_run
+//! use parslay::{event::Button, prelude::*, testing::Harness};
+//!
+//! let counter = RwSignal::new(0);
+//! let ui = Harness::new(move || {
+//!     hstack(4, (dyn_label(move || format!("{counter}")), button("+")))
+//! })
+//! .unwrap();
+//! ui.step().unwrap();
+//! ui.click_text("+", Button::Left);
+//! ui.step().unwrap();
+//! ui.assert_png("tests/snapshots/counter.png", 4);
+//! ```
+
+use std::{
+    env, fs,
+    io::{self, BufWriter},
+    path::Path,
+    rc::Rc,
+};
+
+use lite_graphics::{Buffer, Drawable, Rect};
+
+use crate::{
+    App, ComputedSize, Rgba, WidgetBase,
+    event::{Button, Event, Key, Modifiers, WidgetEvent, WindowEvent},
+    widgets::IntoWidget,
+    window::Window,
+};
+
+/// Set to overwrite golden images instead of comparing against them.
+pub const UPDATE_VAR: &str = "PARSLAY_UPDATE_SNAPSHOTS";
+
+/// A headless app with a single window.
+pub struct Harness {
+    app: Rc<App>,
+    window: Rc<Window>,
+}
+
+impl Harness {
+    /// Mount `view` in a new 800x600 headless window. Call [`Harness::step`] to draw it.
+    pub fn new<V: IntoWidget + 'static>(view: impl FnOnce() -> V + 'static) -> crate::Result<Self> {
+        let app = App::new_headless()?;
+        let window = Window::new(&app)?;
+        window.render(view);
+        Ok(Self { app, window })
+    }
+
+    /// Process every queued event.
+    pub fn step(&self) -> crate::Result<()> {
+        self.app.run()
+    }
+
+    pub fn resize(&self, w: u32, h: u32) {
+        self.push(Event::Window(WindowEvent::Resize(w, h)));
+    }
+    pub fn move_to(&self, x: i32, y: i32) {
+        self.push(Event::Widget(WidgetEvent::Move(x, y)));
+    }
+    pub fn press(&self, button: Button, x: i32, y: i32) {
+        self.push(Event::Widget(WidgetEvent::ButtonPress(button, x, y)));
+    }
+    pub fn release(&self, button: Button, x: i32, y: i32) {
+        self.push(Event::Widget(WidgetEvent::ButtonRelease(button, x, y)));
+    }
+    /// Scroll by `(dx, dy)` wheel notches at `(x, y)`.
+    pub fn scroll(&self, x: i32, y: i32, dx: f32, dy: f32) {
+        self.push(Event::Widget(WidgetEvent::Scroll {
+            x,
+            y,
+            dx,
+            dy,
+            discrete: true,
+        }));
+    }
+    /// Move to, press and release at `(x, y)`.
+    pub fn click(&self, button: Button, x: i32, y: i32) {
+        self.move_to(x, y);
+        self.press(button, x, y);
+        self.release(button, x, y);
+    }
+    /// Click the center of the first widget showing `text`.
+    ///
+    /// Returns `false` if there is no such widget.
+    pub fn click_text(&self, text: &str, button: Button) -> bool {
+        let Some(rect) = self.locate(text) else {
+            return false;
+        };
+        self.click(
+            button,
+            rect.x + rect.w as i32 / 2,
+            rect.y + rect.h as i32 / 2,
+        );
+        true
+    }
+    /// Press and release `key`, typing its text like a keyboard would.
+    pub fn key(&self, mods: Modifiers, key: Key) {
+        self.push(Event::Window(WindowEvent::KeyPress(mods, key)));
+        let text = key.to_string();
+        if mods & (Modifiers::CONTROL | Modifiers::ALT | Modifiers::SUPER) == Modifiers::NONE
+            && text.chars().count() == 1
+            && !text.chars().any(char::is_control)
+        {
+            self.type_text(&text);
+        }
+        self.push(Event::Window(WindowEvent::KeyRelease(mods, key)));
+    }
+    /// Type `text` at once, as an input method would.
+    pub fn type_text(&self, text: &str) {
+        self.push(Event::Window(WindowEvent::Text(text.to_owned())));
+    }
+
+    /// The window the UI is mounted in.
+    pub fn window(&self) -> &Rc<Window> {
+        &self.window
+    }
+
+    fn push(&self, event: Event) {
+        self.window.push_event(event);
+    }
+
+    /// Every widget in the window with its position in window coordinates, in tree order.
+    pub fn widgets(&self) -> Vec<(Rc<dyn WidgetBase>, Rect)> {
+        self.window.widgets()
+    }
+    /// Bounds of the first widget whose text is `text`.
+    pub fn locate(&self, text: &str) -> Option<Rect> {
+        self.widgets()
+            .into_iter()
+            .find(|(w, _)| w.get_text() == text)
+            .map(|(_, rect)| rect)
+    }
+    /// Whether any widget shows `text`.
+    pub fn has_text(&self, text: &str) -> bool {
+        self.locate(text).is_some()
+    }
+
+    /// The last drawn frame. Panics if nothing has been drawn yet.
+    pub fn frame(&self) -> Buffer {
+        self.window
+            .frame()
+            .expect("Nothing was drawn, call `step` first")
+    }
+    pub fn pixel(&self, x: u32, y: u32) -> Rgba {
+        let frame = self.frame();
+        let idx = (y * frame.size().w + x) as usize * 3;
+        let data = &**frame.data();
+        Rgba::from([data[idx], data[idx + 1], data[idx + 2], 255])
+    }
+
+    /// Save the last frame as a PNG.
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> crate::Result<()> {
+        let frame = self.frame();
+        let size = frame.size();
+        if let Some(dir) = path.as_ref().parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = BufWriter::new(fs::File::create(path)?);
+        let mut encoder = png::Encoder::new(file, size.w, size.h);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut w| w.write_image_data(&frame.data()))
+            .map_err(io::Error::other)?;
+        Ok(())
+    }
+
+    /// Compare the last frame to a PNG. Every channel of every pixel may differ by at most `tolerance`.
+    ///
+    /// Returns the number of pixels out of tolerance, or `None` if the sizes don't match.
+    pub fn compare_png<P: AsRef<Path>>(
+        &self,
+        path: P,
+        tolerance: u8,
+    ) -> crate::Result<Option<usize>> {
+        let frame = self.frame();
+        let (size, golden) = load_png(path.as_ref())?;
+        if size != frame.size() {
+            return Ok(None);
+        }
+        let diff = frame
+            .data()
+            .chunks_exact(3)
+            .zip(golden.chunks_exact(3))
+            .filter(|(a, b)| a.iter().zip(*b).any(|(a, b)| a.abs_diff(*b) > tolerance))
+            .count();
+        Ok(Some(diff))
+    }
+
+    /// Panic unless the last frame matches the golden image at `path`.
+    ///
+    /// With [`UPDATE_VAR`] set, the golden image is written instead, missing or not.
+    pub fn assert_png<P: AsRef<Path>>(&self, path: P, tolerance: u8) {
+        let path = path.as_ref();
+        if env::var_os(UPDATE_VAR).is_some() {
+            self.save_png(path).unwrap();
+            return;
+        }
+        if !path.exists() {
+            panic!(
+                "No golden image at {}, set {UPDATE_VAR} to write it",
+                path.display()
+            );
+        }
+        match self.compare_png(path, tolerance).unwrap() {
+            Some(0) => {}
+            Some(n) => panic!("{n} pixels differ from {}", path.display()),
+            None => panic!("Frame size differs from {}", path.display()),
+        }
+    }
+}
+
+/// Load a PNG as 8-bit RGB.
+fn load_png(path: &Path) -> crate::Result<(ComputedSize, Vec<u8>)> {
+    let mut decoder = png::Decoder::new(fs::File::open(path)?);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(io::Error::other)?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).map_err(io::Error::other)?;
+    buf.truncate(info.buffer_size());
+    let data = match info.color_type {
+        png::ColorType::Rgb => buf,
+        png::ColorType::Rgba => buf
+            .chunks_exact(4)
+            .flat_map(|p| [p[0], p[1], p[2]])
+            .collect(),
+        png::ColorType::Grayscale => buf.iter().flat_map(|&p| [p, p, p]).collect(),
+        png::ColorType::GrayscaleAlpha => buf
+            .chunks_exact(2)
+            .flat_map(|p| [p[0], p[0], p[0]])
+            .collect(),
+        png::ColorType::Indexed => {
+            return Err(
+                io::Error::new(io::ErrorKind::InvalidData, "Unexpanded PNG palette").into(),
+            );
+        }
+    };
+    Ok((ComputedSize::new(info.width, info.height), data))
+}
@@ -1,7 +1,8 @@
-use std::{collections::BTreeMap, fmt::Alignment};
+use std::{collections::BTreeMap, fmt::Alignment, ops::Range};
 
 use ab_glyph::{Font, FontArc, GlyphId, PxScaleFont, ScaleFont};
 use unicode_linebreak::{BreakOpportunity, linebreaks};
+use unicode_segmentation::UnicodeSegmentation;
 
 use lite_graphics::{
     Offset, Rect,
@@ -9,6 +10,8 @@ use lite_graphics::{
     draw::Drawable,
 };
 
+use crate::{app::FRAMES, themes::FrameType};
+
 #[derive(Clone)]
 pub struct Text {
     text: String,
@@ -23,6 +26,14 @@ pub struct Text {
     breaks: BTreeMap<usize, (BreakOpportunity, u32)>,
     real_words: BTreeMap<usize, u32>,
     cursor: Option<usize>,
+    /// Where the selection started, the cursor being its other end.
+    anchor: Option<usize>,
+    selection_color: Rgba,
+    /// Text being composed by an input method, shown at the cursor, with its own caret.
+    preedit: String,
+    preedit_cursor: Option<usize>,
+    /// Bytes drawn underlined.
+    underline: Range<usize>,
 }
 
 impl Text {
@@ -39,6 +50,11 @@ impl Text {
             breaks: BTreeMap::new(),
             real_words: BTreeMap::new(),
             cursor: None,
+            anchor: None,
+            selection_color: Rgba::hex("#99c1f1").unwrap(),
+            preedit: String::new(),
+            preedit_cursor: None,
+            underline: 0..0,
         }
     }
 
@@ -117,10 +133,20 @@ impl Text {
         }
         let Some(opportunity) = self.breaks.get_mut(&self.len()) else {
             self.height = height as u32 * line_idx as u32;
+            self.rewrap();
             return;
         };
         opportunity.1 = cursor + 1;
         self.height = height as u32 * line_idx as u32;
+        self.rewrap();
+    }
+
+    /// Wrap again at the last width, after the text changed.
+    fn rewrap(&mut self) {
+        if !self.real_words.is_empty() {
+            self.real_words = BTreeMap::new();
+            let _ = self.set_width(self.width);
+        }
     }
 
     /// Minimum, Maximum allowed width in pixels
@@ -140,6 +166,7 @@ impl Text {
         (min, max)
     }
 
+    /// Height of the lines, wrapped at the width last given to [`Text::set_width`].
     pub fn text_height(&self) -> u32 {
         self.height
     }
@@ -152,7 +179,81 @@ impl Text {
         self.color = color;
     }
 
+    pub fn set_selection_color(&mut self, color: Rgba) {
+        self.selection_color = color;
+    }
+
+    /// Show `text` being composed at the cursor, with its caret at byte `cursor`. Empty when
+    /// the composition ends.
+    pub fn set_preedit(&mut self, text: &str, cursor: Option<usize>) {
+        self.preedit = text.to_owned();
+        self.preedit_cursor = cursor.filter(|&c| text.is_char_boundary(c));
+    }
+
+    /// Byte range of the selected text, `None` if nothing is selected.
+    pub fn selection(&self) -> Option<Range<usize>> {
+        let (cursor, anchor) = (self.cursor?, self.anchor?);
+        (cursor != anchor).then(|| cursor.min(anchor)..cursor.max(anchor))
+    }
+
+    pub fn selected_text(&self) -> &str {
+        self.selection().map_or("", |range| &self.text[range])
+    }
+
+    /// Select `range`, leaving the cursor at its end.
+    pub fn select(&mut self, range: Range<usize>) {
+        self.anchor = Some(range.start.min(self.len()));
+        self.cursor = Some(range.end.min(self.len()));
+    }
+
+    pub fn select_all(&mut self) {
+        self.select(0..self.len());
+    }
+
+    /// Select the word under the cursor.
+    pub fn select_word(&mut self) {
+        let Some(cursor) = self.cursor else {
+            return;
+        };
+        let word = self
+            .text
+            .split_word_bound_indices()
+            .map(|(i, word)| i..i + word.len())
+            .find(|word| word.contains(&cursor) || word.end == self.len());
+        if let Some(word) = word {
+            self.select(word);
+        }
+    }
+
+    /// Select the line under the cursor, without its line break.
+    pub fn select_line(&mut self) {
+        let Some(cursor) = self.cursor else {
+            return;
+        };
+        let start = self.text[..cursor].rfind('\n').map_or(0, |i| i + 1);
+        let end = self.text[cursor..]
+            .find('\n')
+            .map_or(self.len(), |i| cursor + i);
+        self.select(start..end);
+    }
+
+    /// Delete the selected text. Returns `false` if nothing was selected.
+    pub fn remove_selection(&mut self) -> bool {
+        let Some(range) = self.selection() else {
+            return false;
+        };
+        self.text.replace_range(range.clone(), "");
+        self.breaks = BTreeMap::new();
+        if self.font.is_some() {
+            self.get_text_size();
+        }
+        self.cursor = Some(range.start);
+        self.anchor = None;
+        true
+    }
+
     pub fn insert<S: AsRef<str>>(&mut self, text: S) {
+        self.remove_selection();
         self.text.insert_str(self.cursor.unwrap(), text.as_ref());
         self.breaks = BTreeMap::new();
         if self.font.is_some() {
@@ -162,44 +263,323 @@ impl Text {
     }
 
     pub fn remove_front(&mut self) {
-        if self.cursor.unwrap() == self.text.len() {
+        if self.remove_selection() || self.cursor.unwrap() == self.text.len() {
             return;
         }
-        self.text.remove(self.cursor.unwrap());
+        let cursor = self.cursor.unwrap();
+        self.text
+            .replace_range(cursor..self.next_boundary(cursor), "");
         self.breaks = BTreeMap::new();
         if self.font.is_some() {
             self.get_text_size();
         }
     }
 
+    /// Move the cursor, dropping the selection.
     pub fn move_h(&mut self, shift: i32) {
-        if shift >= 0 {
-            if self.cursor.unwrap() == self.text.len() {
-                return;
-            }
-            *self.cursor.as_mut().unwrap() += shift as usize
+        if let Some(range) = self.selection() {
+            self.anchor = None;
+            self.cursor = Some(if shift >= 0 { range.end } else { range.start });
+            return;
+        }
+        self.anchor = None;
+        self.step_h(shift);
+    }
+
+    /// Move the cursor, keeping the selection's other end in place.
+    pub fn select_h(&mut self, shift: i32) {
+        if self.anchor.is_none() {
+            self.anchor = self.cursor;
+        }
+        self.step_h(shift);
+    }
+
+    fn step_h(&mut self, shift: i32) {
+        let Some(mut cursor) = self.cursor else {
+            return;
+        };
+        for _ in 0..shift.unsigned_abs() {
+            cursor = if shift >= 0 {
+                self.next_boundary(cursor)
+            } else {
+                self.prev_boundary(cursor)
+            };
+        }
+        self.cursor = Some(cursor);
+    }
+
+    /// End of the grapheme cluster starting at `pos`.
+    fn next_boundary(&self, pos: usize) -> usize {
+        self.text[pos..]
+            .graphemes(true)
+            .next()
+            .map_or(pos, |g| pos + g.len())
+    }
+
+    /// Start of the grapheme cluster ending at `pos`.
+    fn prev_boundary(&self, pos: usize) -> usize {
+        self.text[..pos]
+            .graphemes(true)
+            .next_back()
+            .map_or(pos, |g| pos - g.len())
+    }
+
+    /// The grapheme cluster boundary at or before `pos`.
+    fn snap(&self, pos: usize) -> usize {
+        if pos >= self.len() {
+            return self.len();
+        }
+        self.text
+            .grapheme_indices(true)
+            .map(|(i, _)| i)
+            .take_while(|&i| i <= pos)
+            .last()
+            .unwrap_or(0)
+    }
+
+    /// Move the cursor to `pos`. With `select`, the selection is extended instead of dropped.
+    pub fn move_to(&mut self, pos: usize, select: bool) {
+        if self.cursor.is_none() {
+            return;
+        }
+        self.anchor = if select {
+            self.anchor.or(self.cursor)
         } else {
-            if self.cursor.unwrap() == 0 {
-                return;
-            }
-            *self.cursor.as_mut().unwrap() -= (-shift) as usize
+            None
+        };
+        self.cursor = Some(pos.min(self.len()));
+    }
+
+    /// Byte ranges of the lines as drawn, without the line break or the space they wrap at.
+    fn lines(&self) -> Vec<Range<usize>> {
+        let mut starts: Vec<usize> = if self.real_words.is_empty() {
+            std::iter::once(0)
+                .chain(self.text.match_indices('\n').map(|(i, _)| i + 1))
+                .collect()
+        } else {
+            self.real_words
+                .keys()
+                .copied()
+                .filter(|&i| i < self.len())
+                .collect()
+        };
+        if starts.first() != Some(&0) {
+            starts.insert(0, 0);
         }
+        if self.text.ends_with('\n') && starts.last() != Some(&self.len()) {
+            starts.push(self.len());
+        }
+        starts
+            .iter()
+            .enumerate()
+            .map(|(n, &start)| {
+                let Some(&end) = starts.get(n + 1) else {
+                    return start..self.len();
+                };
+                match self.text[start..end].chars().next_back() {
+                    Some(c) if c.is_whitespace() => start..end - c.len_utf8(),
+                    _ => start..end,
+                }
+            })
+            .collect()
+    }
+
+    fn line_index(lines: &[Range<usize>], pos: usize) -> usize {
+        lines.iter().rposition(|l| l.start <= pos).unwrap_or(0)
+    }
+
+    pub fn move_home(&mut self, select: bool) {
+        let Some(cursor) = self.cursor else {
+            return;
+        };
+        let lines = self.lines();
+        self.move_to(lines[Self::line_index(&lines, cursor)].start, select);
+    }
+
+    pub fn move_end(&mut self, select: bool) {
+        let Some(cursor) = self.cursor else {
+            return;
+        };
+        let lines = self.lines();
+        self.move_to(lines[Self::line_index(&lines, cursor)].end, select);
+    }
+
+    /// Word boundaries, from the line break opportunities.
+    fn word_bounds(&self) -> Vec<usize> {
+        if self.breaks.is_empty() {
+            linebreaks(&self.text).map(|(i, _)| i).collect()
+        } else {
+            self.breaks.keys().copied().collect()
+        }
+    }
+
+    fn word_start(&self, pos: usize) -> usize {
+        self.word_bounds()
+            .into_iter()
+            .rfind(|&i| i < pos)
+            .unwrap_or(0)
+    }
+
+    fn word_end(&self, pos: usize) -> usize {
+        self.word_bounds()
+            .into_iter()
+            .find(|&i| i > pos)
+            .unwrap_or(self.len())
+    }
+
+    /// Jump to the start of the next or previous word.
+    pub fn move_word(&mut self, forward: bool, select: bool) {
+        let Some(cursor) = self.cursor else {
+            return;
+        };
+        let pos = if forward {
+            self.word_end(cursor)
+        } else {
+            self.word_start(cursor)
+        };
+        self.move_to(pos, select);
+    }
+
+    /// Move by `lines` lines as drawn, keeping the horizontal position.
+    pub fn move_v(&mut self, lines: i32, select: bool) {
+        let Some(cursor) = self.cursor else {
+            return;
+        };
+        if self.font.is_none() || self.real_words.is_empty() {
+            return;
+        }
+        let all = self.lines();
+        let line = Self::line_index(&all, cursor) as i32 + lines;
+        let pos = if line < 0 {
+            0
+        } else if line >= all.len() as i32 {
+            self.len()
+        } else {
+            let offs = self.caret_offset(cursor);
+            let anchor = self.anchor;
+            self.get_cursor_pos(Offset::new(
+                offs.x,
+                (line as f32 * self.line_height()) as i32 + 1,
+            ));
+            self.anchor = anchor;
+            self.cursor.unwrap()
+        };
+        self.cursor = Some(cursor);
+        self.move_to(pos, select);
+    }
+
+    pub(crate) fn line_height(&self) -> f32 {
+        let Some(font) = self.font.as_ref() else {
+            return self.font_size;
+        };
+        let scaled = font.as_scaled(font.pt_to_px_scale(self.font_size).unwrap());
+        scaled.height() + scaled.line_gap()
+    }
+
+    /// Distance from the top to the baseline of the first line. `None` without a font.
+    pub(crate) fn baseline(&self) -> Option<u32> {
+        let font = self.font.as_ref()?;
+        let scaled = font.as_scaled(font.pt_to_px_scale(self.font_size).unwrap());
+        Some(scaled.ascent().max(0.0) as u32)
+    }
+
+    /// Where the caret is drawn, including the preedit. `None` without a cursor or a font.
+    pub(crate) fn caret_rect(&self) -> Option<Rect> {
+        let cursor = self.cursor?;
+        self.font.as_ref()?;
+        let offs = if self.preedit.is_empty() {
+            self.caret_offset(cursor)
+        } else {
+            self.with_preedit(cursor)
+                .caret_offset(cursor + self.preedit_cursor.unwrap_or(self.preedit.len()))
+        };
+        Some(Rect::new(
+            offs,
+            lite_graphics::Size::new(1, self.line_height() as u32),
+        ))
+    }
+
+    /// A copy with the preedit inserted at `cursor`, underlined.
+    fn with_preedit(&self, cursor: usize) -> Self {
+        let mut shown = self.clone();
+        shown.text.insert_str(cursor, &self.preedit);
+        shown.preedit = String::new();
+        shown.underline = cursor..cursor + self.preedit.len();
+        shown.anchor = None;
+        shown.cursor = Some(cursor + self.preedit_cursor.unwrap_or(self.preedit.len()));
+        shown.breaks = BTreeMap::new();
+        shown.real_words = BTreeMap::new();
+        shown.get_text_size();
+        shown
+    }
+
+    /// Where `line` starts as drawn, after the alignment. Needs a font.
+    fn line_x(&self, line: usize) -> u32 {
+        let len = self
+            .real_words
+            .values()
+            .nth(line)
+            .copied()
+            .unwrap_or_default();
+        let x = match self.align {
+            Alignment::Left => 0,
+            Alignment::Center => self.width.saturating_sub(len) / 2,
+            Alignment::Right => self.width.saturating_sub(len),
+        };
+        if line > 0 {
+            return x;
+        }
+        let font = self.font.as_ref().unwrap();
+        let scaled = font.as_scaled(font.pt_to_px_scale(self.font_size).unwrap());
+        x + scaled.h_side_bearing(font.glyph_id(self.text.chars().next().unwrap_or(' '))) as u32
+    }
+
+    /// Where the cursor is drawn at `pos`, matching `get_cursor_pos`. Needs a font.
+    fn caret_offset(&self, pos: usize) -> Offset {
+        let font = self.font.as_ref().unwrap();
+        let scaled = font.as_scaled(font.pt_to_px_scale(self.font_size).unwrap());
+        let lines = self.lines();
+        let line = Self::line_index(&lines, pos);
+        let mut x = self.line_x(line);
+        let mut iter = self.text[lines[line].start..pos].char_indices().peekable();
+        while let Some((_, c)) = iter.next() {
+            let glyphs = self.get_glyphs(c, iter.peek(), false);
+            x += Self::get_glyph_width(scaled, glyphs, false);
+        }
+        Offset::new(x as i32, (line as f32 * self.line_height()) as i32)
     }
 
     pub fn remove_back(&mut self) {
-        if self.cursor.unwrap() == 0 {
+        if self.remove_selection() || self.cursor.unwrap() == 0 {
             return;
         }
-        *self.cursor.as_mut().unwrap() -= 1;
+        self.cursor = Some(self.prev_boundary(self.cursor.unwrap()));
         self.remove_front();
     }
 
+    /// Delete up to the start of the word, e.g. with Ctrl+Backspace.
+    pub fn remove_word_back(&mut self) {
+        if self.selection().is_none() {
+            self.move_word(false, true);
+        }
+        self.remove_selection();
+    }
+
+    pub fn remove_word_front(&mut self) {
+        if self.selection().is_none() {
+            self.move_word(true, true);
+        }
+        self.remove_selection();
+    }
+
     pub fn len(&self) -> usize {
         self.text.len()
     }
 
     pub fn set_text<S: AsRef<str>>(&mut self, text: S) {
         self.text = text.as_ref().to_string();
+        self.anchor = None;
+        self.cursor = self.cursor.map(|c| self.snap(c));
         self.breaks = BTreeMap::new();
         if self.font.is_some() {
             self.get_text_size();
@@ -215,89 +595,144 @@ impl Text {
         self.get_text_size();
     }
 
+    /// Show the cursor at the end, unless it's shown already.
+    pub fn focus(&mut self) {
+        if self.cursor.is_none() {
+            self.cursor = Some(self.len());
+        }
+    }
+
     pub fn unfocus(&mut self) {
         self.cursor = None;
+        self.anchor = None;
+        self.set_preedit("", None);
     }
 
-    /// Returns `None` if the width is too small.
+    /// Wrap the lines at `width`, [`Text::text_height`] follows. Returns `None` if the width is
+    /// too small.
     #[must_use]
     pub fn set_width(&mut self, width: u32) -> Option<()> {
-        if width < self.breaks.values().map(|i| i.1).max().unwrap_or_default() {
+        if width < self.width_bounds().0 {
             return None;
         }
         if self.width == width && !self.real_words.is_empty() {
             return Some(());
         }
-        self.real_words = BTreeMap::new();
-        self.real_words.insert(0, 0);
+        self.width = width;
+        self.real_words = self.wrap(width);
+        self.height = self.lines_height(&self.real_words)?;
+        Some(())
+    }
+
+    /// Height of the lines if wrapped at `width`, without wrapping them.
+    pub(crate) fn height_at(&self, width: u32) -> u32 {
+        if width < self.width_bounds().0 {
+            return self.height;
+        }
+        self.lines_height(&self.wrap(width)).unwrap_or(self.height)
+    }
+
+    /// Starts of the lines wrapped at `width`, with their widths, and the end of the text.
+    fn wrap(&self, width: u32) -> BTreeMap<usize, u32> {
+        let mut lines = BTreeMap::new();
+        lines.insert(0, 0);
         let mut cursor = 0;
-        for word in self.breaks.iter() {
-            cursor += word.1.1;
-            if cursor > width {
-                *self.real_words.last_entry().unwrap().get_mut() = cursor - word.1.1;
-            } else if word.1.0 == BreakOpportunity::Mandatory {
-                *self.real_words.last_entry().unwrap().get_mut() = cursor;
-            } else {
-                continue;
+        let mut start = 0;
+        for (&idx, &(opportunity, len)) in self.breaks.iter() {
+            // The word doesn't fit, it starts the next line.
+            if cursor > 0 && cursor + len > width {
+                *lines.last_entry().unwrap().get_mut() = cursor;
+                lines.insert(start, 0);
+                cursor = 0;
+            }
+            cursor += len;
+            if opportunity == BreakOpportunity::Mandatory {
+                *lines.last_entry().unwrap().get_mut() = cursor;
+                lines.insert(idx, 0);
+                cursor = 0;
             }
-            self.real_words.insert(*word.0, 0);
-            cursor = 0;
+            start = idx;
         }
-        *self.real_words.last_entry().unwrap().get_mut() = cursor;
-        self.real_words.insert(self.text.len(), 0);
-        Some(())
+        *lines.last_entry().unwrap().get_mut() = cursor;
+        lines.insert(self.text.len(), 0);
+        lines
+    }
+
+    /// Height of `lines` from [`Text::wrap`]. `None` without a font.
+    fn lines_height(&self, lines: &BTreeMap<usize, u32>) -> Option<u32> {
+        let font = self.font.as_ref()?;
+        let scaled = font.as_scaled(font.pt_to_px_scale(self.font_size).unwrap());
+        let count = (lines.len() - 1).max(1) + self.text.ends_with('\n') as usize;
+        Some((scaled.height() + scaled.line_gap()) as u32 * count as u32)
+    }
+
+    /// Move the cursor to `offs` while extending the selection, e.g. when dragging.
+    pub(crate) fn drag_cursor(&mut self, offs: Offset) {
+        let anchor = self.anchor.or(self.cursor);
+        self.get_cursor_pos(offs);
+        self.anchor = anchor;
     }
 
     pub(crate) fn get_cursor_pos(&mut self, offs: Offset) {
+        self.anchor = None;
+        self.cursor = Some(self.snap(self.index_at(offs)));
+    }
+
+    /// Byte index of the character boundary closest to `offs`.
+    fn index_at(&self, offs: Offset) -> usize {
         let font = self.font.as_ref().unwrap();
         let scaled = font.as_scaled(font.pt_to_px_scale(self.font_size).unwrap());
-        let mut cursor =
-            scaled.h_side_bearing(font.glyph_id(self.text.chars().next().unwrap_or(' '))) as u32;
-        let line = offs.y as f32 / (scaled.height() + scaled.line_gap());
+        let line = (offs.y as f32 / (scaled.height() + scaled.line_gap())).max(0.0) as usize;
+        let mut cursor = self.line_x(line);
         let initial_idx = self
             .real_words
-            .iter()
-            .skip(line.max(0.0) as usize)
-            .map(|i| *i.0)
-            .next()
+            .keys()
+            .nth(line)
+            .copied()
             .unwrap_or(self.len());
-        let mut iter = self.text.char_indices().skip(initial_idx).peekable();
+        let mut iter = self.text[initial_idx..]
+            .char_indices()
+            .map(|(i, c)| (initial_idx + i, c))
+            .peekable();
         let mut prev_width = None;
-        if offs.x <= 0 {
-            self.cursor = Some(initial_idx);
-            return;
+        if offs.x <= cursor as i32 {
+            return initial_idx;
         }
         while let Some((idx, c)) = iter.next() {
-            let glyphs = self.get_glyphs(c, iter.peek(), self.real_words.contains_key(&(idx + 1)));
+            let next = idx + c.len_utf8();
+            let glyphs = self.get_glyphs(c, iter.peek(), self.real_words.contains_key(&next));
             let half_width = scaled.h_advance(glyphs.0) as i32 / 2;
 
             if offs.x > cursor as i32 + half_width {
-                if self.real_words.contains_key(&(idx + 1))
-                    && (idx + 1 < self.len() || self.real_words.contains_key(&idx))
+                if self.real_words.contains_key(&next)
+                    && (next < self.len() || self.real_words.contains_key(&idx))
                 {
-                    if self.breaks.get(&(idx + 1)).unwrap().0 == BreakOpportunity::Allowed {
-                        self.cursor = Some(idx + 1);
+                    if self.breaks.get(&next).unwrap().0 == BreakOpportunity::Allowed {
+                        return next;
                     } else {
-                        self.cursor = Some(idx);
+                        return idx;
                     }
-
-                    return;
                 }
             } else if offs.x >= cursor as i32 - prev_width.unwrap_or(0)
                 && offs.x <= cursor as i32 + half_width
             {
-                self.cursor = Some(idx);
-                return;
+                return idx;
             }
             cursor += Self::get_glyph_width(scaled, glyphs, iter.peek().is_none());
             prev_width = Some(half_width)
         }
-        self.cursor = Some(self.len())
+        self.len()
     }
 
     /// Returns `None` if the width is too small.
     #[must_use]
     pub fn draw(&mut self, buf: &mut dyn Drawable, rect: Rect) -> Option<()> {
+        if let Some(cursor) = self.cursor
+            && !self.preedit.is_empty()
+            && self.with_preedit(cursor).draw(buf, rect).is_some()
+        {
+            return Some(());
+        }
         self.set_width(rect.w)?;
 
         let text = &self.text;
@@ -306,19 +741,28 @@ impl Text {
 
         let font = self.font.as_ref().unwrap();
         let scaled = font.as_scaled(font.pt_to_px_scale(self.font_size).unwrap());
-        let mut cursor = match self.align {
-            Alignment::Left => 0,
-            Alignment::Center => (rect.w - self.real_words.first_entry().unwrap().get()) / 2,
-            Alignment::Right => rect.w - self.real_words.first_entry().unwrap().get(),
-        };
-        cursor += scaled.h_side_bearing(font.glyph_id(text.chars().next().unwrap_or(' '))) as u32;
-        let mut iter = text.chars().enumerate().peekable();
+        let mut line = 0;
+        let mut cursor = self.line_x(line);
+        let selection = self.selection().unwrap_or_default();
+        let highlight = FRAMES.with_borrow(|f| f.get(&FrameType::Selection.to_string()).cloned());
+        let line_height = scaled.height() as u32;
+        let mut iter = text.char_indices().peekable();
         let mut word_iter = self.real_words.iter().peekable();
         word_iter.next().unwrap();
         while let Some((idx, c)) = iter.next() {
-            let word_end = *word_iter.peek().unwrap().0 == idx + 1;
+            let word_end = *word_iter.peek().unwrap().0 == idx + c.len_utf8();
             let glyphs = self.get_glyphs(c, iter.peek(), word_end);
 
+            if selection.contains(&idx)
+                && let Some(highlight) = &highlight
+            {
+                let width = Self::get_glyph_width(scaled, glyphs, iter.peek().is_none());
+                let size = lite_graphics::Size::new(width, line_height);
+                buf.subregion(Rect::new(Offset::new(cursor as i32, line_offs), size));
+                highlight(&*buf, size, self.selection_color);
+                buf.end_subregion();
+            }
+
             let glyph = glyphs.0.with_scale_and_position(scaled.scale, (0i16, 0));
             let ascent = scaled.ascent() as i32 + line_offs;
             if let Some(q) = font.outline_glyph(glyph) {
@@ -332,6 +776,15 @@ impl Text {
                 });
             }
 
+            if self.underline.contains(&idx) {
+                let width = Self::get_glyph_width(scaled, glyphs, iter.peek().is_none());
+                buf.line_h(
+                    Offset::new(cursor as i32, line_offs + line_height as i32 - 1),
+                    width as i32,
+                    self.color.into(),
+                );
+            }
+
             if self.cursor == Some(idx) {
                 buf.line_v(
                     Offset {
@@ -346,16 +799,13 @@ impl Text {
             cursor += Self::get_glyph_width(scaled, glyphs, iter.peek().is_none());
 
             if word_end {
-                let curr_len = word_iter.next().unwrap();
+                word_iter.next();
                 if word_iter.peek().is_none() && c != '\n' {
                     break;
                 }
                 line_offs += scaled.height() as i32 + scaled.line_gap() as i32;
-                cursor = match self.align {
-                    Alignment::Left => 0,
-                    Alignment::Center => (rect.w - curr_len.1) / 2,
-                    Alignment::Right => rect.w - curr_len.1,
-                };
+                line += 1;
+                cursor = self.line_x(line);
             }
         }
         if self.cursor == Some(self.len()) {
@@ -372,3 +822,437 @@ impl Text {
         Some(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One grapheme cluster each.
+    const SAMPLES: [&str; 5] = [
+        "\u{e9}",
+        "e\u{301}",
+        "\u{1f44d}\u{1f3fd}",
+        "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}",
+        "中",
+    ];
+
+    fn focused(text: &str) -> Text {
+        let mut text = Text::new(text, 12.0);
+        text.focus();
+        text
+    }
+
+    fn assert_on_boundary(text: &Text) {
+        let cursor = text.cursor.unwrap();
+        assert!(
+            cursor == text.len()
+                || text
+                    .get_text()
+                    .grapheme_indices(true)
+                    .any(|(i, _)| i == cursor),
+            "cursor {cursor} inside a grapheme of {:?}",
+            text.get_text()
+        );
+    }
+
+    #[test]
+    fn move_by_grapheme() {
+        for sample in SAMPLES {
+            let text = format!("a{sample}b");
+            let mut text = focused(&text);
+            text.move_h(-1);
+            assert_eq!(text.cursor, Some(text.len() - 1));
+            text.move_h(-1);
+            assert_eq!(text.cursor, Some(1), "{sample:?}");
+            text.move_h(-5);
+            assert_eq!(text.cursor, Some(0));
+            for _ in 0..4 {
+                text.move_h(1);
+                assert_on_boundary(&text);
+            }
+            assert_eq!(text.cursor, Some(text.len()));
+        }
+    }
+
+    #[test]
+    fn select_by_grapheme() {
+        for sample in SAMPLES {
+            let mut text = focused(sample);
+            text.select_h(-1);
+            assert_eq!(text.selected_text(), sample);
+            text.move_h(1);
+            assert_eq!(text.selection(), None);
+            assert_eq!(text.cursor, Some(text.len()));
+
+            let mut text = focused(&format!("{sample}{sample}"));
+            text.move_to(0, false);
+            text.select_h(1);
+            assert_eq!(text.selected_text(), sample);
+            text.move_h(-1);
+            assert_eq!(text.cursor, Some(0));
+        }
+    }
+
+    #[test]
+    fn remove_by_grapheme() {
+        for sample in SAMPLES {
+            let mut text = focused(&format!("a{sample}b"));
+            text.move_h(-1);
+            text.remove_back();
+            assert_eq!(text.get_text(), "ab", "{sample:?}");
+            assert_eq!(text.cursor, Some(1));
+
+            let mut text = focused(&format!("a{sample}b"));
+            text.move_to(1, false);
+            text.remove_front();
+            assert_eq!(text.get_text(), "ab", "{sample:?}");
+            assert_eq!(text.cursor, Some(1));
+
+            let mut text = focused(sample);
+            text.remove_back();
+            text.remove_back();
+            assert_eq!(text.get_text(), "");
+            text.remove_front();
+            assert_eq!(text.cursor, Some(0));
+        }
+    }
+
+    #[test]
+    fn insert_keeps_boundaries() {
+        for sample in SAMPLES {
+            let mut text = focused("ab");
+            text.move_h(-1);
+            text.insert(sample);
+            assert_eq!(text.get_text(), format!("a{sample}b"));
+            assert_on_boundary(&text);
+            text.move_h(-1);
+            assert_eq!(text.cursor, Some(1));
+
+            text.select_h(1);
+            text.insert(sample);
+            assert_eq!(text.get_text(), format!("a{sample}b"));
+            assert_on_boundary(&text);
+        }
+    }
+
+    #[test]
+    fn wide_characters() {
+        let mut text = focused("中文");
+        text.move_h(-1);
+        assert_eq!(text.cursor, Some(3));
+        text.select_h(-1);
+        assert_eq!(text.selected_text(), "中");
+        text.move_h(1);
+        text.remove_back();
+        assert_eq!(text.get_text(), "文");
+        assert_eq!(text.cursor, Some(0));
+        text.insert("中");
+        assert_eq!(text.cursor, Some(3));
+        text.remove_front();
+        assert_eq!(text.get_text(), "中");
+        assert_eq!(text.cursor, Some(3));
+    }
+
+    #[test]
+    fn caret_follows_line_origin() {
+        let mut text = Text::new("one two three", 12.0);
+        text.set_font(crate::test_font());
+        let width = text.width_bounds().0;
+        text.set_width(width).unwrap();
+        let widths: Vec<u32> = text.real_words.values().copied().collect();
+        let starts: Vec<usize> = text.real_words.keys().copied().collect();
+        assert!(starts.len() > 2, "not wrapped: {starts:?}");
+
+        // Wrapped lines start at the edge, only the first has the side bearing.
+        assert_eq!(text.caret_offset(starts[1]).x, 0);
+        assert_eq!(text.caret_offset(starts[1]).y, text.line_height() as i32);
+
+        for (align, x) in [
+            (Alignment::Center, (width - widths[1]) / 2),
+            (Alignment::Right, width - widths[1]),
+        ] {
+            text.set_align(align);
+            assert_eq!(text.caret_offset(starts[1]).x, x as i32);
+            let offs = text.caret_offset(starts[1]) + Offset::new(1, 1);
+            assert_eq!(text.index_at(offs), starts[1]);
+        }
+    }
+
+    #[test]
+    fn select_word_under_cursor() {
+        let mut text = focused("hello, world");
+        for (cursor, word) in [
+            (0, "hello"),
+            (2, "hello"),
+            (5, ","),
+            (6, " "),
+            (12, "world"),
+        ] {
+            text.move_to(cursor, false);
+            text.select_word();
+            assert_eq!(text.selected_text(), word, "at {cursor}");
+            assert_eq!(text.cursor, text.selection().map(|r| r.end));
+        }
+
+        let mut text = Text::new("hello", 12.0);
+        text.select_word();
+        assert_eq!(text.selection(), None);
+    }
+
+    #[test]
+    fn select_line_under_cursor() {
+        let mut text = focused("one\ntwo\nthree");
+        for (cursor, line) in [
+            (0, "one"),
+            (3, "one"),
+            (4, "two"),
+            (6, "two"),
+            (14, "three"),
+        ] {
+            text.move_to(cursor, false);
+            text.select_line();
+            assert_eq!(text.selected_text(), line, "at {cursor}");
+        }
+
+        let mut text = focused("one\n");
+        text.select_line();
+        assert_eq!(text.selection(), None);
+        assert_eq!(text.selected_text(), "");
+    }
+
+    #[test]
+    fn selected_text_follows_anchor() {
+        let mut text = focused("abcdef");
+        assert_eq!(text.selected_text(), "");
+        text.select(1..4);
+        assert_eq!(text.selected_text(), "bcd");
+        assert_eq!((text.anchor, text.cursor), (Some(1), Some(4)));
+
+        // Backwards from the end of the text.
+        text.select(6..2);
+        assert_eq!(text.selected_text(), "cdef");
+        assert_eq!(text.cursor, Some(2));
+
+        text.select(3..100);
+        assert_eq!(text.selected_text(), "def");
+        text.select_all();
+        assert_eq!(text.selected_text(), "abcdef");
+    }
+
+    #[test]
+    fn anchor_stays_while_selecting() {
+        let mut text = focused("abcdef");
+        text.move_to(3, false);
+        text.select_h(1);
+        text.select_h(1);
+        assert_eq!(text.anchor, Some(3));
+        assert_eq!(text.selected_text(), "de");
+
+        // Across the anchor, the selection flips to the other side.
+        text.select_h(-3);
+        assert_eq!(text.anchor, Some(3));
+        assert_eq!(text.selected_text(), "c");
+        text.move_to(0, true);
+        assert_eq!(text.selected_text(), "abc");
+        text.move_end(true);
+        assert_eq!(text.selected_text(), "def");
+
+        // Back on the anchor nothing is selected, but the anchor stays.
+        text.move_to(3, true);
+        assert_eq!(text.selection(), None);
+        assert_eq!(text.anchor, Some(3));
+        text.move_h(1);
+        assert_eq!(text.anchor, None);
+    }
+
+    #[test]
+    fn anchor_dropped() {
+        let mut text = focused("abcdef");
+        text.select(1..4);
+        text.move_h(-1);
+        assert_eq!((text.anchor, text.cursor), (None, Some(1)));
+
+        text.select(1..4);
+        text.move_h(1);
+        assert_eq!((text.anchor, text.cursor), (None, Some(4)));
+
+        text.select(1..4);
+        text.move_to(5, false);
+        assert_eq!(text.anchor, None);
+
+        text.select(1..4);
+        text.set_text("abc");
+        assert_eq!(text.selection(), None);
+
+        text.select(1..3);
+        text.remove_selection();
+        assert_eq!(
+            (text.get_text(), text.anchor, text.cursor),
+            ("a", None, Some(1))
+        );
+
+        text.select(0..1);
+        text.unfocus();
+        assert_eq!((text.anchor, text.cursor), (None, None));
+        assert_eq!(text.selected_text(), "");
+    }
+
+    /// "one two three" wrapped onto a line per word.
+    fn wrapped() -> Text {
+        let mut text = focused("one two three");
+        text.set_font(crate::test_font());
+        text.set_width(text.width_bounds().0).unwrap();
+        assert_eq!(text.lines(), [0..3, 4..7, 8..13]);
+        text
+    }
+
+    #[test]
+    fn move_by_word() {
+        let mut text = focused("one two-three four");
+        for pos in [0, 4, 8, 14, 18] {
+            text.move_to(pos, false);
+            text.move_word(true, false);
+            assert_eq!(
+                text.cursor,
+                Some(match pos {
+                    0 => 4,
+                    4 => 8,
+                    8 => 14,
+                    _ => 18,
+                }),
+                "forward from {pos}"
+            );
+        }
+        for (pos, start) in [(18, 14), (16, 14), (14, 8), (8, 4), (2, 0), (0, 0)] {
+            text.move_to(pos, false);
+            text.move_word(false, false);
+            assert_eq!(text.cursor, Some(start), "back from {pos}");
+        }
+
+        text.move_to(4, false);
+        text.move_word(true, true);
+        text.move_word(true, true);
+        assert_eq!(text.selected_text(), "two-three ");
+    }
+
+    #[test]
+    fn word_bounds_at_punctuation() {
+        // No break inside "e.g." or before the closing parenthesis and comma.
+        let mut text = focused("see (e.g. this), then");
+        text.move_to(0, false);
+        let mut stops = vec![];
+        while text.cursor != Some(text.len()) {
+            text.move_word(true, false);
+            stops.push(text.cursor.unwrap());
+        }
+        assert_eq!(stops, [4, 10, 17, 21]);
+    }
+
+    #[test]
+    fn word_bounds_across_wrapped_lines() {
+        let mut text = wrapped();
+        text.move_to(1, false);
+        text.move_word(true, false);
+        assert_eq!(text.cursor, Some(4));
+        text.move_word(true, false);
+        assert_eq!(text.cursor, Some(8));
+        text.move_word(false, false);
+        text.move_word(false, false);
+        assert_eq!(text.cursor, Some(0));
+    }
+
+    #[test]
+    fn home_and_end() {
+        let mut text = focused("ab\ncd\n");
+        text.move_to(4, false);
+        text.move_home(false);
+        assert_eq!(text.cursor, Some(3));
+        text.move_end(true);
+        assert_eq!(text.selected_text(), "cd");
+        text.move_to(0, false);
+        text.move_end(false);
+        assert_eq!(text.cursor, Some(2));
+        // The empty line after the last break.
+        text.move_to(6, false);
+        text.move_home(false);
+        assert_eq!(text.cursor, Some(6));
+
+        // Wrapped lines end before the space they wrap at.
+        let mut text = wrapped();
+        text.move_to(5, false);
+        text.move_home(false);
+        assert_eq!(text.cursor, Some(4));
+        text.move_end(false);
+        assert_eq!(text.cursor, Some(7));
+        text.move_home(true);
+        assert_eq!(text.selected_text(), "two");
+    }
+
+    #[test]
+    fn move_between_lines() {
+        let mut text = wrapped();
+        text.move_to(1, false);
+        text.move_v(1, false);
+        assert!((4..=7).contains(&text.cursor.unwrap()), "{:?}", text.cursor);
+        text.move_v(1, true);
+        assert!(
+            (8..=13).contains(&text.cursor.unwrap()),
+            "{:?}",
+            text.cursor
+        );
+        assert_eq!(text.selection().unwrap().start, text.anchor.unwrap());
+
+        // Past the last line to the end, past the first to the start.
+        text.move_v(1, false);
+        assert_eq!((text.cursor, text.anchor), (Some(13), None));
+        text.move_v(-5, true);
+        assert_eq!(text.selected_text(), "one two three");
+
+        // Only laid out text has lines to move between.
+        let mut text = focused("one\ntwo");
+        text.move_v(-1, false);
+        assert_eq!(text.cursor, Some(7));
+    }
+
+    #[test]
+    fn remove_by_word() {
+        let mut text = focused("one two three");
+        text.remove_word_back();
+        assert_eq!(text.get_text(), "one two ");
+        text.remove_word_back();
+        assert_eq!(text.get_text(), "one ");
+
+        let mut text = focused("one two three");
+        text.move_to(0, false);
+        text.remove_word_front();
+        assert_eq!(text.get_text(), "two three");
+        assert_eq!(text.cursor, Some(0));
+        text.move_to(2, false);
+        text.remove_word_front();
+        assert_eq!(text.get_text(), "twthree");
+
+        // A selection goes instead of the word.
+        let mut text = focused("one two three");
+        text.select(5..6);
+        text.remove_word_back();
+        assert_eq!(text.get_text(), "one to three");
+        text.select(1..2);
+        text.remove_word_front();
+        assert_eq!(text.get_text(), "oe to three");
+
+        let mut text = focused("");
+        text.remove_word_back();
+        text.remove_word_front();
+        assert_eq!((text.get_text(), text.cursor), ("", Some(0)));
+    }
+
+    #[test]
+    fn set_text_snaps_cursor() {
+        for sample in SAMPLES {
+            let mut text = focused("abcdefghijklmnopqrstuvwxyz");
+            text.move_to(2, false);
+            text.set_text(format!("a{sample}"));
+            assert_on_boundary(&text);
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use std::{collections::HashMap, rc::Rc};
 
-use lite_graphics::{Drawable, Size, color::Rgba};
+use lite_graphics::{Drawable, Offset, Rect, Size, color::Rgba};
 
 pub type FrameFn = Rc<dyn Fn(&dyn Drawable, Size, Rgba)>;
 
@@ -41,6 +41,48 @@ pub(crate) fn get_default_theme() -> HashMap<String, FrameFn> {
         }),
     );
 
+    map.insert(
+        "Selection".to_string(),
+        Rc::new(|buf, size, color| {
+            buf.fill_rect(size.into(), color.into());
+        }),
+    );
+
+    map.insert(
+        "Scrollbar".to_string(),
+        Rc::new(|buf, size, color| {
+            buf.fill_round_rect_aa(size.into(), size.w.min(size.h) / 2, color.into());
+        }),
+    );
+
+    map.insert(
+        "FocusRing".to_string(),
+        Rc::new(|buf, size, color| {
+            buf.round_rect_aa(size.into(), 4, color.into());
+            if size.w > 2 && size.h > 2 {
+                let inner = Rect::new(Offset::new(1, 1), Size::new(size.w - 2, size.h - 2));
+                buf.round_rect_aa(inner, 3, color.into());
+            }
+        }),
+    );
+
+    map
+}
+
+/// Colors drawn with outside of any widget, named like the frames using them.
+pub(crate) fn get_default_colors() -> HashMap<String, Rgba> {
+    let mut map = HashMap::new();
+    map.insert(
+        FrameType::FocusRing.to_string(),
+        Rgba::hex("#3584e4").unwrap(),
+    );
+    map.insert("ScrollbarTrack".to_string(), Rgba::hex("#e8e8e8").unwrap());
+    map.insert("ScrollbarThumb".to_string(), Rgba::hex("#a0a0a0").unwrap());
+    // While dragged.
+    map.insert(
+        "ScrollbarThumbActive".to_string(),
+        Rgba::hex("#808080").unwrap(),
+    );
     map
 }
 
@@ -54,6 +96,13 @@ pub enum FrameType {
     Button,
     Frame,
     InputFrame,
+    /// Drawn over the widget with keyboard focus.
+    FocusRing,
+    /// Drawn behind selected text.
+    Selection,
+    /// Track and thumb of a scrollbar, with the colors `ScrollbarTrack`, `ScrollbarThumb` and
+    /// `ScrollbarThumbActive` while dragged.
+    Scrollbar,
     Custom(String),
 }
 
@@ -66,6 +115,9 @@ impl ToString for FrameType {
             Self::Button => "Button".to_string(),
             Self::Frame => "Frame".to_string(),
             Self::InputFrame => "InputFrame".to_string(),
+            Self::FocusRing => "FocusRing".to_string(),
+            Self::Selection => "Selection".to_string(),
+            Self::Scrollbar => "Scrollbar".to_string(),
             Self::Custom(s) => s.clone(),
         }
     }
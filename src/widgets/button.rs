@@ -7,6 +7,7 @@ use lite_graphics::{Drawable, color::Rgba};
 
 use crate::{
     app::{CursorType, HoverResult},
+    shortcut::KeyBindings,
     themes,
     window::Window,
 };
@@ -57,6 +58,9 @@ impl<W: WidgetBase> WidgetBase for Button<W> {
     fn set_pos(&self, pos: Offset) {
         self.base.set_pos(pos);
     }
+    fn get_pos(&self) -> Offset {
+        self.base.get_pos()
+    }
     fn set_frame(&self, frame: String) {
         self.base.set_frame(frame);
     }
@@ -73,6 +77,15 @@ impl<W: WidgetBase> WidgetBase for Button<W> {
     fn get_padding(&self) -> (u32, u32, u32, u32) {
         self.base.get_padding()
     }
+    fn set_padding_sides(&self, padding: (u32, u32, u32, u32)) {
+        self.base.set_padding_sides(padding);
+    }
+    fn set_margin(&self, margin: (u32, u32, u32, u32)) {
+        self.base.set_margin(margin);
+    }
+    fn get_margin(&self) -> (u32, u32, u32, u32) {
+        self.base.get_margin()
+    }
     fn set_border_radius(&self, radius: u32) {
         self.base.set_border_radius(radius);
     }
@@ -149,6 +162,9 @@ impl<W: WidgetBase> WidgetInternal for Button<W> {
     fn set_offset(&self, pos: Offset) {
         self.base.set_offset(pos);
     }
+    fn baseline(&self) -> Option<u32> {
+        self.base.baseline()
+    }
     fn get_frame(&self) -> themes::FrameFn {
         self.base.get_frame()
     }
@@ -167,6 +183,9 @@ impl<W: WidgetBase> WidgetInternal for Button<W> {
         self.base.draw(buf);
     }
 
+    fn key_bindings(&self) -> Option<&KeyBindings> {
+        self.base.key_bindings()
+    }
     fn handle_button(self: Rc<Self>, pos: Offset, pressed: Option<Rc<Window>>) {
         if self.is_disabled() {
             return;
@@ -212,6 +231,14 @@ impl<W: WidgetBase> WidgetInternal for Button<W> {
             cursor: CursorType::Pointer,
         }
     }
+    fn focusable(&self) -> bool {
+        true
+    }
+    fn activate(self: Rc<Self>) {
+        if !self.is_disabled() {
+            (self.click_fn.borrow_mut())(&self.clone(), Offset::default())
+        }
+    }
 }
 
 pub fn button<W: IntoWidget>(base: W) -> Rc<Button<W::W>> {
@@ -8,7 +8,9 @@ use lite_graphics::{Buffer, Drawable, Overlay, Rect, color::Rgba};
 
 use crate::{
     app::{CursorType, HoverResult},
+    event::{Key, Modifiers},
     reactive::{RwSignal, SignalGet, SignalUpdate, create_effect},
+    shortcut::KeyBindings,
     themes,
     window::Window,
 };
@@ -43,7 +45,14 @@ pub struct DropDown<W: WidgetBase + ?Sized + 'static> {
 }
 
 impl<W: WidgetBase + 'static> InputBase for DropDown<W> {
-    fn handle_key(&self, _: crate::event::Key) {}
+    fn handle_key(&self, _: &Window, mods: Modifiers, key: Key) {
+        if mods & Modifiers::CONTROL == Modifiers::NONE
+            && matches!(key, Key::Enter | Key::NumEnter | Key::Space)
+            && !self.is_disabled()
+        {
+            self.selected.update(|s| s.1 = !s.1);
+        }
+    }
 }
 
 impl<W: WidgetBase + 'static> InputExt for DropDown<W> {
@@ -65,6 +74,9 @@ impl<W: WidgetBase> WidgetBase for DropDown<W> {
     fn set_pos(&self, pos: Offset) {
         self.base.set_pos(pos);
     }
+    fn get_pos(&self) -> Offset {
+        self.base.get_pos()
+    }
     fn set_frame(&self, frame: String) {
         self.base.set_frame(frame);
     }
@@ -81,6 +93,15 @@ impl<W: WidgetBase> WidgetBase for DropDown<W> {
     fn get_padding(&self) -> (u32, u32, u32, u32) {
         self.base.get_padding()
     }
+    fn set_padding_sides(&self, padding: (u32, u32, u32, u32)) {
+        self.base.set_padding_sides(padding);
+    }
+    fn set_margin(&self, margin: (u32, u32, u32, u32)) {
+        self.base.set_margin(margin);
+    }
+    fn get_margin(&self) -> (u32, u32, u32, u32) {
+        self.base.get_margin()
+    }
     fn set_border_radius(&self, radius: u32) {
         self.base.set_border_radius(radius);
     }
@@ -168,6 +189,9 @@ impl<W: WidgetBase> WidgetInternal for DropDown<W> {
         self.overlay_pos.set(pos);
         self.overlay.set_offset(Offset::default());
     }
+    fn baseline(&self) -> Option<u32> {
+        self.base.baseline()
+    }
     fn get_frame(&self) -> themes::FrameFn {
         self.base.get_frame()
     }
@@ -200,6 +224,9 @@ impl<W: WidgetBase> WidgetInternal for DropDown<W> {
         }
     }
 
+    fn key_bindings(&self) -> Option<&KeyBindings> {
+        self.base.key_bindings()
+    }
     fn handle_button(self: Rc<Self>, pos: Offset, pressed: Option<Rc<Window>>) {
         if self.is_disabled() {
             self.selected.update(|s| s.1 = false);
@@ -267,6 +294,17 @@ impl<W: WidgetBase> WidgetInternal for DropDown<W> {
             .clone()
             .handle_hover(pos - self.overlay_pos.get())
     }
+    fn focusable(&self) -> bool {
+        true
+    }
+    fn set_focused(&self, focused: bool) {
+        if !focused {
+            self.selected.update(|s| s.1 = false);
+        }
+    }
+    fn as_input(self: Rc<Self>) -> Option<Rc<dyn InputBase>> {
+        Some(self)
+    }
     fn handle_overlay_button(self: Rc<Self>, pos: Offset, pressed: Option<Rc<Window>>) -> bool {
         if self.is_disabled() {
             return false;
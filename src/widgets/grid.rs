@@ -0,0 +1,652 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use lite_graphics::{Buffer, Drawable, Rect, color::Rgba};
+
+use crate::{
+    Align, Sizing,
+    app::{CursorType, HoverResult},
+    shortcut::KeyBindings,
+    themes,
+    window::Window,
+};
+
+use super::{
+    ComputedSize, Offset, Size, Widget, WidgetBase, WidgetExt, WidgetGroup, WidgetInternal,
+};
+
+struct GridChild {
+    widget: Rc<dyn WidgetBase>,
+    /// Columns and rows taken.
+    span: Cell<(usize, usize)>,
+    align: Cell<(Align, Align)>,
+}
+
+/// Where a child is along one axis.
+struct Extent {
+    start: usize,
+    span: usize,
+    bounds: (u32, u32),
+    sizing: Sizing,
+}
+
+/// Children in columns and rows that line up. Children fill the cells row by row, skipping
+/// cells taken by spans.
+pub struct Grid {
+    base: Widget,
+    columns: RefCell<Vec<Sizing>>,
+    rows: RefCell<Vec<Sizing>>,
+    /// Between columns and between rows.
+    gap: Cell<(u32, u32)>,
+    children: RefCell<Vec<GridChild>>,
+    /// Set by `set_width` and `set_height`.
+    widths: RefCell<Vec<u32>>,
+    heights: RefCell<Vec<u32>>,
+}
+
+impl Grid {
+    fn new_internal(columns: Vec<Sizing>, children: Vec<Rc<dyn WidgetBase>>) -> Self {
+        let this = Self {
+            base: Widget::new_internal(),
+            columns: RefCell::new(columns),
+            rows: RefCell::new(vec![]),
+            gap: Cell::new((0, 0)),
+            children: RefCell::new(
+                children
+                    .into_iter()
+                    .map(|widget| GridChild {
+                        widget,
+                        span: Cell::new((1, 1)),
+                        align: Cell::new((Align::Start, Align::Start)),
+                    })
+                    .collect(),
+            ),
+            widths: RefCell::new(vec![]),
+            heights: RefCell::new(vec![]),
+        };
+        this.base.set_frame(themes::FrameType::Box.to_string());
+        this
+    }
+    /// Sizing of each row. Rows past these fit their children.
+    pub fn rows(self: Rc<Self>, rows: impl IntoIterator<Item = Sizing>) -> Rc<Self> {
+        *self.rows.borrow_mut() = rows.into_iter().collect();
+        self
+    }
+    /// Space between columns and between rows.
+    pub fn gap(self: Rc<Self>, column: u32, row: u32) -> Rc<Self> {
+        self.gap.set((column, row));
+        self
+    }
+    /// Make child `index` take `columns` columns and `rows` rows.
+    pub fn span(self: Rc<Self>, index: usize, columns: usize, rows: usize) -> Rc<Self> {
+        if let Some(child) = self.children.borrow().get(index) {
+            child.span.set((columns.max(1), rows.max(1)));
+        }
+        self
+    }
+    /// Place child `index` in its cell, if it doesn't fill it.
+    pub fn align(self: Rc<Self>, index: usize, x: Align, y: Align) -> Rc<Self> {
+        if let Some(child) = self.children.borrow().get(index) {
+            child.align.set((x, y));
+        }
+        self
+    }
+
+    fn column_tracks(&self) -> Vec<Sizing> {
+        let columns = self.columns.borrow();
+        match columns.is_empty() {
+            true => vec![Sizing::default()],
+            false => columns.clone(),
+        }
+    }
+    fn row_tracks(&self) -> Vec<Sizing> {
+        let count = self
+            .placements()
+            .iter()
+            .map(|&(_, row, _, rows)| row + rows)
+            .max()
+            .unwrap_or(0);
+        let mut rows = self.rows.borrow().clone();
+        if rows.len() < count {
+            rows.resize(count, Sizing::default());
+        }
+        rows
+    }
+    /// Column, row, columns and rows taken by each child.
+    fn placements(&self) -> Vec<(usize, usize, usize, usize)> {
+        let columns = self.column_tracks().len();
+        let mut taken: Vec<Vec<bool>> = vec![];
+        let (mut column, mut row) = (0, 0);
+        let mut out = vec![];
+        for child in &*self.children.borrow() {
+            let (spans, rows) = child.span.get();
+            let spans = spans.min(columns);
+            loop {
+                if column + spans > columns {
+                    column = 0;
+                    row += 1;
+                    continue;
+                }
+                let free = (row..row + rows).all(|r| {
+                    taken
+                        .get(r)
+                        .is_none_or(|taken| !taken[column..column + spans].contains(&true))
+                });
+                if free {
+                    break;
+                }
+                column += 1;
+            }
+            if taken.len() < row + rows {
+                taken.resize(row + rows, vec![false; columns]);
+            }
+            for r in &mut taken[row..row + rows] {
+                r[column..column + spans].fill(true);
+            }
+            out.push((column, row, spans, rows));
+            column += spans;
+        }
+        out
+    }
+    fn extents(&self, horizontal: bool) -> Vec<Extent> {
+        self.children
+            .borrow()
+            .iter()
+            .zip(self.placements())
+            .map(|(child, (column, row, columns, rows))| match horizontal {
+                true => Extent {
+                    start: column,
+                    span: columns,
+                    bounds: child.widget.width_bounds(),
+                    sizing: child.widget.get_size().w,
+                },
+                false => Extent {
+                    start: row,
+                    span: rows,
+                    bounds: child.widget.height_bounds(),
+                    sizing: child.widget.get_size().h,
+                },
+            })
+            .collect()
+    }
+    /// Bounds of the grid and the sizes of the children along one axis.
+    fn layout(&self, horizontal: bool, size: Option<u32>) -> ((u32, u32), Vec<u32>) {
+        let padding = self.get_padding();
+        let (tracks, gap, padding) = match horizontal {
+            true => (
+                self.column_tracks(),
+                self.gap.get().0,
+                padding.1 + padding.3,
+            ),
+            false => (self.row_tracks(), self.gap.get().1, padding.0 + padding.2),
+        };
+        let extents = self.extents(horizontal);
+        let bounds = track_bounds(&tracks, &extents, gap);
+        let gaps = gap * (tracks.len() as u32).saturating_sub(1) + padding;
+        let min = bounds.iter().map(|b| b.0).sum::<u32>() + gaps;
+        let max = bounds.iter().fold(gaps, |max, b| max.saturating_add(b.1));
+        let Some(size) = size else {
+            return ((min, max), vec![]);
+        };
+        let sizes = track_sizes(&tracks, &bounds, size.saturating_sub(gaps));
+        let children = extents
+            .iter()
+            .map(|e| {
+                let cell = sizes[e.start..e.start + e.span].iter().sum::<u32>()
+                    + gap * (e.span as u32 - 1);
+                match e.sizing {
+                    Sizing::Fill | Sizing::Stretch(1..) => cell.max(e.bounds.0),
+                    _ => e.bounds.0,
+                }
+            })
+            .collect();
+        match horizontal {
+            true => *self.widths.borrow_mut() = sizes,
+            false => *self.heights.borrow_mut() = sizes,
+        }
+        ((min, max), children)
+    }
+}
+
+/// Bounds of each track, from the children in it.
+fn track_bounds(tracks: &[Sizing], extents: &[Extent], gap: u32) -> Vec<(u32, u32)> {
+    let mut bounds: Vec<(u32, u32)> = tracks
+        .iter()
+        .map(|t| match t {
+            Sizing::Fixed(w) => (*w, *w),
+            _ => (0, 0),
+        })
+        .collect();
+    for e in extents.iter().filter(|e| e.span == 1) {
+        if let Sizing::Fixed(_) = tracks[e.start] {
+            continue;
+        }
+        let b = &mut bounds[e.start];
+        *b = (b.0.max(e.bounds.0), b.1.max(e.bounds.1));
+    }
+    // Children across tracks grow the ones that can, evenly.
+    for e in extents.iter().filter(|e| e.span > 1) {
+        let range = e.start..e.start + e.span;
+        let have =
+            bounds[range.clone()].iter().map(|b| b.0).sum::<u32>() + gap * (e.span as u32 - 1);
+        let growable: Vec<usize> = range
+            .filter(|&i| !matches!(tracks[i], Sizing::Fixed(_)))
+            .collect();
+        if e.bounds.0 <= have || growable.is_empty() {
+            continue;
+        }
+        let mut extra = e.bounds.0 - have;
+        let mut len = growable.len() as u32;
+        for i in growable {
+            let add = extra / len;
+            bounds[i].0 += add;
+            extra -= add;
+            len -= 1;
+        }
+    }
+    for (b, t) in bounds.iter_mut().zip(tracks) {
+        b.1 = match t {
+            Sizing::Fixed(_) | Sizing::Stretch(0) => b.0,
+            _ => b.1.max(b.0),
+        };
+    }
+    bounds
+}
+
+/// Sizes of the tracks sharing `total`, the way a stack shares it between its children.
+fn track_sizes(tracks: &[Sizing], bounds: &[(u32, u32)], total: u32) -> Vec<u32> {
+    let mut sizes: Vec<u32> = bounds.iter().map(|b| b.0).collect();
+    let mut rem = total.saturating_sub(sizes.iter().sum());
+    let fills: Vec<usize> = (0..tracks.len())
+        .filter(|&i| tracks[i] == Sizing::Fill)
+        .collect();
+    if !fills.is_empty() {
+        let mut len = fills.len() as u32;
+        for i in fills {
+            let extra = rem / len;
+            sizes[i] += extra;
+            rem -= extra;
+            len -= 1;
+        }
+        return sizes;
+    }
+    let mut total_fac: u32 = tracks
+        .iter()
+        .map(|t| match t {
+            Sizing::Stretch(fac) => *fac,
+            _ => 0,
+        })
+        .sum();
+    for (size, track) in sizes.iter_mut().zip(tracks) {
+        if let Sizing::Stretch(fac @ 1..) = *track {
+            let extra = (rem as u64 * fac as u64 / total_fac as u64) as u32;
+            *size += extra;
+            rem -= extra;
+            total_fac -= fac;
+        }
+    }
+    sizes
+}
+
+impl WidgetBase for Grid {
+    fn set_size(&self, size: Size) {
+        self.base.set_size(size);
+    }
+    fn get_size(&self) -> Size {
+        self.base.get_size()
+    }
+    fn set_pos(&self, pos: Offset) {
+        self.base.set_pos(pos);
+    }
+    fn get_pos(&self) -> Offset {
+        self.base.get_pos()
+    }
+    fn set_frame(&self, frame: String) {
+        self.base.set_frame(frame);
+    }
+    fn set_background_color(&self, color: Rgba) {
+        self.base.set_background_color(color);
+    }
+    fn get_background_color(&self) -> Rgba {
+        self.base.get_background_color()
+    }
+    fn set_padding(&self, padding: u32) {
+        self.base.set_padding(padding);
+    }
+    fn get_padding(&self) -> (u32, u32, u32, u32) {
+        self.base.get_padding()
+    }
+    fn set_padding_sides(&self, padding: (u32, u32, u32, u32)) {
+        self.base.set_padding_sides(padding);
+    }
+    fn set_margin(&self, margin: (u32, u32, u32, u32)) {
+        self.base.set_margin(margin);
+    }
+    fn get_margin(&self) -> (u32, u32, u32, u32) {
+        self.base.get_margin()
+    }
+    fn set_border_radius(&self, radius: u32) {
+        self.base.set_border_radius(radius);
+    }
+    fn get_border_radius(&self) -> u32 {
+        self.base.get_border_radius()
+    }
+    // No meaning here
+    fn set_color(&self, _color: Rgba) {}
+    // No meaning here
+    fn set_text(&self, _text: &str) {}
+    fn get_text(&self) -> String {
+        "".to_owned()
+    }
+    fn set_text_align(&self, _: core::fmt::Alignment) {}
+    fn set_disabled(&self, _: bool) {}
+    fn is_disabled(&self) -> bool {
+        false
+    }
+}
+
+impl WidgetExt for Grid {
+    fn new() -> Rc<Self> {
+        Rc::new(Self::new_internal(vec![], vec![]))
+    }
+
+    fn on_hover<F: FnMut(&Self, Offset) + 'static>(self: Rc<Self>, _f: F) -> Rc<Self> {
+        self
+    }
+    fn on_click<F: FnMut(&Self, Offset) + 'static>(self: Rc<Self>, _f: F) -> Rc<Self> {
+        self
+    }
+}
+
+impl WidgetInternal for Grid {
+    fn set_font(&self, font: ab_glyph::FontArc) {
+        for child in &*self.children.borrow() {
+            child.widget.set_font(font.clone());
+        }
+    }
+    fn width_bounds(&self) -> (u32, u32) {
+        let ((min, max), _) = self.layout(true, None);
+        match self.get_size().w {
+            Sizing::Fixed(w) => (w.clamp(min, max), w.clamp(min, max)),
+            Sizing::Stretch(0) => (min, min),
+            _ => (min, max),
+        }
+    }
+    fn set_width(&self, width: u32) {
+        self.base.set_width(width);
+        let (_, widths) = self.layout(true, Some(width));
+        for (child, width) in self.children.borrow().iter().zip(widths) {
+            child.widget.set_width(width);
+        }
+    }
+    fn height_bounds(&self) -> (u32, u32) {
+        let ((min, max), _) = self.layout(false, None);
+        match self.get_size().h {
+            Sizing::Fixed(h) => (h.clamp(min, max), h.clamp(min, max)),
+            Sizing::Stretch(0) => (min, min),
+            _ => (min, max),
+        }
+    }
+    fn set_height(&self, height: u32) {
+        self.base.set_height(height);
+        let (_, heights) = self.layout(false, Some(height));
+        for (child, height) in self.children.borrow().iter().zip(heights) {
+            child.widget.set_height(height);
+        }
+    }
+    fn get_computed_size(&self) -> ComputedSize {
+        self.base.get_computed_size()
+    }
+    fn get_offset(&self) -> Offset {
+        self.base.get_offset()
+    }
+    fn set_offset(&self, pos: Offset) {
+        self.base.set_offset(pos);
+        let padding = self.get_padding();
+        let (column_gap, row_gap) = self.gap.get();
+        let widths = self.widths.borrow();
+        let heights = self.heights.borrow();
+        // Start of each track, and of the one past the last.
+        let starts = |sizes: &[u32], gap: u32, padding: u32| {
+            let mut starts = vec![padding];
+            for size in sizes {
+                starts.push(starts.last().unwrap() + size + gap);
+            }
+            starts
+        };
+        let xs = starts(&widths, column_gap, padding.3);
+        let ys = starts(&heights, row_gap, padding.0);
+        let placements = self.placements();
+        for (child, (column, row, columns, rows)) in self.children.borrow().iter().zip(placements) {
+            // Sizes may lag the tracks until the next layout.
+            let (Some(&x), Some(&x_end), Some(&y), Some(&y_end)) = (
+                xs.get(column),
+                xs.get(column + columns),
+                ys.get(row),
+                ys.get(row + rows),
+            ) else {
+                continue;
+            };
+            let size = child.widget.get_computed_size();
+            let (align_x, align_y) = child.align.get();
+            let cell = ComputedSize::new(x_end - x - column_gap, y_end - y - row_gap);
+            child.widget.set_offset(Offset::new(
+                x as i32 + align_x.place(size.w, cell.w),
+                y as i32 + align_y.place(size.h, cell.h),
+            ));
+        }
+    }
+    fn set_viewport(&self, rect: Rect) {
+        for child in &*self.children.borrow() {
+            let offs = child.widget.get_offset();
+            child.widget.set_viewport(Rect::new(
+                Offset::new(rect.x - offs.x, rect.y - offs.y),
+                ComputedSize::new(rect.w, rect.h),
+            ));
+        }
+    }
+    fn get_frame(&self) -> themes::FrameFn {
+        self.base.get_frame()
+    }
+    fn draw_frame(&self, buf: &dyn Drawable) {
+        self.base.draw_frame(buf);
+    }
+    fn draw(&self, buf: &mut dyn Drawable) {
+        let bounds = Rect::new(self.get_offset(), self.get_computed_size());
+        buf.subregion(bounds);
+        self.draw_frame(buf);
+        for child in &*self.children.borrow() {
+            child.widget.draw(buf);
+        }
+        buf.end_subregion();
+    }
+    fn draw_overlays(&self, buf: &mut Buffer) {
+        for child in &*self.children.borrow() {
+            child.widget.draw_overlays(buf);
+        }
+    }
+    fn children(&self) -> Vec<Rc<dyn WidgetBase>> {
+        self.children
+            .borrow()
+            .iter()
+            .map(|c| c.widget.clone())
+            .collect()
+    }
+
+    fn key_bindings(&self) -> Option<&KeyBindings> {
+        self.base.key_bindings()
+    }
+    fn handle_button(self: Rc<Self>, pos: Offset, pressed: Option<Rc<Window>>) {
+        let pos = pos - self.get_offset();
+        let size = self.get_computed_size();
+        if pos.x < 0 || pos.y < 0 || pos.x > size.w as i32 || pos.y > size.h as i32 {
+            return;
+        }
+        for child in self.children() {
+            child.handle_button(pos, pressed.clone());
+        }
+    }
+    fn handle_hover(self: Rc<Self>, pos: Offset) -> HoverResult {
+        let pos = pos - self.get_offset();
+        let size = self.get_computed_size();
+        let mut result = HoverResult {
+            redraw: false,
+            cursor: CursorType::Arrow,
+        };
+        if pos.x < 0 || pos.y < 0 || pos.x > size.w as i32 || pos.y > size.h as i32 {
+            return result;
+        }
+        for child in self.children() {
+            result |= child.handle_hover(pos);
+        }
+        result
+    }
+    fn handle_scroll(self: Rc<Self>, pos: Offset, dx: f32, dy: f32, discrete: bool) -> bool {
+        let pos = pos - self.get_offset();
+        let size = self.get_computed_size();
+        if pos.x < 0 || pos.y < 0 || pos.x > size.w as i32 || pos.y > size.h as i32 {
+            return false;
+        }
+        self.children()
+            .into_iter()
+            .any(|child| child.handle_scroll(pos, dx, dy, discrete))
+    }
+    fn handle_overlay_button(self: Rc<Self>, pos: Offset, pressed: Option<Rc<Window>>) -> bool {
+        let mut result = false;
+        for child in self.children() {
+            result |= child.handle_overlay_button(pos, pressed.clone());
+        }
+        result
+    }
+    fn handle_overlay_hover(self: Rc<Self>, pos: Offset) -> HoverResult {
+        let mut result = HoverResult {
+            redraw: false,
+            cursor: CursorType::Arrow,
+        };
+        for child in self.children() {
+            result |= child.handle_overlay_hover(pos);
+        }
+        result
+    }
+}
+
+/// A grid with a column for each of `columns`, filled with `widgets` row by row.
+pub fn grid<G: WidgetGroup>(columns: impl IntoIterator<Item = Sizing>, widgets: G) -> Rc<Grid> {
+    Rc::new(Grid::new_internal(
+        columns.into_iter().collect(),
+        widgets.create_group(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn boxed(w: u32, h: u32) -> Rc<Widget> {
+        Widget::new().size(Size::fixed(w, h))
+    }
+
+    fn lay_out(widget: &dyn WidgetBase, w: u32, h: u32) {
+        widget.set_width(w);
+        widget.set_height(h);
+        widget.set_offset(Offset::default());
+    }
+
+    fn rect(widget: &dyn WidgetBase) -> (i32, i32, u32, u32) {
+        let (offs, size) = (widget.get_offset(), widget.get_computed_size());
+        (offs.x, offs.y, size.w, size.h)
+    }
+
+    #[test]
+    fn fixed_and_stretch_tracks() {
+        // 150 left over for the stretching columns, a quarter and three.
+        let (a, b, c) = (boxed(10, 10), boxed(10, 10), boxed(10, 10));
+        let columns = [Sizing::Fixed(30), Sizing::Stretch(1), Sizing::Stretch(3)];
+        let grid = grid(columns, (a.clone(), b.clone(), c.clone()));
+        assert_eq!(grid.width_bounds().0, 50);
+        lay_out(&*grid, 200, 10);
+        assert_eq!(*grid.widths.borrow(), [30, 47, 123]);
+        assert_eq!(
+            (rect(&*a), rect(&*b), rect(&*c)),
+            ((0, 0, 10, 10), (30, 0, 10, 10), (77, 0, 10, 10))
+        );
+    }
+
+    #[test]
+    fn fill_track_takes_the_rest() {
+        let (a, b) = (boxed(10, 10), boxed(10, 10));
+        let tracks = grid([Sizing::Fill, Sizing::Stretch(1)], (a.clone(), b.clone()));
+        lay_out(&*tracks, 100, 10);
+        assert_eq!(*tracks.widths.borrow(), [90, 10]);
+        assert_eq!(rect(&*b), (90, 0, 10, 10));
+
+        // Children that fill get the whole cell.
+        let a = Widget::new().size(Size::new(Sizing::Fill, Sizing::Fill));
+        let cell = grid([Sizing::Fixed(40)], a.clone()).rows([Sizing::Fixed(30)]);
+        lay_out(&*cell, 40, 30);
+        assert_eq!(rect(&*a), (0, 0, 40, 30));
+    }
+
+    #[test]
+    fn gaps() {
+        let children: Vec<_> = (0..4).map(|_| boxed(10, 10)).collect();
+        let grid = grid([Sizing::default(); 2], children.clone()).gap(5, 7);
+        assert_eq!(grid.width_bounds(), (25, 25));
+        assert_eq!(grid.height_bounds(), (27, 27));
+        lay_out(&*grid, 25, 27);
+        let offsets: Vec<_> = children.iter().map(|c| rect(&**c)).collect();
+        assert_eq!(
+            offsets,
+            [
+                (0, 0, 10, 10),
+                (15, 0, 10, 10),
+                (0, 17, 10, 10),
+                (15, 17, 10, 10)
+            ]
+        );
+    }
+
+    #[test]
+    fn spans_grow_their_tracks() {
+        // 20 short of the span, shared by both columns.
+        let (a, b, c) = (boxed(44, 10), boxed(10, 10), boxed(10, 10));
+        let across = grid([Sizing::default(); 2], (a.clone(), b.clone(), c.clone()))
+            .gap(4, 0)
+            .span(0, 2, 1);
+        assert_eq!(across.width_bounds().0, 44);
+        lay_out(&*across, 44, 20);
+        assert_eq!(*across.widths.borrow(), [20, 20]);
+        assert_eq!((rect(&*b), rect(&*c)), ((0, 10, 10, 10), (24, 10, 10, 10)));
+
+        // Fixed tracks keep their size.
+        let a = boxed(50, 10);
+        let fixed = grid([Sizing::Fixed(10), Sizing::default()], a).span(0, 2, 1);
+        lay_out(&*fixed, 50, 10);
+        assert_eq!(*fixed.widths.borrow(), [10, 40]);
+
+        // Down rows, the next children go around it.
+        let (a, b, c) = (boxed(10, 30), boxed(10, 10), boxed(10, 10));
+        let down = grid([Sizing::default(); 2], (a.clone(), b.clone(), c.clone())).span(0, 1, 2);
+        lay_out(&*down, 20, 30);
+        assert_eq!(*down.heights.borrow(), [15, 15]);
+        assert_eq!((rect(&*b), rect(&*c)), ((10, 0, 10, 10), (10, 15, 10, 10)));
+    }
+
+    #[test]
+    fn align_in_cell() {
+        for (x, y, offset) in [
+            (Align::Start, Align::Start, (0, 0)),
+            (Align::Center, Align::End, (15, 20)),
+            (Align::End, Align::Center, (30, 10)),
+        ] {
+            let a = boxed(10, 10);
+            let grid = grid([Sizing::Fixed(40)], a.clone())
+                .rows([Sizing::Fixed(30)])
+                .align(0, x, y);
+            lay_out(&*grid, 40, 30);
+            assert_eq!(rect(&*a), (offset.0, offset.1, 10, 10));
+        }
+    }
+}
@@ -3,12 +3,13 @@ use std::{
     rc::Rc,
 };
 
-use lite_graphics::{Drawable, color::Rgba};
+use lite_graphics::{Drawable, Rect, color::Rgba};
 
 use crate::{
-    app::{CursorType, HoverResult},
-    event::Key,
-    reactive::SignalUpdate,
+    app::{ClipboardKind, CursorType, HoverResult},
+    event::{Key, Modifiers},
+    reactive::{SignalUpdate, SignalWith},
+    shortcut::KeyBindings,
     themes,
     window::Window,
 };
@@ -19,13 +20,31 @@ use super::{
 };
 
 pub trait InputBase {
-    fn handle_key(&self, key: Key);
+    fn handle_key(&self, window: &Window, mods: Modifiers, key: Key);
+    /// Insert typed or pasted text at the cursor. Ignored by default.
+    fn insert_text(&self, _text: &str) {}
+    /// Show text being composed by an input method, with its caret at byte `cursor`.
+    /// Ignored by default.
+    fn set_preedit(&self, _text: &str, _cursor: Option<usize>) {}
+    /// Caret relative to the widget, where input method popups go. `None` if there's no caret.
+    fn caret_rect(&self) -> Option<Rect> {
+        None
+    }
 }
 
 pub trait InputExt: InputBase {
     fn on_edit<F: FnMut(&Self) + 'static>(self: Rc<Self>, f: F) -> Rc<Self>;
 }
 
+impl Input {
+    pub fn selected_text(&self) -> String {
+        self.base.selected_text()
+    }
+    pub fn set_selection_color(&self, color: Rgba) {
+        self.base.set_selection_color(color);
+    }
+}
+
 pub struct Input {
     base: Label,
 
@@ -51,6 +70,9 @@ impl WidgetBase for Input {
     fn set_pos(&self, pos: Offset) {
         self.base.set_pos(pos);
     }
+    fn get_pos(&self) -> Offset {
+        self.base.get_pos()
+    }
     fn set_frame(&self, frame: String) {
         self.base.set_frame(frame);
     }
@@ -67,6 +89,15 @@ impl WidgetBase for Input {
     fn get_padding(&self) -> (u32, u32, u32, u32) {
         self.base.get_padding()
     }
+    fn set_padding_sides(&self, padding: (u32, u32, u32, u32)) {
+        self.base.set_padding_sides(padding);
+    }
+    fn set_margin(&self, margin: (u32, u32, u32, u32)) {
+        self.base.set_margin(margin);
+    }
+    fn get_margin(&self) -> (u32, u32, u32, u32) {
+        self.base.get_margin()
+    }
     fn set_border_radius(&self, radius: u32) {
         self.base.set_border_radius(radius);
     }
@@ -94,19 +125,93 @@ impl WidgetBase for Input {
 }
 
 impl InputBase for Input {
-    fn handle_key(&self, key: Key) {
+    fn handle_key(&self, window: &Window, mods: Modifiers, key: Key) {
         let text = self.base.get_text_signal();
-        let string = key.to_string();
+        let shift = mods & Modifiers::SHIFT != Modifiers::NONE;
+        if mods & Modifiers::CONTROL != Modifiers::NONE {
+            let Some(app) = window.app() else {
+                return;
+            };
+            let selected = self.selected_text();
+            match key.unshift() {
+                Key::a => text.update(|text| text.select_all()),
+                Key::ArrowLeft => text.update(|text| text.move_word(false, shift)),
+                Key::ArrowRight => text.update(|text| text.move_word(true, shift)),
+                Key::Home => text.update(|text| text.move_to(0, shift)),
+                Key::End => text.update(|text| text.move_to(usize::MAX, shift)),
+                Key::Backspace => {
+                    text.update(|text| text.remove_word_back());
+                    (self.edit_fn.borrow_mut())(self)
+                }
+                Key::Delete => {
+                    text.update(|text| text.remove_word_front());
+                    (self.edit_fn.borrow_mut())(self)
+                }
+                Key::c if !selected.is_empty() => {
+                    app.set_clipboard(ClipboardKind::Clipboard, &selected)
+                }
+                Key::x if !selected.is_empty() => {
+                    app.set_clipboard(ClipboardKind::Clipboard, &selected);
+                    text.update(|text| {
+                        text.remove_selection();
+                    });
+                    (self.edit_fn.borrow_mut())(self)
+                }
+                Key::v => {
+                    if let Some(text) = app.clipboard(ClipboardKind::Clipboard) {
+                        self.insert_text(&text);
+                    }
+                }
+                _ => {}
+            }
+            return;
+        }
+        let padding = self.get_padding();
+        let page = (self
+            .get_computed_size()
+            .h
+            .saturating_sub(padding.0 + padding.2) as f32
+            / text.with(|t| t.line_height()))
+        .max(1.0) as i32;
         text.update(|text| match key {
             Key::Backspace => text.remove_back(),
             Key::Delete => text.remove_front(),
+            Key::ArrowLeft if shift => text.select_h(-1),
+            Key::ArrowRight if shift => text.select_h(1),
             Key::ArrowLeft => text.move_h(-1),
             Key::ArrowRight => text.move_h(1),
-            key if !string.is_empty() => text.insert(&key.to_string()[0..1]),
+            Key::ArrowUp => text.move_v(-1, shift),
+            Key::ArrowDown => text.move_v(1, shift),
+            Key::PgUp => text.move_v(-page, shift),
+            Key::PgDn => text.move_v(page, shift),
+            Key::Home => text.move_home(shift),
+            Key::End => text.move_end(shift),
+            Key::Enter | Key::NumEnter => text.insert("\n"),
             _ => {}
         });
         (self.edit_fn.borrow_mut())(self)
     }
+    fn insert_text(&self, text: &str) {
+        if self.is_disabled() {
+            return;
+        }
+        self.base.get_text_signal().update(|t| {
+            t.focus();
+            t.insert(text);
+        });
+        (self.edit_fn.borrow_mut())(self)
+    }
+    fn set_preedit(&self, text: &str, cursor: Option<usize>) {
+        if self.is_disabled() {
+            return;
+        }
+        self.base
+            .get_text_signal()
+            .update(|t| t.set_preedit(text, cursor));
+    }
+    fn caret_rect(&self) -> Option<Rect> {
+        self.base.caret_rect()
+    }
 }
 impl InputExt for Input {
     fn on_edit<F: FnMut(&Self) + 'static>(self: Rc<Self>, f: F) -> Rc<Self> {
@@ -169,6 +274,9 @@ impl WidgetInternal for Input {
     fn set_offset(&self, pos: Offset) {
         self.base.set_offset(pos);
     }
+    fn baseline(&self) -> Option<u32> {
+        self.base.baseline()
+    }
     fn get_frame(&self) -> themes::FrameFn {
         self.base.get_frame()
     }
@@ -187,6 +295,9 @@ impl WidgetInternal for Input {
         self.base.draw(buf);
     }
 
+    fn key_bindings(&self) -> Option<&KeyBindings> {
+        self.base.key_bindings()
+    }
     fn handle_button(self: Rc<Self>, pos: Offset, pressed: Option<Rc<Window>>) {
         if self.is_disabled() {
             return;
@@ -204,16 +315,12 @@ impl WidgetInternal for Input {
                 self.base.get_text_signal().update(|t| t.unfocus());
                 return;
             }
-            self.base.get_text_signal().update(|text| {
-                text.get_cursor_pos(
-                    pos - Offset {
-                        x: self.get_padding().3 as i32,
-                        y: self.get_padding().0 as i32,
-                    },
-                )
-            });
-        } else if inside {
-            (self.click_fn.borrow_mut())(&self, pos)
+            self.base.press_text(pos, &w);
+        } else {
+            self.base.release_text();
+            if inside {
+                (self.click_fn.borrow_mut())(&self, pos)
+            }
         };
 
         // todo: add button handling!
@@ -231,6 +338,12 @@ impl WidgetInternal for Input {
         let pos = pos - self.get_offset();
         let size = self.get_computed_size();
 
+        if self.base.drag_text(pos) {
+            return HoverResult {
+                redraw: true,
+                cursor: CursorType::Text,
+            };
+        }
         if pos.x < 0 || pos.y < 0 || pos.x > size.w as i32 || pos.y > size.h as i32 {
             self.clicked.set(false);
             self.hovered.set(None);
@@ -251,6 +364,17 @@ impl WidgetInternal for Input {
     fn handle_overlay_hover(self: Rc<Self>, pos: Offset) -> HoverResult {
         self.handle_hover(pos)
     }
+    fn focusable(&self) -> bool {
+        true
+    }
+    fn set_focused(&self, focused: bool) {
+        self.base
+            .get_text_signal()
+            .update(|t| if focused { t.focus() } else { t.unfocus() });
+    }
+    fn as_input(self: Rc<Self>) -> Option<Rc<dyn InputBase>> {
+        Some(self)
+    }
     fn handle_overlay_button(self: Rc<Self>, pos: Offset, pressed: Option<Rc<Window>>) -> bool {
         if self.is_disabled() {
             return false;
@@ -265,8 +389,10 @@ impl WidgetInternal for Input {
         let is_pressed = pressed.is_some();
         if result {
             self.handle_button(pos, pressed);
-        } else {
+        } else if is_pressed {
             self.base.get_text_signal().update(|t| t.unfocus());
+        } else {
+            self.base.release_text();
         }
         result && is_pressed
     }
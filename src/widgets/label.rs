@@ -1,28 +1,57 @@
-use std::rc::Rc;
+use std::{
+    cell::{Cell, RefCell},
+    rc::{Rc, Weak},
+    time::{Duration, Instant},
+};
 
 use lite_graphics::{Drawable, Rect, color::Rgba};
 
 use crate::{
     Sizing,
-    app::{CursorType, HoverResult},
-    reactive::{RwSignal, SignalGet as _, SignalUpdate as _, create_effect},
+    app::{ClipboardKind, CursorType, HoverResult},
+    event::{Key, Modifiers},
+    reactive::{RwSignal, SignalGet as _, SignalUpdate as _, SignalWith as _, create_effect},
+    shortcut::KeyBindings,
     text::Text,
     window::Window,
 };
 
-use super::{ComputedSize, Offset, Size, Widget, WidgetBase, WidgetExt, WidgetInternal};
+use super::{
+    ComputedSize, Offset, Size, Widget, WidgetBase, WidgetExt, WidgetInternal, input::InputBase,
+};
+
+/// Longest delay between the clicks of a double or triple click.
+const MULTI_CLICK_TIME: Duration = Duration::from_millis(400);
+
+/// Mouse selection state.
+#[derive(Default)]
+struct Selecting {
+    last_click: Cell<Option<(Instant, Offset)>>,
+    clicks: Cell<u8>,
+    /// Set while the button is held.
+    window: RefCell<Option<Weak<Window>>>,
+}
 
 pub struct Label {
     base: Widget,
     text: RwSignal<Text>,
+    /// Width the text wraps at, set by the layout. Drawing wraps the text at it.
+    wrap_width: Cell<Option<u32>>,
+    selectable: Cell<bool>,
+    selecting: Selecting,
 }
 
 impl Label {
     pub(crate) fn new_internal() -> Self {
-        let text = RwSignal::new(Text::new("", 12.0));
+        Self::with_text(RwSignal::new(Text::new("", 12.0)))
+    }
+    fn with_text(text: RwSignal<Text>) -> Self {
         Self {
             base: Widget::new_internal(),
             text,
+            wrap_width: Cell::new(None),
+            selectable: Cell::new(false),
+            selecting: Selecting::default(),
         }
     }
     pub(crate) fn new_dyn_internal<S: AsRef<str> + 'static>(
@@ -30,14 +59,121 @@ impl Label {
     ) -> Self {
         let text = RwSignal::new(Text::new("", 12.0));
         create_effect(move |_| text.update(|text| text.set_text(label())));
-        Self {
-            base: Widget::new_internal(),
-            text,
-        }
+        Self::with_text(text)
     }
     pub(crate) fn get_text_signal(&self) -> RwSignal<Text> {
         self.text
     }
+    /// Let the user select and copy the text.
+    pub fn selectable(self: Rc<Self>, selectable: bool) -> Rc<Self> {
+        self.selectable.set(selectable);
+        self
+    }
+    pub fn selected_text(&self) -> String {
+        self.text.get().selected_text().to_owned()
+    }
+    pub fn set_selection_color(&self, color: Rgba) {
+        self.text.update(|t| t.set_selection_color(color));
+    }
+
+    /// Place the cursor on a press at `pos`, relative to the widget. Double and triple clicks
+    /// select a word and a line.
+    pub(crate) fn press_text(&self, pos: Offset, window: &Rc<Window>) {
+        let now = Instant::now();
+        let clicks = match self.selecting.last_click.get() {
+            Some((time, last))
+                if now - time < MULTI_CLICK_TIME
+                    && (pos.x - last.x).abs() <= 4
+                    && (pos.y - last.y).abs() <= 4 =>
+            {
+                self.selecting.clicks.get() % 3 + 1
+            }
+            _ => 1,
+        };
+        self.selecting.clicks.set(clicks);
+        self.selecting.last_click.set(Some((now, pos)));
+        *self.selecting.window.borrow_mut() = Some(Rc::downgrade(window));
+
+        let pos = self.text_pos(pos);
+        self.text.update(|text| {
+            text.get_cursor_pos(pos);
+            match clicks {
+                2 => text.select_word(),
+                3 => text.select_line(),
+                _ => {}
+            }
+        });
+    }
+    /// Extend the selection to `pos` if the button is held. Returns whether it did.
+    pub(crate) fn drag_text(&self, pos: Offset) -> bool {
+        if self.selecting.window.borrow().is_none() {
+            return false;
+        }
+        let pos = self.text_pos(pos);
+        self.text.update(|text| text.drag_cursor(pos));
+        true
+    }
+    /// End a press, sharing the selection as the primary selection.
+    pub(crate) fn release_text(&self) {
+        let Some(window) = self.selecting.window.take().and_then(|w| w.upgrade()) else {
+            return;
+        };
+        let selected = self.selected_text();
+        if let Some(app) = window.app()
+            && !selected.is_empty()
+        {
+            app.set_clipboard(ClipboardKind::Primary, &selected);
+        }
+    }
+    /// Caret of the text relative to the label.
+    pub(crate) fn caret_rect(&self) -> Option<Rect> {
+        let padding = self.get_padding();
+        let rect = self.text.with_untracked(|text| text.caret_rect())?;
+        Some(Rect::new(
+            Offset::new(rect.x + padding.3 as i32, rect.y + padding.0 as i32),
+            ComputedSize::new(rect.w, rect.h),
+        ))
+    }
+    fn text_pos(&self, pos: Offset) -> Offset {
+        let padding = self.get_padding();
+        pos - Offset::new(padding.3 as i32, padding.0 as i32)
+    }
+    fn contains(&self, pos: Offset) -> bool {
+        let offs = self.get_offset();
+        let size = self.get_computed_size();
+        pos.x >= offs.x
+            && pos.y >= offs.y
+            && pos.x < offs.x + size.w as i32
+            && pos.y < offs.y + size.h as i32
+    }
+}
+
+impl InputBase for Label {
+    fn handle_key(&self, window: &Window, mods: Modifiers, key: Key) {
+        let shift = mods & Modifiers::SHIFT != Modifiers::NONE;
+        if mods & Modifiers::CONTROL != Modifiers::NONE {
+            match key.unshift() {
+                Key::a => self.text.update(|t| t.select_all()),
+                Key::c => {
+                    let selected = self.selected_text();
+                    if let Some(app) = window.app()
+                        && !selected.is_empty()
+                    {
+                        app.set_clipboard(ClipboardKind::Clipboard, &selected);
+                    }
+                }
+                _ => {}
+            }
+            return;
+        }
+        match key {
+            Key::ArrowLeft if shift => self.text.update(|t| t.select_h(-1)),
+            Key::ArrowRight if shift => self.text.update(|t| t.select_h(1)),
+            Key::ArrowLeft => self.text.update(|t| t.move_h(-1)),
+            Key::ArrowRight => self.text.update(|t| t.move_h(1)),
+            _ => {}
+        }
+    }
 }
 
 impl WidgetBase for Label {
@@ -50,6 +186,9 @@ impl WidgetBase for Label {
     fn set_pos(&self, pos: Offset) {
         self.base.set_pos(pos);
     }
+    fn get_pos(&self) -> Offset {
+        self.base.get_pos()
+    }
     fn set_frame(&self, frame: String) {
         self.base.set_frame(frame);
     }
@@ -65,6 +204,15 @@ impl WidgetBase for Label {
     fn get_padding(&self) -> (u32, u32, u32, u32) {
         self.base.get_padding()
     }
+    fn set_padding_sides(&self, padding: (u32, u32, u32, u32)) {
+        self.base.set_padding_sides(padding);
+    }
+    fn set_margin(&self, margin: (u32, u32, u32, u32)) {
+        self.base.set_margin(margin);
+    }
+    fn get_margin(&self) -> (u32, u32, u32, u32) {
+        self.base.get_margin()
+    }
     fn set_border_radius(&self, radius: u32) {
         self.base.set_border_radius(radius);
     }
@@ -111,7 +259,7 @@ impl WidgetInternal for Label {
     fn width_bounds(&self) -> (u32, u32) {
         let padding = self.get_padding();
         let widths = self.text.get().width_bounds();
-        match self.get_size().w {
+        let bounds = match self.get_size().w {
             Sizing::Fixed(w) => (
                 w.max(widths.0 + padding.1 + padding.3),
                 w.max(widths.0 + padding.1 + padding.3),
@@ -120,24 +268,35 @@ impl WidgetInternal for Label {
                 widths.0 + padding.1 + padding.3,
                 widths.1 + padding.1 + padding.3,
             ),
-        }
+        };
+        self.get_size().limit_width(bounds)
     }
     fn set_width(&self, width: u32) {
         self.base.set_width(width);
+        let padding = self.get_padding();
+        self.wrap_width
+            .set(Some(width.saturating_sub(padding.1 + padding.3)));
     }
     fn height_bounds(&self) -> (u32, u32) {
         let padding = self.get_padding();
-        let height = self.text.get().text_height();
-        match self.get_size().h {
+        let height = self
+            .text
+            .with_untracked(|text| match self.wrap_width.get() {
+                Some(width) => text.height_at(width),
+                None => text.text_height(),
+            });
+        let bounds = match self.get_size().h {
             Sizing::Fixed(h) => (
                 h.max(height + padding.0 + padding.2),
                 h.max(height + padding.0 + padding.2),
             ),
             _ => (
-                height + padding.1 + padding.3,
-                height + padding.1 + padding.3,
+                height + padding.0 + padding.2,
+                height + padding.0 + padding.2,
             ),
-        }
+        };
+        self.get_size()
+            .limit_height(bounds, self.get_computed_size().w)
     }
     fn set_height(&self, height: u32) {
         self.base.set_height(height);
@@ -151,6 +310,9 @@ impl WidgetInternal for Label {
     fn set_offset(&self, pos: Offset) {
         self.base.set_offset(pos);
     }
+    fn baseline(&self) -> Option<u32> {
+        Some(self.get_padding().0 + self.text.get().baseline()?)
+    }
     fn get_frame(&self) -> crate::themes::FrameFn {
         self.base.get_frame()
     }
@@ -177,8 +339,45 @@ impl WidgetInternal for Label {
         buf.end_subregion();
     }
 
-    fn handle_button(self: Rc<Self>, _: Offset, _: Option<Rc<Window>>) {}
+    fn key_bindings(&self) -> Option<&KeyBindings> {
+        self.base.key_bindings()
+    }
+    fn handle_button(self: Rc<Self>, pos: Offset, pressed: Option<Rc<Window>>) {
+        if !self.selectable.get() {
+            return;
+        }
+        match pressed {
+            Some(w) if self.contains(pos) => {
+                *w.focus.borrow_mut() = Some(self.clone());
+                self.press_text(pos - self.get_offset(), &w);
+            }
+            Some(_) => self.text.update(|t| t.unfocus()),
+            None => self.release_text(),
+        }
+    }
+    fn handle_overlay_button(self: Rc<Self>, pos: Offset, pressed: Option<Rc<Window>>) -> bool {
+        if !self.selectable.get() {
+            return false;
+        }
+        let inside = self.contains(pos);
+        let is_pressed = pressed.is_some();
+        if inside || !is_pressed {
+            self.handle_button(pos, pressed);
+        } else {
+            self.text.update(|t| t.unfocus());
+        }
+        inside && is_pressed
+    }
+    fn handle_overlay_hover(self: Rc<Self>, pos: Offset) -> HoverResult {
+        self.handle_hover(pos)
+    }
     fn handle_hover(self: Rc<Self>, pos: Offset) -> HoverResult {
+        if self.selectable.get() && self.drag_text(pos - self.get_offset()) {
+            return HoverResult {
+                redraw: true,
+                cursor: CursorType::Text,
+            };
+        }
         let pos = pos - self.get_offset();
         let size = self.get_computed_size();
         if pos.x < 0 || pos.y < 0 || pos.x > size.w as i32 || pos.y > size.h as i32 {
@@ -196,17 +395,55 @@ impl WidgetInternal for Label {
 
 pub fn label<S: AsRef<str> + 'static>(label: S) -> Rc<Label> {
     let text = RwSignal::new(Text::new(label.as_ref(), 12.0));
-    Rc::new(Label {
-        base: Widget::new_internal(),
-        text,
-    })
+    Rc::new(Label::with_text(text))
 }
 
 pub fn dyn_label<S: AsRef<str> + 'static>(label: impl Fn() -> S + 'static) -> Rc<Label> {
-    let text = RwSignal::new(Text::new("", 12.0));
-    create_effect(move |_| text.update(|text| text.set_text(label())));
-    Rc::new(Label {
-        base: Widget::new_internal(),
-        text,
-    })
+    Rc::new(Label::new_dyn_internal(label))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reactive::SignalTrack as _;
+
+    #[test]
+    fn wrapped_height_grows_as_width_shrinks() {
+        let label = label("Some words that wrap onto more lines when squeezed");
+        label.set_font(crate::test_font());
+        let runs = Rc::new(Cell::new(0));
+        create_effect({
+            let (text, runs) = (label.text, runs.clone());
+            move |_| {
+                text.track();
+                runs.set(runs.get() + 1);
+            }
+        });
+
+        let (min, max) = label.width_bounds();
+        let mut last = 0;
+        for width in [max, (min + max) / 2, min] {
+            label.set_width(width);
+            let height = label.height_bounds().0;
+            assert!(height > last, "{height} at {width} wide, {last} wider");
+            last = height;
+        }
+        // Laying out doesn't notify whatever reads the text.
+        assert_eq!(runs.get(), 1);
+    }
+
+    #[test]
+    fn size_limits() {
+        let label = label("Some words").size(Size::default().min(500, 100));
+        label.set_font(crate::test_font());
+        assert_eq!(label.width_bounds(), (500, 500));
+        label.set_width(500);
+        assert_eq!(label.height_bounds(), (100, 100));
+
+        let label = label.size(Size::default().max(40, 10));
+        let (min, max) = label.width_bounds();
+        assert!(min <= 40 && max == 40, "{min} to {max}");
+        label.set_width(40);
+        assert_eq!(label.height_bounds(), (10, 10));
+    }
 }
@@ -1,19 +1,26 @@
 pub mod button;
 pub mod drop_down;
+pub mod grid;
 pub mod input;
 pub mod label;
+pub mod scroll_view;
 pub mod stack;
+pub mod switch;
+pub mod virtual_list;
 pub mod widget;
+pub mod zstack;
 
 pub use widget::Widget;
 
 use std::{any::Any, rc::Rc};
 
-use lite_graphics::{Buffer, Drawable, Offset, color::Rgba};
+use lite_graphics::{Buffer, Drawable, Offset, Rect, color::Rgba};
 
 use crate::{
     ComputedSize, Size,
     app::{CursorType, HoverResult},
+    event::{Key, Modifiers},
+    shortcut::{KeyBindings, Shortcut},
     themes,
     window::Window,
 };
@@ -64,14 +71,20 @@ impl<W: WidgetBase> IntoWidget for Rc<W> {
 pub trait WidgetBase: WidgetInternal {
     fn set_size(&self, size: Size);
     fn get_size(&self) -> Size;
+    /// Where a [`zstack`](zstack::zstack) puts it, other containers lay it out themselves.
     fn set_pos(&self, pos: Offset);
-    // get_pos missing
+    fn get_pos(&self) -> Offset;
     fn set_frame(&self, frame: String);
     // get_frame missing
     fn set_background_color(&self, color: Rgba);
     fn get_background_color(&self) -> Rgba;
     fn set_padding(&self, padding: u32);
     fn get_padding(&self) -> (u32, u32, u32, u32);
+    /// Padding of the top, right, bottom and left sides.
+    fn set_padding_sides(&self, padding: (u32, u32, u32, u32));
+    /// Space kept around it in a stack, top, right, bottom and left.
+    fn set_margin(&self, margin: (u32, u32, u32, u32));
+    fn get_margin(&self) -> (u32, u32, u32, u32);
     fn set_border_radius(&self, radius: u32);
     fn get_border_radius(&self) -> u32;
     fn set_color(&self, color: Rgba);
@@ -121,6 +134,28 @@ pub trait WidgetExt: WidgetBase {
         self.set_padding(padding);
         self
     }
+    fn padding_sides(self: Rc<Self>, top: u32, right: u32, bottom: u32, left: u32) -> Rc<Self>
+    where
+        Self: Sized,
+    {
+        self.set_padding_sides((top, right, bottom, left));
+        self
+    }
+    /// `x` on the left and right, `y` on the top and bottom.
+    fn padding_xy(self: Rc<Self>, x: u32, y: u32) -> Rc<Self>
+    where
+        Self: Sized,
+    {
+        self.set_padding_sides((y, x, y, x));
+        self
+    }
+    fn margin(self: Rc<Self>, top: u32, right: u32, bottom: u32, left: u32) -> Rc<Self>
+    where
+        Self: Sized,
+    {
+        self.set_margin((top, right, bottom, left));
+        self
+    }
     fn border_radius(self: Rc<Self>, radius: u32) -> Rc<Self>
     where
         Self: Sized,
@@ -156,6 +191,57 @@ pub trait WidgetExt: WidgetBase {
     fn on_click<F: FnMut(&Self, Offset) + 'static>(self: Rc<Self>, f: F) -> Rc<Self>
     where
         Self: Sized;
+    /// Run `f` on `shortcut` while this widget or one inside it has focus.
+    fn on_shortcut<S: Into<Shortcut>, F: FnMut(&Self) + 'static>(
+        self: Rc<Self>,
+        shortcut: S,
+        description: &str,
+        mut f: F,
+    ) -> Rc<Self>
+    where
+        Self: Sized,
+    {
+        if let Some(bindings) = self.key_bindings() {
+            let this = Rc::downgrade(&self);
+            bindings.shortcuts().add(shortcut, description, move || {
+                if let Some(this) = this.upgrade() {
+                    f(&this)
+                }
+            });
+        }
+        self
+    }
+    /// Run `f` on every key press while this widget or one inside it has focus, again while the key
+    /// is held.
+    fn on_key_down<F: FnMut(&Self, Modifiers, Key) + 'static>(self: Rc<Self>, mut f: F) -> Rc<Self>
+    where
+        Self: Sized,
+    {
+        if let Some(bindings) = self.key_bindings() {
+            let this = Rc::downgrade(&self);
+            bindings.set_key_handler(true, move |mods, key| {
+                if let Some(this) = this.upgrade() {
+                    f(&this, mods, key)
+                }
+            });
+        }
+        self
+    }
+    /// Run `f` on every key release while this widget or one inside it has focus.
+    fn on_key_up<F: FnMut(&Self, Modifiers, Key) + 'static>(self: Rc<Self>, mut f: F) -> Rc<Self>
+    where
+        Self: Sized,
+    {
+        if let Some(bindings) = self.key_bindings() {
+            let this = Rc::downgrade(&self);
+            bindings.set_key_handler(false, move |mods, key| {
+                if let Some(this) = this.upgrade() {
+                    f(&this, mods, key)
+                }
+            });
+        }
+        self
+    }
 }
 
 /// Internal functions
@@ -163,19 +249,52 @@ pub trait WidgetInternal: Any {
     fn set_font(&self, font: ab_glyph::FontArc);
     fn width_bounds(&self) -> (u32, u32);
     fn set_width(&self, width: u32);
+    /// Called after `set_width`, so it may depend on the width given, like wrapped text does.
     fn height_bounds(&self) -> (u32, u32);
     fn set_height(&self, height: u32);
     fn get_computed_size(&self) -> ComputedSize;
     fn get_offset(&self) -> Offset;
     fn set_offset(&self, pos: Offset);
+    /// Part of the widget that can show, relative to it. Called after `set_offset`.
+    fn set_viewport(&self, _rect: Rect) {}
+    /// Distance from the top to the baseline of its first line of text, if it has one.
+    fn baseline(&self) -> Option<u32> {
+        None
+    }
     fn get_frame(&self) -> themes::FrameFn;
     fn draw_frame(&self, buf: &dyn Drawable);
     fn draw(&self, buf: &mut dyn Drawable);
     fn draw_overlays(&self, _buf: &mut Buffer) {}
+    /// Direct children, positioned relative to this widget.
+    fn children(&self) -> Vec<Rc<dyn WidgetBase>> {
+        vec![]
+    }
+    /// Whether Tab can move the keyboard focus here.
+    fn focusable(&self) -> bool {
+        false
+    }
+    /// Keyboard focus moved here, or away.
+    fn set_focused(&self, _focused: bool) {}
+    /// The key handler that takes over while focused, if any.
+    fn as_input(self: Rc<Self>) -> Option<Rc<dyn input::InputBase>> {
+        None
+    }
+    /// Enter or Space while focused.
+    fn activate(self: Rc<Self>) {}
+    /// Shortcuts and key handlers, if it can take any.
+    fn key_bindings(&self) -> Option<&KeyBindings> {
+        None
+    }
 
     fn handle_button(self: Rc<Self>, pos: Offset, pressed: Option<Rc<Window>>);
     /// Return: If Should Redraw
     fn handle_hover(self: Rc<Self>, pos: Offset) -> HoverResult;
+    /// Scrolled by `(dx, dy)` wheel notches with the pointer at `pos`, `discrete` from a wheel.
+    ///
+    /// Return: If it scrolled something, which redraws
+    fn handle_scroll(self: Rc<Self>, _pos: Offset, _dx: f32, _dy: f32, _discrete: bool) -> bool {
+        false
+    }
     fn handle_overlay_button(self: Rc<Self>, _pos: Offset, _pressed: Option<Rc<Window>>) -> bool {
         false
     }
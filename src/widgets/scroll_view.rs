@@ -0,0 +1,563 @@
+use std::{cell::Cell, rc::Rc};
+
+use lite_graphics::{Buffer, Drawable, Offset, Rect, color::Rgba};
+
+use crate::{
+    ComputedSize, Size, Sizing,
+    app::{COLORS, CursorType, FRAMES, HoverResult},
+    event::{Key, Modifiers},
+    shortcut::KeyBindings,
+    themes,
+    window::Window,
+};
+
+use super::{IntoWidget, Widget, WidgetBase, WidgetExt, WidgetInternal, input::InputBase};
+
+/// Thickness of the scrollbars.
+const BAR: u32 = 8;
+/// Smallest length of a scrollbar thumb.
+const MIN_THUMB: u32 = 16;
+/// Smallest viewport along a scrolling axis.
+const MIN_VIEW: u32 = 32;
+/// Pixels per wheel notch or arrow key.
+const LINE: f32 = 40.0;
+
+/// Shows part of its child, scrolled by the wheel, the keyboard or by dragging the scrollbars.
+///
+/// The child gets at least the visible size, more if it needs it. Padding scrolls with it.
+pub struct ScrollView {
+    base: Widget,
+    child: Rc<dyn WidgetBase>,
+    horizontal: Cell<bool>,
+    vertical: Cell<bool>,
+    /// How far the content is scrolled, never negative.
+    scroll: Cell<Offset>,
+    /// Scrollbar being dragged, vertical or not, with where the thumb was grabbed.
+    drag: Cell<Option<(bool, i32)>>,
+}
+
+impl ScrollView {
+    fn new_internal(child: Rc<dyn WidgetBase>) -> Rc<Self> {
+        let this = Self {
+            base: Widget::new_internal(),
+            child,
+            horizontal: Cell::new(false),
+            vertical: Cell::new(true),
+            scroll: Cell::new(Offset::default()),
+            drag: Cell::new(None),
+        };
+        this.base.set_frame(themes::FrameType::Box.to_string());
+        this.base.set_size(Size::fill());
+        Rc::new(this)
+    }
+    /// Scroll sideways too, the child isn't narrowed to fit then. Off by default.
+    pub fn horizontal(self: Rc<Self>, enabled: bool) -> Rc<Self> {
+        self.horizontal.set(enabled);
+        self
+    }
+    /// Scroll up and down, the child isn't shortened to fit then. On by default.
+    pub fn vertical(self: Rc<Self>, enabled: bool) -> Rc<Self> {
+        self.vertical.set(enabled);
+        self
+    }
+    /// How far the content is scrolled.
+    pub fn scroll_offset(&self) -> Offset {
+        self.scroll.get()
+    }
+    /// Scroll so `offset` of the content is at the top left, as far as it goes.
+    pub fn scroll_to(&self, offset: Offset) {
+        self.scroll.set(offset);
+        self.clamp_scroll();
+        self.place_child();
+    }
+    /// Scroll as little as needed to show `widget`, or its top left if it doesn't fit.
+    ///
+    /// Returns `false` if `widget` isn't inside this view.
+    pub fn scroll_to_widget(&self, widget: &Rc<dyn WidgetBase>) -> bool {
+        fn find(
+            widget: Rc<dyn WidgetBase>,
+            parent: Offset,
+            target: &Rc<dyn WidgetBase>,
+        ) -> Option<Rect> {
+            let offs = parent + widget.get_offset();
+            if Rc::ptr_eq(&widget, target) {
+                return Some(Rect::new(offs, widget.get_computed_size()));
+            }
+            widget
+                .children()
+                .into_iter()
+                .find_map(|c| find(c, offs, target))
+        }
+        let Some(rect) = find(self.child.clone(), Offset::default(), widget) else {
+            return false;
+        };
+        let view = self.viewport();
+        let scroll = self.scroll.get();
+        let reveal = |start: i32, len: u32, view: u32, scroll: i32| {
+            let start = start + scroll;
+            if start < scroll || len > view {
+                start
+            } else if start + len as i32 > scroll + view as i32 {
+                start + len as i32 - view as i32
+            } else {
+                scroll
+            }
+        };
+        self.scroll_to(Offset::new(
+            reveal(rect.x, rect.w, view.w, scroll.x),
+            reveal(rect.y, rect.h, view.h, scroll.y),
+        ));
+        true
+    }
+
+    /// Visible part of the content, from the top left.
+    fn viewport(&self) -> ComputedSize {
+        let size = self.get_computed_size();
+        ComputedSize::new(
+            size.w
+                .saturating_sub(if self.vertical.get() { BAR } else { 0 }),
+            size.h
+                .saturating_sub(if self.horizontal.get() { BAR } else { 0 }),
+        )
+    }
+    fn max_scroll(&self) -> Offset {
+        let padding = self.get_padding();
+        let content = self.child.get_computed_size();
+        let view = self.viewport();
+        Offset::new(
+            (content.w + padding.1 + padding.3).saturating_sub(view.w) as i32,
+            (content.h + padding.0 + padding.2).saturating_sub(view.h) as i32,
+        )
+    }
+    fn clamp_scroll(&self) {
+        let max = self.max_scroll();
+        let scroll = self.scroll.get();
+        self.scroll.set(Offset::new(
+            scroll.x.clamp(0, max.x),
+            scroll.y.clamp(0, max.y),
+        ));
+    }
+    fn place_child(&self) {
+        let padding = self.get_padding();
+        let scroll = self.scroll.get();
+        self.child.set_offset(Offset::new(
+            padding.3 as i32 - scroll.x,
+            padding.0 as i32 - scroll.y,
+        ));
+        self.child.set_viewport(Rect::new(
+            Offset::new(scroll.x - padding.3 as i32, scroll.y - padding.0 as i32),
+            self.viewport(),
+        ));
+    }
+    /// Start and length of the thumb of the scrollbar along `vertical`, `None` if there's
+    /// nothing to scroll.
+    fn thumb(&self, vertical: bool) -> Option<(i32, u32)> {
+        let padding = self.get_padding();
+        let content = self.child.get_computed_size();
+        let view = self.viewport();
+        let (len, content, scroll, max) = if vertical {
+            let max = self.max_scroll().y;
+            (
+                view.h,
+                content.h + padding.0 + padding.2,
+                self.scroll.get().y,
+                max,
+            )
+        } else {
+            let max = self.max_scroll().x;
+            (
+                view.w,
+                content.w + padding.1 + padding.3,
+                self.scroll.get().x,
+                max,
+            )
+        };
+        if max == 0 {
+            return None;
+        }
+        let thumb = (len as u64 * len as u64 / content.max(1) as u64) as u32;
+        let thumb = thumb.clamp(MIN_THUMB.min(len), len);
+        let start = (len - thumb) as i64 * scroll as i64 / max as i64;
+        Some((start as i32, thumb))
+    }
+    /// Bounds of the scrollbar along `vertical`.
+    fn track(&self, vertical: bool) -> Rect {
+        let view = self.viewport();
+        if vertical {
+            Rect::new(
+                Offset::new(view.w as i32, 0),
+                ComputedSize::new(BAR, view.h),
+            )
+        } else {
+            Rect::new(
+                Offset::new(0, view.h as i32),
+                ComputedSize::new(view.w, BAR),
+            )
+        }
+    }
+    fn scroll_by(&self, dx: i32, dy: i32) -> bool {
+        let before = self.scroll.get();
+        self.scroll_to(Offset::new(before.x + dx, before.y + dy));
+        self.scroll.get() != before
+    }
+    /// Move the thumb along `vertical` so the point grabbed is at `pos`.
+    fn drag_to(&self, vertical: bool, pos: Offset, grab: i32) {
+        let Some((_, thumb)) = self.thumb(vertical) else {
+            return;
+        };
+        let track = self.track(vertical);
+        let max = self.max_scroll();
+        let (along, len, max) = if vertical {
+            (pos.y - track.y, track.h, max.y)
+        } else {
+            (pos.x - track.x, track.w, max.x)
+        };
+        let room = (len - thumb).max(1) as i64;
+        let target = ((along - grab) as i64 * max as i64 / room) as i32;
+        let scroll = self.scroll.get();
+        self.scroll_to(if vertical {
+            Offset::new(scroll.x, target)
+        } else {
+            Offset::new(target, scroll.y)
+        });
+    }
+    fn draw_scrollbar(&self, buf: &mut dyn Drawable, vertical: bool) {
+        let Some(frame) =
+            FRAMES.with_borrow(|f| f.get(&themes::FrameType::Scrollbar.to_string()).cloned())
+        else {
+            return;
+        };
+        let color = |name: &str| COLORS.with_borrow(|c| c.get(name).copied());
+        let track = self.track(vertical);
+        if let Some(color) = color("ScrollbarTrack") {
+            buf.subregion(track);
+            frame(buf, ComputedSize::new(track.w, track.h), color);
+            buf.end_subregion();
+        }
+        let Some((start, len)) = self.thumb(vertical) else {
+            return;
+        };
+        let thumb = if vertical {
+            Rect::new(Offset::new(track.x, start), ComputedSize::new(BAR, len))
+        } else {
+            Rect::new(Offset::new(start, track.y), ComputedSize::new(len, BAR))
+        };
+        let name = if self.drag.get().is_some_and(|(v, _)| v == vertical) {
+            "ScrollbarThumbActive"
+        } else {
+            "ScrollbarThumb"
+        };
+        if let Some(color) = color(name) {
+            buf.subregion(thumb);
+            frame(buf, ComputedSize::new(thumb.w, thumb.h), color);
+            buf.end_subregion();
+        }
+    }
+}
+
+impl InputBase for ScrollView {
+    fn handle_key(&self, _: &Window, mods: Modifiers, key: Key) {
+        if mods & (Modifiers::CONTROL | Modifiers::ALT | Modifiers::SUPER) != Modifiers::NONE {
+            return;
+        }
+        let view = self.viewport();
+        let line = LINE as i32;
+        let page = view.h as i32 - line;
+        match key {
+            Key::ArrowUp => self.scroll_by(0, -line),
+            Key::ArrowDown => self.scroll_by(0, line),
+            Key::ArrowLeft => self.scroll_by(-line, 0),
+            Key::ArrowRight => self.scroll_by(line, 0),
+            Key::PgUp => self.scroll_by(0, -page),
+            Key::PgDn => self.scroll_by(0, page),
+            Key::Space if mods & Modifiers::SHIFT != Modifiers::NONE => self.scroll_by(0, -page),
+            Key::Space => self.scroll_by(0, page),
+            Key::Home => self.scroll_by(0, i32::MIN / 2),
+            Key::End => self.scroll_by(0, i32::MAX / 2),
+            _ => false,
+        };
+    }
+}
+
+impl WidgetBase for ScrollView {
+    fn set_size(&self, size: Size) {
+        self.base.set_size(size);
+    }
+    fn get_size(&self) -> Size {
+        self.base.get_size()
+    }
+    fn set_pos(&self, pos: Offset) {
+        self.base.set_pos(pos);
+    }
+    fn get_pos(&self) -> Offset {
+        self.base.get_pos()
+    }
+    fn set_frame(&self, frame: String) {
+        self.base.set_frame(frame);
+    }
+    fn set_background_color(&self, color: Rgba) {
+        self.base.set_background_color(color);
+    }
+    fn get_background_color(&self) -> Rgba {
+        self.base.get_background_color()
+    }
+    fn set_padding(&self, padding: u32) {
+        self.base.set_padding(padding);
+    }
+    fn get_padding(&self) -> (u32, u32, u32, u32) {
+        self.base.get_padding()
+    }
+    fn set_padding_sides(&self, padding: (u32, u32, u32, u32)) {
+        self.base.set_padding_sides(padding);
+    }
+    fn set_margin(&self, margin: (u32, u32, u32, u32)) {
+        self.base.set_margin(margin);
+    }
+    fn get_margin(&self) -> (u32, u32, u32, u32) {
+        self.base.get_margin()
+    }
+    fn set_border_radius(&self, radius: u32) {
+        self.base.set_border_radius(radius);
+    }
+    fn get_border_radius(&self) -> u32 {
+        self.base.get_border_radius()
+    }
+    // No meaning here
+    fn set_color(&self, _color: Rgba) {}
+    // No meaning here
+    fn set_text(&self, _text: &str) {}
+    fn get_text(&self) -> String {
+        "".to_owned()
+    }
+    fn set_text_align(&self, _: core::fmt::Alignment) {}
+    fn set_disabled(&self, _: bool) {}
+    fn is_disabled(&self) -> bool {
+        false
+    }
+}
+
+impl WidgetExt for ScrollView {
+    fn new() -> Rc<Self> {
+        Self::new_internal(Widget::new())
+    }
+
+    fn on_hover<F: FnMut(&Self, Offset) + 'static>(self: Rc<Self>, _f: F) -> Rc<Self> {
+        self
+    }
+    fn on_click<F: FnMut(&Self, Offset) + 'static>(self: Rc<Self>, _f: F) -> Rc<Self> {
+        self
+    }
+}
+
+impl WidgetInternal for ScrollView {
+    fn set_font(&self, font: ab_glyph::FontArc) {
+        self.child.set_font(font);
+    }
+    fn width_bounds(&self) -> (u32, u32) {
+        let padding = self.get_padding();
+        let extra = padding.1 + padding.3 + if self.vertical.get() { BAR } else { 0 };
+        let child = self.child.width_bounds();
+        let (min, max) = if self.horizontal.get() {
+            (MIN_VIEW, child.1.saturating_add(extra))
+        } else {
+            (child.0 + extra, child.1.saturating_add(extra))
+        };
+        match self.get_size().w {
+            Sizing::Fixed(w) => (w.max(min), w.max(min)),
+            Sizing::Stretch(0) => (min, min),
+            _ => (min, max.max(min)),
+        }
+    }
+    fn set_width(&self, width: u32) {
+        let padding = self.get_padding();
+        self.base.set_width(width);
+        let inner = self.viewport().w.saturating_sub(padding.1 + padding.3);
+        if self.horizontal.get() {
+            self.child.set_width(self.child.width_bounds().0.max(inner));
+        } else {
+            self.child.set_width(inner);
+        }
+        self.clamp_scroll();
+    }
+    fn height_bounds(&self) -> (u32, u32) {
+        let padding = self.get_padding();
+        let extra = padding.0 + padding.2 + if self.horizontal.get() { BAR } else { 0 };
+        let child = self.child.height_bounds();
+        let (min, max) = if self.vertical.get() {
+            (MIN_VIEW, child.1.saturating_add(extra))
+        } else {
+            (child.0 + extra, child.1.saturating_add(extra))
+        };
+        match self.get_size().h {
+            Sizing::Fixed(h) => (h.max(min), h.max(min)),
+            Sizing::Stretch(0) => (min, min),
+            _ => (min, max.max(min)),
+        }
+    }
+    fn set_height(&self, height: u32) {
+        let padding = self.get_padding();
+        self.base.set_height(height);
+        let inner = self.viewport().h.saturating_sub(padding.0 + padding.2);
+        if self.vertical.get() {
+            self.child
+                .set_height(self.child.height_bounds().0.max(inner));
+        } else {
+            self.child.set_height(inner);
+        }
+        self.clamp_scroll();
+    }
+    fn get_computed_size(&self) -> ComputedSize {
+        self.base.get_computed_size()
+    }
+    fn get_offset(&self) -> Offset {
+        self.base.get_offset()
+    }
+    fn set_offset(&self, pos: Offset) {
+        self.base.set_offset(pos);
+        self.place_child();
+    }
+    fn get_frame(&self) -> themes::FrameFn {
+        self.base.get_frame()
+    }
+    fn draw_frame(&self, buf: &dyn Drawable) {
+        self.base.draw_frame(buf);
+    }
+    fn draw(&self, buf: &mut dyn Drawable) {
+        let bounds = Rect::new(self.get_offset(), self.get_computed_size());
+        buf.subregion(bounds);
+        self.draw_frame(buf);
+        buf.subregion(Rect::new(Offset::default(), self.viewport()));
+        self.child.draw(buf);
+        buf.end_subregion();
+        if self.vertical.get() {
+            self.draw_scrollbar(buf, true);
+        }
+        if self.horizontal.get() {
+            self.draw_scrollbar(buf, false);
+        }
+        buf.end_subregion();
+    }
+    fn draw_overlays(&self, buf: &mut Buffer) {
+        self.child.draw_overlays(buf);
+    }
+    fn children(&self) -> Vec<Rc<dyn WidgetBase>> {
+        vec![self.child.clone()]
+    }
+    fn focusable(&self) -> bool {
+        true
+    }
+    fn as_input(self: Rc<Self>) -> Option<Rc<dyn InputBase>> {
+        Some(self)
+    }
+
+    fn key_bindings(&self) -> Option<&KeyBindings> {
+        self.base.key_bindings()
+    }
+    fn handle_button(self: Rc<Self>, pos: Offset, pressed: Option<Rc<Window>>) {
+        let pos = pos - self.get_offset();
+        if pressed.is_none() {
+            self.drag.set(None);
+        }
+        let size = self.get_computed_size();
+        if pos.x < 0 || pos.y < 0 || pos.x > size.w as i32 || pos.y > size.h as i32 {
+            return;
+        }
+        let view = self.viewport();
+        if pos.x < view.w as i32 && pos.y < view.h as i32 {
+            self.child.clone().handle_button(pos, pressed.clone());
+            // Keys scroll unless something inside takes them.
+            if let Some(w) = pressed
+                && w.focus.borrow().is_none()
+                && !focusable_at(self.child.clone(), Offset::default(), pos)
+            {
+                *w.focus.borrow_mut() = Some(self.clone());
+            }
+            return;
+        }
+        let Some(w) = pressed else {
+            return;
+        };
+        *w.focus.borrow_mut() = Some(self.clone());
+        let vertical = pos.x >= view.w as i32;
+        let Some((start, len)) = self.thumb(vertical) else {
+            return;
+        };
+        let along = if vertical { pos.y } else { pos.x };
+        if along >= start && along < start + len as i32 {
+            self.drag.set(Some((vertical, along - start)));
+        } else {
+            // A page towards the click.
+            let page = if vertical { view.h } else { view.w } as i32 - LINE as i32;
+            let page = if along < start { -page } else { page };
+            if vertical {
+                self.scroll_by(0, page);
+            } else {
+                self.scroll_by(page, 0);
+            }
+        }
+    }
+    fn handle_hover(self: Rc<Self>, pos: Offset) -> HoverResult {
+        let pos = pos - self.get_offset();
+        if let Some((vertical, grab)) = self.drag.get() {
+            self.drag_to(vertical, pos, grab);
+            return HoverResult {
+                redraw: true,
+                cursor: CursorType::Arrow,
+            };
+        }
+        let view = self.viewport();
+        let inside = pos.x >= 0 && pos.y >= 0 && pos.x < view.w as i32 && pos.y < view.h as i32;
+        // Hidden parts of the child aren't hovered.
+        let pos = if inside {
+            pos
+        } else {
+            Offset::new(i32::MIN / 2, i32::MIN / 2)
+        };
+        self.child.clone().handle_hover(pos)
+    }
+    fn handle_scroll(self: Rc<Self>, pos: Offset, dx: f32, dy: f32, discrete: bool) -> bool {
+        let pos = pos - self.get_offset();
+        let size = self.get_computed_size();
+        if pos.x < 0 || pos.y < 0 || pos.x > size.w as i32 || pos.y > size.h as i32 {
+            return false;
+        }
+        if self.child.clone().handle_scroll(pos, dx, dy, discrete) {
+            return true;
+        }
+        // At the end already, a view around this one may go on.
+        self.scroll_by((dx * LINE) as i32, (dy * LINE) as i32)
+    }
+    fn handle_overlay_button(self: Rc<Self>, pos: Offset, pressed: Option<Rc<Window>>) -> bool {
+        self.child.clone().handle_overlay_button(pos, pressed)
+    }
+    // Moves go here while this view has focus, so a drag goes on.
+    fn handle_overlay_hover(self: Rc<Self>, pos: Offset) -> HoverResult {
+        if self.drag.get().is_some() {
+            return self.handle_hover(pos);
+        }
+        self.child.clone().handle_overlay_hover(pos)
+    }
+}
+
+/// Whether a focusable widget in `widget` is at `pos`, relative to its parent at `parent`.
+fn focusable_at(widget: Rc<dyn WidgetBase>, parent: Offset, pos: Offset) -> bool {
+    let offs = parent + widget.get_offset();
+    let size = widget.get_computed_size();
+    if pos.x < offs.x
+        || pos.y < offs.y
+        || pos.x >= offs.x + size.w as i32
+        || pos.y >= offs.y + size.h as i32
+    {
+        return false;
+    }
+    (widget.focusable() && !widget.is_disabled())
+        || widget
+            .children()
+            .into_iter()
+            .any(|c| focusable_at(c, offs, pos))
+}
+
+/// Scroll `child` when it doesn't fit.
+pub fn scroll_view<W: IntoWidget + 'static>(child: W) -> Rc<ScrollView> {
+    ScrollView::new_internal(child.into_widget())
+}
@@ -1,5 +1,7 @@
 use std::{
     cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+    hash::Hash,
     marker::PhantomData,
     rc::Rc,
 };
@@ -7,33 +9,126 @@ use std::{
 use lite_graphics::{Buffer, Drawable, Rect, color::Rgba};
 
 use crate::{
-    Sizing,
-    app::{CursorType, HoverResult},
+    Align, AlignItems, JustifyContent, Sizing,
+    app::{CursorType, HoverResult, NEEDS_REDRAW},
+    reactive::{Scope, create_effect, untrack, with_scope},
+    shortcut::KeyBindings,
     themes,
     window::Window,
 };
 
 use super::{
-    ComputedSize, Offset, Size, Widget, WidgetBase, WidgetExt, WidgetGroup, WidgetInternal,
+    ComputedSize, IntoWidget, Offset, Size, Widget, WidgetBase, WidgetExt, WidgetGroup,
+    WidgetInternal,
 };
 
-pub trait Direction {}
+pub trait Direction {
+    /// Whether children go left to right, or top to bottom.
+    const HORIZONTAL: bool;
+}
 
 pub struct Horizontal;
 pub struct Vertical;
 
-impl Direction for Horizontal {}
-impl Direction for Vertical {}
+impl Direction for Horizontal {
+    const HORIZONTAL: bool = true;
+}
+impl Direction for Vertical {
+    const HORIZONTAL: bool = false;
+}
+/// Left to right, onto a new line when out of room.
+pub struct Wrap;
+impl Direction for Wrap {
+    const HORIZONTAL: bool = true;
+}
+
+/// Bounds of `widget` across `horizontal` or not.
+fn bounds(widget: &Rc<dyn WidgetBase>, horizontal: bool) -> (u32, u32) {
+    match horizontal {
+        true => widget.width_bounds(),
+        false => widget.height_bounds(),
+    }
+}
+
+fn sizing(widget: &dyn WidgetBase, horizontal: bool) -> Sizing {
+    match horizontal {
+        true => widget.get_size().w,
+        false => widget.get_size().h,
+    }
+}
+
+fn set_len(widget: &Rc<dyn WidgetBase>, horizontal: bool, len: u32) {
+    match horizontal {
+        true => widget.set_width(len),
+        false => widget.set_height(len),
+    }
+}
+
+/// `bounds` of `widget` along `horizontal` or not, within the limits of its [`Size`].
+fn limit(widget: &dyn WidgetBase, horizontal: bool, bounds: (u32, u32)) -> (u32, u32) {
+    let size = widget.get_size();
+    match horizontal {
+        true => size.limit_width(bounds),
+        false => size.limit_height(bounds, widget.get_computed_size().w),
+    }
+}
+
+/// Largest `widget` may be made along `horizontal` or not.
+fn most(widget: &Rc<dyn WidgetBase>, horizontal: bool) -> u32 {
+    let size = widget.get_size();
+    match horizontal {
+        true => size.get_max().0,
+        // The aspect ratio gives the one height it takes.
+        false if size.get_aspect().is_some() => widget.height_bounds().1,
+        false => size.get_max().1,
+    }
+}
+
+/// Give `rem` out to `sizes` by `weights`, none past its `most`, again whenever one reaches it.
+/// Returns what's left.
+fn hand_out(sizes: &mut [u32], most: &[u32], weights: &[u64], mut rem: u32) -> u32 {
+    loop {
+        let open: Vec<usize> = (0..sizes.len())
+            .filter(|&i| weights[i] > 0 && sizes[i] < most[i])
+            .collect();
+        let mut total: u64 = open.iter().map(|&i| weights[i]).sum();
+        if rem == 0 || total == 0 {
+            return rem;
+        }
+        let mut left = rem;
+        for i in open {
+            let extra = (left as u64 * weights[i] / total) as u32;
+            left -= extra;
+            total -= weights[i];
+            let extra = extra.min(most[i] - sizes[i]);
+            sizes[i] += extra;
+            rem -= extra;
+        }
+    }
+}
+
+/// Leading and trailing sides of top, right, bottom and left ones.
+fn sides(sides: (u32, u32, u32, u32), horizontal: bool) -> (u32, u32) {
+    match horizontal {
+        true => (sides.3, sides.1),
+        false => (sides.0, sides.2),
+    }
+}
 
 pub struct Stack<D: Direction> {
     base: Widget,
     gap: Cell<u32>,
+    align_items: Cell<AlignItems>,
+    justify_content: Cell<JustifyContent>,
     children: RefCell<Vec<Rc<dyn WidgetBase>>>,
+    /// Given to children added later.
+    font: RefCell<Option<ab_glyph::FontArc>>,
     _marker: PhantomData<D>,
 }
 
 pub type HStack = Stack<Horizontal>;
 pub type VStack = Stack<Vertical>;
+pub type Flow = Stack<Wrap>;
 
 impl<D: Direction> Stack<D>
 where
@@ -43,7 +138,10 @@ where
         let this = Self {
             base: Widget::new_internal(),
             gap: Cell::new(gap),
+            align_items: Cell::new(AlignItems::default()),
+            justify_content: Cell::new(JustifyContent::default()),
             children: RefCell::new(children),
+            font: RefCell::new(None),
             _marker: PhantomData,
         };
         this.base.set_frame(themes::FrameType::Box.to_string());
@@ -53,6 +151,240 @@ where
         self.gap.set(gap);
         self
     }
+    /// Place children across the stack. [`AlignItems::Start`] by default.
+    pub fn align_items(self: Rc<Self>, align: AlignItems) -> Rc<Self> {
+        self.align_items.set(align);
+        self
+    }
+    /// Share the room left along the stack. [`JustifyContent::Start`] by default.
+    pub fn justify_content(self: Rc<Self>, justify: JustifyContent) -> Rc<Self> {
+        self.justify_content.set(justify);
+        self
+    }
+    fn main_bounds(&self) -> (u32, u32) {
+        let horizontal = D::HORIZONTAL;
+        let children = self.children.borrow();
+        let padding = sides(self.get_padding(), horizontal);
+        let gaps = self.gap.get() * (children.len() as u32).saturating_sub(1);
+        let mut min = padding.0 + padding.1 + gaps;
+        let mut max = min;
+        for child in &*children {
+            let bounds = bounds(child, horizontal);
+            let margin = sides(child.get_margin(), horizontal);
+            min += bounds.0 + margin.0 + margin.1;
+            max = max.saturating_add(bounds.1.saturating_add(margin.0 + margin.1));
+        }
+        let max = max.max(min);
+        let bounds = match sizing(self, horizontal) {
+            Sizing::Fixed(w) => (w.clamp(min, max), w.clamp(min, max)),
+            _ => (min, max),
+        };
+        limit(self, horizontal, bounds)
+    }
+    fn cross_bounds(&self) -> (u32, u32) {
+        let horizontal = !D::HORIZONTAL;
+        let children = self.children.borrow();
+        let padding = sides(self.get_padding(), horizontal);
+        let (mut min, mut max) = (0, 0);
+        for child in &*children {
+            let bounds = bounds(child, horizontal);
+            let margin = sides(child.get_margin(), horizontal);
+            min = min.max(bounds.0 + margin.0 + margin.1);
+            max = max.max(bounds.1.saturating_add(margin.0 + margin.1));
+        }
+        // Lining up baselines can take more height than the tallest child.
+        if let Some((above, below)) = self.baselines(&children) {
+            min = min.max(above + below);
+        }
+        let min = min + padding.0 + padding.1;
+        let max = max.saturating_add(padding.0 + padding.1).max(min);
+        let bounds = match sizing(self, horizontal) {
+            Sizing::Fixed(w) => (w.clamp(min, max), w.clamp(min, max)),
+            Sizing::Stretch(0) if D::HORIZONTAL => (min, min),
+            _ => (min, max),
+        };
+        limit(self, horizontal, bounds)
+    }
+    /// Room taken above and below the baseline when lining `children` up on it, `None` unless
+    /// that's how a horizontal stack aligns them.
+    fn baselines(&self, children: &[Rc<dyn WidgetBase>]) -> Option<(u32, u32)> {
+        if !D::HORIZONTAL || self.align_items.get() != AlignItems::Baseline {
+            return None;
+        }
+        let (mut above, mut below) = (0, 0);
+        for child in children {
+            let margin = child.get_margin();
+            let height = child.height_bounds().0;
+            let baseline = child.baseline().unwrap_or(0).min(height);
+            above = above.max(margin.0 + baseline);
+            below = below.max(height - baseline + margin.2);
+        }
+        Some((above, below))
+    }
+    /// Share `len` along the stack, the way [`Sizing`] says.
+    fn set_main(&self, len: u32) {
+        let padding = sides(self.get_padding(), D::HORIZONTAL);
+        let children = self.children.borrow().clone();
+        self.share(&children, len.saturating_sub(padding.0 + padding.1));
+    }
+    /// Size `children` along the stack to take `room`, gaps included.
+    fn share(&self, children: &[Rc<dyn WidgetBase>], room: u32) {
+        let horizontal = D::HORIZONTAL;
+        let gaps = self.gap.get() * (children.len() as u32).saturating_sub(1);
+        let mut rem = room.saturating_sub(gaps);
+        let mut sizes: Vec<u32> = children.iter().map(|c| bounds(c, horizontal).0).collect();
+        for (child, size) in children.iter().zip(&sizes) {
+            let margin = sides(child.get_margin(), horizontal);
+            rem = rem.saturating_sub(size + margin.0 + margin.1);
+        }
+        let fill = children
+            .iter()
+            .any(|c| sizing(&**c, horizontal) == Sizing::Fill);
+        let weights: Vec<u64> = children
+            .iter()
+            .map(|c| match sizing(&**c, horizontal) {
+                Sizing::Fill => 1,
+                Sizing::Stretch(fac) if !fill => fac as u64,
+                _ => 0,
+            })
+            .collect();
+        let most: Vec<u32> = children.iter().map(|c| most(c, horizontal)).collect();
+        // Children fitting their content grow to it first, wrapped text takes less room.
+        let fit: Vec<u32> = children
+            .iter()
+            .zip(&most)
+            .map(|(c, &most)| match sizing(&**c, horizontal) {
+                Sizing::Stretch(0) => bounds(c, horizontal).1.min(most),
+                _ => 0,
+            })
+            .collect();
+        let rem = hand_out(&mut sizes, &fit, &vec![1; children.len()], rem);
+        hand_out(&mut sizes, &most, &weights, rem);
+        for (child, size) in children.iter().zip(sizes) {
+            set_len(child, horizontal, size);
+        }
+    }
+    /// Size the children across the stack, `len` wide.
+    fn set_cross(&self, len: u32) {
+        let padding = sides(self.get_padding(), !D::HORIZONTAL);
+        let children = self.children.borrow().clone();
+        self.fit_across(&children, len.saturating_sub(padding.0 + padding.1));
+    }
+    /// Size `children` across the stack, in `room`.
+    fn fit_across(&self, children: &[Rc<dyn WidgetBase>], room: u32) {
+        let horizontal = !D::HORIZONTAL;
+        let stretch = self.align_items.get() == AlignItems::Stretch;
+        for child in children {
+            let margin = sides(child.get_margin(), horizontal);
+            let room = room.saturating_sub(margin.0 + margin.1);
+            let bounds = bounds(child, horizontal);
+            let size = match sizing(&**child, horizontal) {
+                Sizing::Fixed(_) => bounds.0,
+                Sizing::Fill | Sizing::Stretch(1..) => room.max(bounds.0),
+                _ if stretch => room.max(bounds.0),
+                // Fit, as wide as it likes in a column.
+                _ if D::HORIZONTAL => bounds.0,
+                _ => bounds.1.min(room).max(bounds.0),
+            };
+            set_len(
+                child,
+                horizontal,
+                size.min(most(child, horizontal)).max(bounds.0),
+            );
+        }
+    }
+    /// Position the children once they're sized.
+    fn place_children(&self) {
+        let horizontal = D::HORIZONTAL;
+        let size = self.get_computed_size();
+        let padding = self.get_padding();
+        let (main_padding, cross_padding) =
+            (sides(padding, horizontal), sides(padding, !horizontal));
+        let (main_len, cross_len) = match horizontal {
+            true => (size.w, size.h),
+            false => (size.h, size.w),
+        };
+        let children = self.children.borrow().clone();
+        self.place_line(
+            &children,
+            (
+                main_padding.0,
+                main_len.saturating_sub(main_padding.0 + main_padding.1),
+            ),
+            (
+                cross_padding.0,
+                cross_len.saturating_sub(cross_padding.0 + cross_padding.1),
+            ),
+        );
+    }
+    /// Position `children` in a line, given the start and length of the room along and across
+    /// the stack.
+    fn place_line(&self, children: &[Rc<dyn WidgetBase>], main: (u32, u32), cross: (u32, u32)) {
+        let horizontal = D::HORIZONTAL;
+        let along = |size: ComputedSize| match horizontal {
+            true => (size.w, size.h),
+            false => (size.h, size.w),
+        };
+        let gap = self.gap.get();
+        let used = children.iter().fold(
+            gap * (children.len() as u32).saturating_sub(1),
+            |used, c| {
+                let margin = sides(c.get_margin(), horizontal);
+                used + along(c.get_computed_size()).0 + margin.0 + margin.1
+            },
+        );
+        let free = main.1.saturating_sub(used);
+        let count = children.len() as u32;
+        let (start, between) = match self.justify_content.get() {
+            JustifyContent::Start => (0, 0),
+            JustifyContent::Center => (free / 2, 0),
+            JustifyContent::End => (free, 0),
+            JustifyContent::SpaceBetween if count > 1 => (0, free / (count - 1)),
+            JustifyContent::SpaceBetween => (0, 0),
+            JustifyContent::SpaceAround if count > 0 => (free / count / 2, free / count),
+            JustifyContent::SpaceAround => (0, 0),
+        };
+        let align = self.align_items.get();
+        let baseline = self.baselines(children).map(|(above, _)| above);
+        let mut pos = main.0 + start;
+        for child in children {
+            let main_margin = sides(child.get_margin(), horizontal);
+            let cross_margin = sides(child.get_margin(), !horizontal);
+            let (len, across) = along(child.get_computed_size());
+            let room = cross.1.saturating_sub(cross_margin.0 + cross_margin.1);
+            let cross_pos = match (align, baseline.zip(child.baseline())) {
+                (AlignItems::Baseline, Some((line, own))) => (line - own.min(line)) as i32,
+                (AlignItems::Center, _) => {
+                    cross_margin.0 as i32 + Align::Center.place(across, room)
+                }
+                (AlignItems::End, _) => cross_margin.0 as i32 + Align::End.place(across, room),
+                _ => cross_margin.0 as i32,
+            } + cross.0 as i32;
+            pos += main_margin.0;
+            child.set_offset(match horizontal {
+                true => Offset::new(pos as i32, cross_pos),
+                false => Offset::new(cross_pos, pos as i32),
+            });
+            pos += len + main_margin.1 + gap + between;
+        }
+    }
+    /// Swap in `children`, the ones that weren't there get the font.
+    pub(crate) fn set_children(&self, children: Vec<Rc<dyn WidgetBase>>) {
+        if let Some(font) = &*self.font.borrow() {
+            let old: HashSet<_> = self
+                .children
+                .borrow()
+                .iter()
+                .map(|c| Rc::as_ptr(c) as *const ())
+                .collect();
+            for child in &children {
+                if !old.contains(&(Rc::as_ptr(child) as *const ())) {
+                    child.set_font(font.clone());
+                }
+            }
+        }
+        *self.children.borrow_mut() = children;
+    }
     fn draw_frame(&self, buf: &dyn Drawable) {
         let frame = self.get_frame();
         frame(buf, self.get_computed_size(), self.get_background_color())
@@ -66,6 +398,15 @@ where
         }
         buf.end_subregion();
     }
+    fn set_viewport(&self, rect: Rect) {
+        for child in &*self.children.borrow() {
+            let offs = child.get_offset();
+            child.set_viewport(Rect::new(
+                Offset::new(rect.x - offs.x, rect.y - offs.y),
+                ComputedSize::new(rect.w, rect.h),
+            ));
+        }
+    }
     fn handle_button(self: Rc<Self>, pos: Offset, pressed: Option<Rc<Window>>) {
         let pos = pos - self.get_offset();
         let size = self.get_computed_size();
@@ -91,6 +432,18 @@ where
         }
         result
     }
+    fn handle_scroll(self: Rc<Self>, pos: Offset, dx: f32, dy: f32, discrete: bool) -> bool {
+        let pos = pos - self.get_offset();
+        let size = self.get_computed_size();
+        if pos.x < 0 || pos.y < 0 || pos.x > size.w as i32 || pos.y > size.h as i32 {
+            return false;
+        }
+        // Only one child is under the pointer, the innermost scrollable takes it.
+        let children = self.children.borrow().clone();
+        children
+            .into_iter()
+            .any(|child| child.handle_scroll(pos, dx, dy, discrete))
+    }
 }
 
 impl<D: Direction> WidgetBase for Stack<D>
@@ -106,6 +459,9 @@ where
     fn set_pos(&self, pos: Offset) {
         self.base.set_pos(pos);
     }
+    fn get_pos(&self) -> Offset {
+        self.base.get_pos()
+    }
     fn set_frame(&self, frame: String) {
         self.base.set_frame(frame);
     }
@@ -132,6 +488,15 @@ where
     fn get_padding(&self) -> (u32, u32, u32, u32) {
         self.base.get_padding()
     }
+    fn set_padding_sides(&self, padding: (u32, u32, u32, u32)) {
+        self.base.set_padding_sides(padding);
+    }
+    fn set_margin(&self, margin: (u32, u32, u32, u32)) {
+        self.base.set_margin(margin);
+    }
+    fn get_margin(&self) -> (u32, u32, u32, u32) {
+        self.base.get_margin()
+    }
 
     fn get_text(&self) -> String {
         "".to_owned()
@@ -148,14 +513,7 @@ where
     Stack<D>: WidgetInternal,
 {
     fn new() -> Rc<Self> {
-        let this = Self {
-            base: Widget::new_internal(),
-            gap: Cell::new(0),
-            children: RefCell::new(vec![]),
-            _marker: PhantomData,
-        };
-        this.base.set_frame(themes::FrameType::Box.to_string());
-        Rc::new(this)
+        Self::new_internal(0, vec![])
     }
 
     fn on_hover<F: FnMut(&Self, Offset) + 'static>(self: Rc<Self>, _f: F) -> Rc<Self> {
@@ -171,105 +529,103 @@ impl WidgetInternal for HStack {
         for child in &*self.children.borrow() {
             child.set_font(font.clone());
         }
+        *self.font.borrow_mut() = Some(font);
     }
     fn width_bounds(&self) -> (u32, u32) {
-        let padding = self.get_padding();
-        let gaps = self.gap.get() * (self.children.borrow().len() as u32 - 1);
-        let mut min: u32 = padding.1 + padding.3 + gaps;
-        let mut max: u32 = padding.1 + padding.3 + gaps;
-        for child in &*self.children.borrow() {
-            let bounds = child.width_bounds();
-            min += bounds.0;
-            max = max.saturating_add(bounds.1)
-        }
-        match self.get_size().w {
-            Sizing::Fixed(w) => (w.clamp(min, max), w.clamp(min, max)),
-            Sizing::Stretch(0) => (min, min),
-            _ => (min, max),
-        }
+        Stack::main_bounds(self)
     }
     fn set_width(&self, width: u32) {
-        let padding = self.get_padding();
         self.base.set_width(width);
-        let mut rem = width - padding.1 - padding.3;
+        Stack::set_main(self, width);
+    }
+    fn height_bounds(&self) -> (u32, u32) {
+        Stack::cross_bounds(self)
+    }
+    fn set_height(&self, height: u32) {
+        self.base.set_height(height);
+        Stack::set_cross(self, height);
+    }
+    fn get_computed_size(&self) -> ComputedSize {
+        self.base.get_computed_size()
+    }
+    fn get_offset(&self) -> Offset {
+        self.base.get_offset()
+    }
+    fn set_offset(&self, pos: Offset) {
+        self.base.set_offset(pos);
+        Stack::place_children(self);
+    }
+    fn set_viewport(&self, rect: Rect) {
+        Stack::set_viewport(self, rect);
+    }
+    fn get_frame(&self) -> crate::themes::FrameFn {
+        self.base.get_frame()
+    }
+    fn draw_frame(&self, buf: &dyn Drawable) {
+        Stack::draw_frame(self, buf);
+    }
+    fn draw(&self, buf: &mut dyn Drawable) {
+        Stack::draw(self, buf);
+    }
+    fn draw_overlays(&self, buf: &mut Buffer) {
         for child in &*self.children.borrow() {
-            let bounds = child.width_bounds();
-            rem -= bounds.0;
-            if let Sizing::Fixed(_) = child.get_size().w {
-                child.set_width(bounds.0);
-            }
+            child.draw_overlays(buf)
         }
-        let mut spreads = vec![];
+    }
+    fn children(&self) -> Vec<Rc<dyn WidgetBase>> {
+        self.children.borrow().clone()
+    }
+    fn key_bindings(&self) -> Option<&KeyBindings> {
+        self.base.key_bindings()
+    }
+    fn handle_button(self: Rc<Self>, pos: Offset, pressed: Option<Rc<Window>>) {
+        Stack::handle_button(self, pos, pressed);
+    }
+    fn handle_hover(self: Rc<Self>, pos: Offset) -> HoverResult {
+        Stack::handle_hover(self, pos)
+    }
+    fn handle_scroll(self: Rc<Self>, pos: Offset, dx: f32, dy: f32, discrete: bool) -> bool {
+        Stack::handle_scroll(self, pos, dx, dy, discrete)
+    }
+    fn handle_overlay_button(self: Rc<Self>, pos: Offset, pressed: Option<Rc<Window>>) -> bool {
+        let mut result = false;
         for child in &*self.children.borrow() {
-            if child.get_size().w == Sizing::Fill {
-                spreads.push((0, child.clone()));
-            }
+            result |= child.clone().handle_overlay_button(pos, pressed.clone());
         }
-        let mut len = spreads.len() as u32;
-        spreads.drain(..).for_each(|w| {
-            let extra = rem / len;
-            let width = w.1.width_bounds().0 + extra;
-            rem -= extra;
-            len -= 1;
-            w.1.set_width(width);
-        });
-        if rem == 0 {
-            return;
+        result
+    }
+    fn handle_overlay_hover(self: Rc<Self>, pos: Offset) -> HoverResult {
+        let mut result = HoverResult {
+            redraw: false,
+            cursor: CursorType::Arrow,
+        };
+        for child in &*self.children.borrow() {
+            result |= child.clone().handle_overlay_hover(pos);
         }
-        let mut total_fac = 0;
+        result
+    }
+}
+
+impl WidgetInternal for VStack {
+    fn set_font(&self, font: ab_glyph::FontArc) {
         for child in &*self.children.borrow() {
-            if let Sizing::Stretch(fac) = child.get_size().w {
-                spreads.push((fac, child.clone()));
-                total_fac += fac;
-            }
+            child.set_font(font.clone());
         }
-        spreads.drain(..).for_each(|w| {
-            if w.0 == 0 {
-                w.1.set_width(w.1.width_bounds().0);
-                return;
-            }
-            let extra = rem * w.0 / total_fac;
-            let width = w.1.width_bounds().0 + extra;
-            rem -= extra;
-            total_fac -= w.0;
-            w.1.set_width(width);
-        });
+        *self.font.borrow_mut() = Some(font);
+    }
+    fn width_bounds(&self) -> (u32, u32) {
+        Stack::cross_bounds(self)
+    }
+    fn set_width(&self, width: u32) {
+        self.base.set_width(width);
+        Stack::set_cross(self, width);
     }
     fn height_bounds(&self) -> (u32, u32) {
-        let padding = self.get_padding();
-        let min = self
-            .children
-            .borrow()
-            .iter()
-            .map(|w| w.height_bounds().0)
-            .max()
-            .unwrap()
-            + padding.0
-            + padding.2;
-        let max = self
-            .children
-            .borrow()
-            .iter()
-            .map(|w| w.height_bounds().1)
-            .max()
-            .unwrap()
-            + padding.0
-            + padding.2;
-        match self.get_size().h {
-            Sizing::Fixed(h) => (h.clamp(min, max), h.clamp(min, max)),
-            Sizing::Stretch(0) => (min, min),
-            _ => (min, max),
-        }
+        Stack::main_bounds(self)
     }
     fn set_height(&self, height: u32) {
-        let padding = self.get_padding();
         self.base.set_height(height);
-        for child in &*self.children.borrow() {
-            match child.get_size().h {
-                Sizing::Fixed(_) | Sizing::Stretch(0) => child.set_height(child.height_bounds().0),
-                _ => child.set_height(height - padding.0 - padding.2),
-            }
-        }
+        Stack::set_main(self, height);
     }
     fn get_computed_size(&self) -> ComputedSize {
         self.base.get_computed_size()
@@ -279,13 +635,10 @@ impl WidgetInternal for HStack {
     }
     fn set_offset(&self, pos: Offset) {
         self.base.set_offset(pos);
-        let padding = self.get_padding();
-        let mut offs = Offset::from((padding.3 as i32, padding.0 as i32));
-        for child in &*self.children.borrow() {
-            let bounds = child.get_computed_size();
-            child.set_offset(offs);
-            offs.x += bounds.w as i32 + self.gap.get() as i32;
-        }
+        Stack::place_children(self);
+    }
+    fn set_viewport(&self, rect: Rect) {
+        Stack::set_viewport(self, rect);
     }
     fn get_frame(&self) -> crate::themes::FrameFn {
         self.base.get_frame()
@@ -298,15 +651,24 @@ impl WidgetInternal for HStack {
     }
     fn draw_overlays(&self, buf: &mut Buffer) {
         for child in &*self.children.borrow() {
-            child.draw_overlays(buf)
+            child.draw_overlays(buf);
         }
     }
+    fn children(&self) -> Vec<Rc<dyn WidgetBase>> {
+        self.children.borrow().clone()
+    }
+    fn key_bindings(&self) -> Option<&KeyBindings> {
+        self.base.key_bindings()
+    }
     fn handle_button(self: Rc<Self>, pos: Offset, pressed: Option<Rc<Window>>) {
         Stack::handle_button(self, pos, pressed);
     }
     fn handle_hover(self: Rc<Self>, pos: Offset) -> HoverResult {
         Stack::handle_hover(self, pos)
     }
+    fn handle_scroll(self: Rc<Self>, pos: Offset, dx: f32, dy: f32, discrete: bool) -> bool {
+        Stack::handle_scroll(self, pos, dx, dy, discrete)
+    }
     fn handle_overlay_button(self: Rc<Self>, pos: Offset, pressed: Option<Rc<Window>>) -> bool {
         let mut result = false;
         for child in &*self.children.borrow() {
@@ -326,11 +688,57 @@ impl WidgetInternal for HStack {
     }
 }
 
-impl WidgetInternal for VStack {
+impl Flow {
+    /// Children on each line when `width` wide, as many as fit at their content's width.
+    fn lines(&self, width: u32) -> Vec<Vec<Rc<dyn WidgetBase>>> {
+        let padding = self.get_padding();
+        let room = width.saturating_sub(padding.1 + padding.3);
+        let gap = self.gap.get();
+        let mut lines: Vec<Vec<Rc<dyn WidgetBase>>> = vec![];
+        let mut used = 0;
+        for child in &*self.children.borrow() {
+            let margin = child.get_margin();
+            let bounds = child.width_bounds();
+            let len = match child.get_size().w {
+                Sizing::Stretch(0) => bounds.1.min(room).max(bounds.0),
+                _ => bounds.0,
+            } + margin.1
+                + margin.3;
+            match lines.last_mut() {
+                Some(line) if used + gap + len <= room => {
+                    used += gap + len;
+                    line.push(child.clone());
+                }
+                _ => {
+                    used = len;
+                    lines.push(vec![child.clone()]);
+                }
+            }
+        }
+        lines
+    }
+    /// Smallest and largest height of `line`.
+    fn line_bounds(&self, line: &[Rc<dyn WidgetBase>]) -> (u32, u32) {
+        let (mut min, mut max) = (0, 0);
+        for child in line {
+            let bounds = child.height_bounds();
+            let margin = child.get_margin();
+            min = min.max(bounds.0 + margin.0 + margin.2);
+            max = max.max(bounds.1.saturating_add(margin.0 + margin.2));
+        }
+        if let Some((above, below)) = self.baselines(line) {
+            min = min.max(above + below);
+        }
+        (min, max.max(min))
+    }
+}
+
+impl WidgetInternal for Flow {
     fn set_font(&self, font: ab_glyph::FontArc) {
         for child in &*self.children.borrow() {
             child.set_font(font.clone());
         }
+        *self.font.borrow_mut() = Some(font);
     }
     fn width_bounds(&self) -> (u32, u32) {
         let padding = self.get_padding();
@@ -338,95 +746,50 @@ impl WidgetInternal for VStack {
             .children
             .borrow()
             .iter()
-            .map(|w| w.width_bounds().0)
+            .map(|c| c.width_bounds().0 + c.get_margin().1 + c.get_margin().3)
             .max()
-            .unwrap()
-            + padding.0
-            + padding.2;
-        let max = self
-            .children
-            .borrow()
-            .iter()
-            .map(|w| w.width_bounds().1)
-            .max()
-            .unwrap()
-            + padding.0
-            + padding.2;
-        match self.get_size().w {
+            .unwrap_or(0)
+            + padding.1
+            + padding.3;
+        // Widest on a single line.
+        let max = Stack::main_bounds(self).1;
+        let bounds = match self.get_size().w {
             Sizing::Fixed(w) => (w.clamp(min, max), w.clamp(min, max)),
-            _ => (min, max),
-        }
+            _ => (min, max.max(min)),
+        };
+        limit(self, true, bounds)
     }
     fn set_width(&self, width: u32) {
-        let padding = self.get_padding();
         self.base.set_width(width);
-        for child in &*self.children.borrow() {
-            match child.get_size().w {
-                Sizing::Fixed(_) | Sizing::Stretch(0) => child.set_width(child.width_bounds().1),
-                _ => child.set_width(width - padding.1 - padding.3),
-            }
+        let padding = self.get_padding();
+        let room = width.saturating_sub(padding.1 + padding.3);
+        for line in self.lines(width) {
+            self.share(&line, room);
         }
     }
     fn height_bounds(&self) -> (u32, u32) {
         let padding = self.get_padding();
-        let gaps = self.gap.get() * (self.children.borrow().len() as u32 - 1);
-        let mut min: u32 = padding.0 + padding.2 + gaps;
-        let mut max: u32 = padding.0 + padding.2 + gaps;
-        for child in &*self.children.borrow() {
-            let bounds = child.height_bounds();
+        let lines = self.lines(self.get_computed_size().w);
+        let gaps = self.gap.get() * (lines.len() as u32).saturating_sub(1);
+        let mut min = padding.0 + padding.2 + gaps;
+        let mut max = min;
+        for line in &lines {
+            let bounds = self.line_bounds(line);
             min += bounds.0;
-            max = max.saturating_add(bounds.1)
+            max = max.saturating_add(bounds.1);
         }
-        match self.get_size().h {
+        let bounds = match self.get_size().h {
             Sizing::Fixed(h) => (h.clamp(min, max), h.clamp(min, max)),
             Sizing::Stretch(0) => (min, min),
             _ => (min, max),
-        }
+        };
+        limit(self, false, bounds)
     }
     fn set_height(&self, height: u32) {
-        let padding = self.get_padding();
         self.base.set_height(height);
-        let mut rem = height - padding.0 - padding.2;
-        for child in &*self.children.borrow() {
-            let bounds = child.height_bounds();
-            rem -= bounds.0;
-            child.set_height(bounds.0);
+        for line in self.lines(self.get_computed_size().w) {
+            self.fit_across(&line, self.line_bounds(&line).0);
         }
-        let mut spreads = vec![];
-        for child in &*self.children.borrow() {
-            if child.get_size().h == Sizing::Fill {
-                spreads.push((0, child.clone()));
-            }
-        }
-        let mut len = spreads.len() as u32;
-        spreads.drain(..).for_each(|w| {
-            let extra = rem / len;
-            let height = w.1.height_bounds().0 + extra;
-            rem -= extra;
-            len -= 1;
-            w.1.set_height(height);
-        });
-        if rem == 0 {
-            return;
-        }
-        let mut total_fac = 0;
-        for child in &*self.children.borrow() {
-            if let Sizing::Stretch(fac) = child.get_size().h {
-                spreads.push((fac, child.clone()));
-                total_fac += fac;
-            }
-        }
-        spreads.drain(..).for_each(|w| {
-            if w.0 == 0 {
-                w.1.set_height(w.1.height_bounds().0);
-                return;
-            }
-            let extra = rem * w.0 / total_fac;
-            let height = w.1.height_bounds().0 + extra;
-            rem -= extra;
-            total_fac -= w.0;
-            w.1.set_height(height);
-        });
     }
     fn get_computed_size(&self) -> ComputedSize {
         self.base.get_computed_size()
@@ -437,13 +800,18 @@ impl WidgetInternal for VStack {
     fn set_offset(&self, pos: Offset) {
         self.base.set_offset(pos);
         let padding = self.get_padding();
-        let mut offs = Offset::from((padding.3 as i32, padding.0 as i32));
-        for child in &*self.children.borrow() {
-            let bounds = child.get_computed_size();
-            child.set_offset(offs);
-            offs.y += bounds.h as i32 + self.gap.get() as i32;
+        let size = self.get_computed_size();
+        let room = size.w.saturating_sub(padding.1 + padding.3);
+        let mut y = padding.0;
+        for line in self.lines(size.w) {
+            let height = self.line_bounds(&line).0;
+            self.place_line(&line, (padding.3, room), (y, height));
+            y += height + self.gap.get();
         }
     }
+    fn set_viewport(&self, rect: Rect) {
+        Stack::set_viewport(self, rect);
+    }
     fn get_frame(&self) -> crate::themes::FrameFn {
         self.base.get_frame()
     }
@@ -455,16 +823,24 @@ impl WidgetInternal for VStack {
     }
     fn draw_overlays(&self, buf: &mut Buffer) {
         for child in &*self.children.borrow() {
-            child.draw_overlays(buf);
+            child.draw_overlays(buf)
         }
     }
-
+    fn children(&self) -> Vec<Rc<dyn WidgetBase>> {
+        self.children.borrow().clone()
+    }
+    fn key_bindings(&self) -> Option<&KeyBindings> {
+        self.base.key_bindings()
+    }
     fn handle_button(self: Rc<Self>, pos: Offset, pressed: Option<Rc<Window>>) {
         Stack::handle_button(self, pos, pressed);
     }
     fn handle_hover(self: Rc<Self>, pos: Offset) -> HoverResult {
         Stack::handle_hover(self, pos)
     }
+    fn handle_scroll(self: Rc<Self>, pos: Offset, dx: f32, dy: f32, discrete: bool) -> bool {
+        Stack::handle_scroll(self, pos, dx, dy, discrete)
+    }
     fn handle_overlay_button(self: Rc<Self>, pos: Offset, pressed: Option<Rc<Window>>) -> bool {
         let mut result = false;
         for child in &*self.children.borrow() {
@@ -485,23 +861,312 @@ impl WidgetInternal for VStack {
 }
 
 pub fn hstack<G: WidgetGroup>(gap: u32, widgets: G) -> Rc<HStack> {
-    let this = Stack {
-        base: Widget::new_internal(),
-        gap: Cell::new(gap),
-        children: RefCell::new(widgets.create_group()),
-        _marker: PhantomData,
-    };
-    this.base.set_frame(themes::FrameType::Box.to_string());
-    Rc::new(this)
+    Stack::new_internal(gap, widgets.create_group())
 }
 
 pub fn vstack<G: WidgetGroup>(gap: u32, widgets: G) -> Rc<VStack> {
-    let this = Stack {
-        base: Widget::new_internal(),
-        gap: Cell::new(gap),
-        children: RefCell::new(widgets.create_group()),
-        _marker: PhantomData,
-    };
-    this.base.set_frame(themes::FrameType::Box.to_string());
-    Rc::new(this)
+    Stack::new_internal(gap, widgets.create_group())
+}
+
+/// A row that wraps onto more lines when out of room, `gap` apart both ways.
+pub fn flow<G: WidgetGroup>(gap: u32, widgets: G) -> Rc<Flow> {
+    Stack::new_internal(gap, widgets.create_group())
+}
+
+type KeyedChildren<K> = HashMap<K, (Rc<dyn WidgetBase>, Scope)>;
+
+fn dyn_stack_internal<D, T, K, W>(
+    gap: u32,
+    each: impl Fn() -> Vec<T> + 'static,
+    key: impl Fn(&T) -> K + 'static,
+    view: impl Fn(T) -> W + 'static,
+) -> Rc<Stack<D>>
+where
+    D: Direction + 'static,
+    Stack<D>: WidgetInternal,
+    K: Eq + Hash + 'static,
+    W: IntoWidget + 'static,
+{
+    let stack = Stack::<D>::new_internal(gap, vec![]);
+    let weak = Rc::downgrade(&stack);
+    let scope = Scope::current();
+    create_effect(move |old: Option<KeyedChildren<K>>| {
+        let items = each();
+        let mut old = old.unwrap_or_default();
+        let mut keyed = HashMap::with_capacity(items.len());
+        let mut children = Vec::with_capacity(items.len());
+        for item in items {
+            let key = key(&item);
+            if keyed.contains_key(&key) {
+                continue;
+            }
+            let child = old.remove(&key).unwrap_or_else(|| {
+                // Each child owns its effects, dropped along with it.
+                let scope = scope.create_child();
+                let child = with_scope(scope, || untrack(|| view(item).into_widget()));
+                (child as Rc<dyn WidgetBase>, scope)
+            });
+            children.push(child.0.clone());
+            keyed.insert(key, child);
+        }
+        for (_, scope) in old.into_values() {
+            scope.dispose();
+        }
+        if let Some(stack) = weak.upgrade() {
+            stack.set_children(children);
+            NEEDS_REDRAW.set(true);
+        }
+        keyed
+    });
+    stack
+}
+
+/// A column with a child for each of `each`, kept in sync with it. Children are matched by
+/// `key`, only new keys run `view`. Items whose key came earlier are skipped.
+pub fn dyn_stack<T, K, W>(
+    gap: u32,
+    each: impl Fn() -> Vec<T> + 'static,
+    key: impl Fn(&T) -> K + 'static,
+    view: impl Fn(T) -> W + 'static,
+) -> Rc<VStack>
+where
+    K: Eq + Hash + 'static,
+    W: IntoWidget + 'static,
+{
+    dyn_stack_internal(gap, each, key, view)
+}
+
+/// A row version of [`dyn_stack`].
+pub fn dyn_hstack<T, K, W>(
+    gap: u32,
+    each: impl Fn() -> Vec<T> + 'static,
+    key: impl Fn(&T) -> K + 'static,
+    view: impl Fn(T) -> W + 'static,
+) -> Rc<HStack>
+where
+    K: Eq + Hash + 'static,
+    W: IntoWidget + 'static,
+{
+    dyn_stack_internal(gap, each, key, view)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::widgets::label::label;
+
+    fn boxed(w: u32, h: u32) -> Rc<Widget> {
+        Widget::new().size(Size::fixed(w, h))
+    }
+
+    fn lay_out(widget: &dyn WidgetBase, w: u32, h: u32) {
+        widget.set_width(w);
+        widget.set_height(h);
+        widget.set_offset(Offset::default());
+    }
+
+    fn rect(widget: &dyn WidgetBase) -> (i32, i32, u32, u32) {
+        let (offs, size) = (widget.get_offset(), widget.get_computed_size());
+        (offs.x, offs.y, size.w, size.h)
+    }
+
+    #[test]
+    fn align_items() {
+        for (align, ys) in [
+            (AlignItems::Start, (0, 0)),
+            (AlignItems::Center, (15, 5)),
+            (AlignItems::End, (30, 10)),
+        ] {
+            let (a, b) = (boxed(20, 10), boxed(20, 30));
+            let stack = hstack(0, (a.clone(), b.clone())).align_items(align);
+            lay_out(&*stack, 100, 40);
+            assert_eq!(
+                (rect(&*a), rect(&*b)),
+                ((0, ys.0, 20, 10), (20, ys.1, 20, 30))
+            );
+        }
+    }
+
+    #[test]
+    fn align_items_stretch() {
+        let a = Widget::new().size(Size::new(Sizing::Fixed(20), Sizing::Stretch(0)));
+        let b = boxed(20, 30);
+        let stack = hstack(0, (a.clone(), b.clone())).align_items(AlignItems::Stretch);
+        lay_out(&*stack, 100, 40);
+        assert_eq!((rect(&*a), rect(&*b)), ((0, 0, 20, 40), (20, 0, 20, 30)));
+
+        // Only in the stretching stack.
+        let a = Widget::new().size(Size::new(Sizing::Fixed(20), Sizing::Stretch(0)));
+        let stack = hstack(0, (a.clone(), boxed(0, 0)));
+        lay_out(&*stack, 100, 40);
+        assert_eq!(rect(&*a), (0, 0, 20, 0));
+    }
+
+    #[test]
+    fn align_items_baseline() {
+        let font = crate::test_font();
+        let a = label("a").padding_sides(0, 0, 0, 0);
+        let b = label("b").padding_sides(10, 0, 0, 0);
+        let stack = hstack(0, (a.clone(), b.clone())).align_items(AlignItems::Baseline);
+        stack.set_font(font);
+        assert_eq!(a.baseline().unwrap() + 10, b.baseline().unwrap());
+
+        let width = stack.width_bounds().0;
+        stack.set_width(width);
+        let height = stack.height_bounds().0;
+        assert_eq!(height, b.height_bounds().0);
+        stack.set_height(height);
+        stack.set_offset(Offset::default());
+        assert_eq!((a.get_offset().y, b.get_offset().y), (10, 0));
+    }
+
+    #[test]
+    fn justify_content() {
+        // 40 left over in a row of 100.
+        for (justify, xs) in [
+            (JustifyContent::Start, (0, 30)),
+            (JustifyContent::Center, (20, 50)),
+            (JustifyContent::End, (40, 70)),
+            (JustifyContent::SpaceBetween, (0, 70)),
+            (JustifyContent::SpaceAround, (10, 60)),
+        ] {
+            let (a, b) = (boxed(20, 10), boxed(30, 10));
+            let stack = hstack(10, (a.clone(), b.clone())).justify_content(justify);
+            lay_out(&*stack, 100, 10);
+            assert_eq!(
+                (rect(&*a), rect(&*b)),
+                ((xs.0, 0, 20, 10), (xs.1, 0, 30, 10))
+            );
+        }
+    }
+
+    #[test]
+    fn justify_content_vertical() {
+        let (a, b) = (boxed(20, 10), boxed(20, 10));
+        let stack = vstack(0, (a.clone(), b.clone())).justify_content(JustifyContent::End);
+        lay_out(&*stack, 20, 100);
+        assert_eq!((rect(&*a), rect(&*b)), ((0, 80, 20, 10), (0, 90, 20, 10)));
+    }
+
+    #[test]
+    fn padding_sides() {
+        let (a, b) = (boxed(20, 10), boxed(20, 10));
+        let stack = vstack(5, (a.clone(), b.clone())).padding_sides(1, 2, 3, 4);
+        assert_eq!(stack.width_bounds(), (26, 26));
+        assert_eq!(stack.height_bounds(), (29, 29));
+        lay_out(&*stack, 26, 29);
+        assert_eq!((rect(&*a), rect(&*b)), ((4, 1, 20, 10), (4, 16, 20, 10)));
+    }
+
+    #[test]
+    fn margins() {
+        let a = boxed(20, 10).margin(1, 2, 3, 4);
+        let b = boxed(20, 10);
+        let stack = hstack(0, (a.clone(), b.clone()));
+        assert_eq!(stack.width_bounds(), (46, 46));
+        assert_eq!(stack.height_bounds(), (14, 14));
+        lay_out(&*stack, 46, 14);
+        assert_eq!((rect(&*a), rect(&*b)), ((4, 1, 20, 10), (26, 0, 20, 10)));
+
+        // Across, the margins come off the room to align in.
+        let stack = hstack(0, (a.clone(), b.clone())).align_items(AlignItems::End);
+        lay_out(&*stack, 46, 30);
+        assert_eq!((rect(&*a), rect(&*b)), ((4, 17, 20, 10), (26, 20, 20, 10)));
+    }
+
+    #[test]
+    fn size_limits_in_a_row() {
+        // The capped child's share goes to the other.
+        let a = Widget::new().size(Size::stretch(1, 0).max(30, u32::MAX));
+        let b = Widget::new().size(Size::stretch(1, 0));
+        let stack = hstack(0, (a.clone(), b.clone()));
+        lay_out(&*stack, 100, 10);
+        assert_eq!((rect(&*a), rect(&*b)), ((0, 0, 30, 0), (30, 0, 70, 0)));
+
+        let a = Widget::new().size(Size::default().min(40, 15));
+        let stack = hstack(0, (a.clone(), boxed(10, 10)));
+        assert_eq!(stack.width_bounds(), (50, 50));
+        assert_eq!(stack.height_bounds(), (15, 15));
+    }
+
+    #[test]
+    fn size_limits_in_a_column() {
+        let a = Widget::new().size(Size::stretch(0, 1).max(u32::MAX, 30));
+        let b = Widget::new().size(Size::stretch(0, 1));
+        let stack = vstack(0, (a.clone(), b.clone()));
+        lay_out(&*stack, 10, 100);
+        assert_eq!((rect(&*a), rect(&*b)), ((0, 0, 0, 30), (0, 30, 0, 70)));
+
+        let a = Widget::new().size(Size::default().min(15, 40));
+        let stack = vstack(0, (a.clone(), boxed(10, 10)));
+        assert_eq!(stack.width_bounds(), (15, 15));
+        assert_eq!(stack.height_bounds(), (50, 50));
+
+        // With an aspect, the height follows the width it's given.
+        let a = Widget::new().size(Size::new(Sizing::Stretch(1), Sizing::Fill).aspect(2.0));
+        let stack = vstack(0, (a.clone(), boxed(10, 10)));
+        lay_out(&*stack, 60, 100);
+        assert_eq!(rect(&*a), (0, 0, 60, 30));
+    }
+
+    fn flowed() -> (Rc<Flow>, [Rc<Widget>; 3]) {
+        let children = [boxed(30, 10), boxed(30, 20), boxed(30, 10)];
+        let [a, b, c] = children.clone();
+        (flow(10, (a, b, c)), children)
+    }
+
+    #[test]
+    fn flow_breaks_lines() {
+        let (flow, [a, b, c]) = flowed();
+        assert_eq!(flow.width_bounds(), (30, 110));
+
+        flow.set_width(110);
+        assert_eq!(flow.height_bounds(), (20, 20));
+        lay_out(&*flow, 110, 20);
+        assert_eq!(
+            [rect(&*a), rect(&*b), rect(&*c)],
+            [(0, 0, 30, 10), (40, 0, 30, 20), (80, 0, 30, 10)]
+        );
+
+        // The gap goes between lines too.
+        flow.set_width(75);
+        assert_eq!(flow.height_bounds(), (40, 40));
+        lay_out(&*flow, 75, 40);
+        assert_eq!(
+            [rect(&*a), rect(&*b), rect(&*c)],
+            [(0, 0, 30, 10), (40, 0, 30, 20), (0, 30, 30, 10)]
+        );
+
+        flow.set_width(30);
+        assert_eq!(flow.height_bounds(), (60, 60));
+        lay_out(&*flow, 30, 60);
+        assert_eq!(
+            [rect(&*a), rect(&*b), rect(&*c)],
+            [(0, 0, 30, 10), (0, 20, 30, 20), (0, 50, 30, 10)]
+        );
+    }
+
+    #[test]
+    fn flow_aligns_each_line() {
+        let (flow, [a, b, c]) = flowed();
+        let flow = flow
+            .align_items(AlignItems::End)
+            .justify_content(JustifyContent::End);
+        lay_out(&*flow, 75, 40);
+        assert_eq!(
+            [rect(&*a), rect(&*b), rect(&*c)],
+            [(5, 10, 30, 10), (45, 0, 30, 20), (45, 30, 30, 10)]
+        );
+
+        let (flow, [a, b, c]) = flowed();
+        let flow = flow
+            .align_items(AlignItems::Center)
+            .justify_content(JustifyContent::SpaceBetween)
+            .padding_sides(1, 2, 3, 4);
+        lay_out(&*flow, 81, 44);
+        assert_eq!(
+            [rect(&*a), rect(&*b), rect(&*c)],
+            [(4, 6, 30, 10), (49, 1, 30, 20), (4, 31, 30, 10)]
+        );
+    }
 }
@@ -0,0 +1,264 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use lite_graphics::{Buffer, Drawable, Rect, color::Rgba};
+
+use crate::{
+    app::{HoverResult, NEEDS_REDRAW},
+    reactive::{Scope, create_effect, untrack, with_scope},
+    shortcut::KeyBindings,
+    themes,
+    window::Window,
+};
+
+use super::{
+    ComputedSize, IntoWidget, Offset, Size, Widget, WidgetBase, WidgetExt, WidgetInternal,
+};
+
+type ViewFn<T> = Box<dyn Fn(T) -> Rc<dyn WidgetBase>>;
+
+/// Holds one child at a time, swapped as state changes. Sized and drawn as the child is.
+pub struct Switch {
+    base: Widget,
+    child: RefCell<Rc<dyn WidgetBase>>,
+    /// Owns the effects of the child, disposed when it's swapped out.
+    scope: Cell<Option<Scope>>,
+    /// Given to the children swapped in.
+    font: RefCell<Option<ab_glyph::FontArc>>,
+    /// Given to the children swapped in once set, they keep their own otherwise.
+    size: Cell<Option<Size>>,
+    margin: Cell<Option<(u32, u32, u32, u32)>>,
+}
+
+impl Switch {
+    fn new_internal() -> Self {
+        Self {
+            base: Widget::new_internal(),
+            child: RefCell::new(Widget::new()),
+            scope: Cell::new(None),
+            font: RefCell::new(None),
+            size: Cell::new(None),
+            margin: Cell::new(None),
+        }
+    }
+    fn set_child(&self, child: Rc<dyn WidgetBase>, scope: Scope) {
+        if let Some(font) = &*self.font.borrow() {
+            child.set_font(font.clone());
+        }
+        if let Some(size) = self.size.get() {
+            child.set_size(size);
+        }
+        if let Some(margin) = self.margin.get() {
+            child.set_margin(margin);
+        }
+        *self.child.borrow_mut() = child;
+        if let Some(old) = self.scope.replace(Some(scope)) {
+            old.dispose();
+        }
+        NEEDS_REDRAW.set(true);
+    }
+    fn child(&self) -> Rc<dyn WidgetBase> {
+        self.child.borrow().clone()
+    }
+}
+
+impl WidgetBase for Switch {
+    fn set_size(&self, size: Size) {
+        self.size.set(Some(size));
+        self.child().set_size(size);
+    }
+    fn get_size(&self) -> Size {
+        self.child().get_size()
+    }
+    fn set_pos(&self, pos: Offset) {
+        self.base.set_pos(pos);
+    }
+    fn get_pos(&self) -> Offset {
+        self.base.get_pos()
+    }
+    // No meaning here
+    fn set_frame(&self, _frame: String) {}
+    // No meaning here
+    fn set_background_color(&self, _color: Rgba) {}
+    fn get_background_color(&self) -> Rgba {
+        self.base.get_background_color()
+    }
+    // No meaning here
+    fn set_padding(&self, _padding: u32) {}
+    fn get_padding(&self) -> (u32, u32, u32, u32) {
+        (0, 0, 0, 0)
+    }
+    // No meaning here
+    fn set_padding_sides(&self, _padding: (u32, u32, u32, u32)) {}
+    fn set_margin(&self, margin: (u32, u32, u32, u32)) {
+        self.margin.set(Some(margin));
+        self.child().set_margin(margin);
+    }
+    fn get_margin(&self) -> (u32, u32, u32, u32) {
+        self.child().get_margin()
+    }
+    // No meaning here
+    fn set_border_radius(&self, _radius: u32) {}
+    fn get_border_radius(&self) -> u32 {
+        0
+    }
+    // No meaning here
+    fn set_color(&self, _color: Rgba) {}
+    // No meaning here
+    fn set_text(&self, _text: &str) {}
+    fn get_text(&self) -> String {
+        "".to_owned()
+    }
+    fn set_text_align(&self, _: core::fmt::Alignment) {}
+    fn set_disabled(&self, _: bool) {}
+    fn is_disabled(&self) -> bool {
+        false
+    }
+}
+
+impl WidgetExt for Switch {
+    fn new() -> Rc<Self> {
+        Rc::new(Self::new_internal())
+    }
+
+    fn on_hover<F: FnMut(&Self, Offset) + 'static>(self: Rc<Self>, _f: F) -> Rc<Self> {
+        self
+    }
+    fn on_click<F: FnMut(&Self, Offset) + 'static>(self: Rc<Self>, _f: F) -> Rc<Self> {
+        self
+    }
+}
+
+impl WidgetInternal for Switch {
+    fn set_font(&self, font: ab_glyph::FontArc) {
+        self.child().set_font(font.clone());
+        *self.font.borrow_mut() = Some(font);
+    }
+    fn width_bounds(&self) -> (u32, u32) {
+        self.child().width_bounds()
+    }
+    fn set_width(&self, width: u32) {
+        self.base.set_width(width);
+        self.child().set_width(width);
+    }
+    fn height_bounds(&self) -> (u32, u32) {
+        self.child().height_bounds()
+    }
+    fn set_height(&self, height: u32) {
+        self.base.set_height(height);
+        self.child().set_height(height);
+    }
+    fn get_computed_size(&self) -> ComputedSize {
+        self.base.get_computed_size()
+    }
+    fn get_offset(&self) -> Offset {
+        self.base.get_offset()
+    }
+    fn set_offset(&self, pos: Offset) {
+        self.base.set_offset(pos);
+        self.child().set_offset(Offset::default());
+    }
+    fn set_viewport(&self, rect: Rect) {
+        self.child().set_viewport(rect);
+    }
+    fn baseline(&self) -> Option<u32> {
+        self.child().baseline()
+    }
+    fn get_frame(&self) -> themes::FrameFn {
+        self.base.get_frame()
+    }
+    fn draw_frame(&self, _buf: &dyn Drawable) {}
+    fn draw(&self, buf: &mut dyn Drawable) {
+        buf.subregion(Rect::new(self.get_offset(), self.get_computed_size()));
+        self.child().draw(buf);
+        buf.end_subregion();
+    }
+    fn draw_overlays(&self, buf: &mut Buffer) {
+        self.child().draw_overlays(buf);
+    }
+    fn children(&self) -> Vec<Rc<dyn WidgetBase>> {
+        vec![self.child()]
+    }
+
+    fn key_bindings(&self) -> Option<&KeyBindings> {
+        self.base.key_bindings()
+    }
+    fn handle_button(self: Rc<Self>, pos: Offset, pressed: Option<Rc<Window>>) {
+        self.child().handle_button(pos - self.get_offset(), pressed);
+    }
+    fn handle_hover(self: Rc<Self>, pos: Offset) -> HoverResult {
+        self.child().handle_hover(pos - self.get_offset())
+    }
+    fn handle_scroll(self: Rc<Self>, pos: Offset, dx: f32, dy: f32, discrete: bool) -> bool {
+        self.child()
+            .handle_scroll(pos - self.get_offset(), dx, dy, discrete)
+    }
+    fn handle_overlay_button(self: Rc<Self>, pos: Offset, pressed: Option<Rc<Window>>) -> bool {
+        self.child().handle_overlay_button(pos, pressed)
+    }
+    fn handle_overlay_hover(self: Rc<Self>, pos: Offset) -> HoverResult {
+        self.child().handle_overlay_hover(pos)
+    }
+}
+
+fn switch_internal<T: PartialEq + Clone + 'static>(
+    value: impl Fn() -> T + 'static,
+    view: ViewFn<T>,
+) -> Rc<Switch> {
+    let this = Rc::new(Switch::new_internal());
+    let weak = Rc::downgrade(&this);
+    let scope = Scope::current();
+    create_effect(move |old: Option<T>| {
+        let value = value();
+        if old.as_ref() == Some(&value) {
+            return value;
+        }
+        if let Some(this) = weak.upgrade() {
+            let child_scope = scope.create_child();
+            let child = with_scope(child_scope, || untrack(|| view(value.clone())));
+            this.set_child(child, child_scope);
+        }
+        value
+    });
+    this
+}
+
+/// The view of `value`, built again whenever it changes.
+pub fn switch<T, W>(value: impl Fn() -> T + 'static, view: impl Fn(T) -> W + 'static) -> Rc<Switch>
+where
+    T: PartialEq + Clone + 'static,
+    W: IntoWidget + 'static,
+{
+    switch_internal(value, Box::new(move |value| view(value).into_widget()))
+}
+
+/// `view` while `cond` holds, nothing otherwise.
+pub fn show<W: IntoWidget + 'static>(
+    cond: impl Fn() -> bool + 'static,
+    view: impl Fn() -> W + 'static,
+) -> Rc<Switch> {
+    switch_internal(
+        cond,
+        Box::new(move |shown| match shown {
+            true => view().into_widget(),
+            false => Widget::new(),
+        }),
+    )
+}
+
+/// `a` while `cond` holds, `b` otherwise.
+pub fn either<A: IntoWidget + 'static, B: IntoWidget + 'static>(
+    cond: impl Fn() -> bool + 'static,
+    a: impl Fn() -> A + 'static,
+    b: impl Fn() -> B + 'static,
+) -> Rc<Switch> {
+    switch_internal(
+        cond,
+        Box::new(move |cond| match cond {
+            true => a().into_widget(),
+            false => b().into_widget(),
+        }),
+    )
+}
@@ -0,0 +1,457 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet, VecDeque},
+    hash::Hash,
+    rc::Rc,
+};
+
+use lite_graphics::{Buffer, Drawable, Rect, color::Rgba};
+
+use crate::{
+    Sizing,
+    app::{CursorType, HoverResult, NEEDS_REDRAW},
+    reactive::{Scope, create_effect, untrack, with_scope},
+    shortcut::KeyBindings,
+    themes,
+    window::Window,
+};
+
+use super::{
+    ComputedSize, IntoWidget, Offset, Size, Widget, WidgetBase, WidgetExt, WidgetInternal,
+};
+
+/// Rows kept past each end of the viewport, so short scrolls don't rebuild them.
+const OVERSCAN: usize = 4;
+/// Rows kept once out of view, for when they scroll back.
+const RECYCLED: usize = 64;
+
+type KeyFn<T, K> = Box<dyn Fn(&T) -> K>;
+type RowFn<T> = Box<dyn Fn(&T) -> Rc<dyn WidgetBase>>;
+/// A built row, with the scope owning its effects and the item it was built from.
+type Row<T> = (Rc<dyn WidgetBase>, Scope, T);
+
+/// A column of rows for long lists, only the rows in view are built and laid out.
+///
+/// Rows are matched to items by key, a row is built again only when its item changes. Rows that
+/// scroll out of view are kept for a while, and taken again if they scroll back. Rows all get
+/// the height of the first one unless [`row_height`](Self::row_height) is set.
+pub struct VirtualList<T, K> {
+    base: Widget,
+    items: RefCell<Vec<T>>,
+    key: KeyFn<T, K>,
+    row: RowFn<T>,
+    /// Parent of the row scopes.
+    scope: Scope,
+    /// Built rows by key, shown or recycled.
+    rows: RefCell<HashMap<K, Row<T>>>,
+    /// Keys of the rows out of view, oldest first.
+    recycled: RefCell<VecDeque<K>>,
+    /// Indices of the shown rows, end excluded.
+    shown: Cell<(usize, usize)>,
+    row_height: Cell<Option<u32>>,
+    /// Height of the first row, measured when `row_height` isn't set.
+    measured: Cell<Option<u32>>,
+    font: RefCell<Option<ab_glyph::FontArc>>,
+}
+
+impl<T: Clone + Eq + 'static, K: Clone + Eq + Hash + 'static> VirtualList<T, K> {
+    fn new_internal(key: KeyFn<T, K>, row: RowFn<T>) -> Self {
+        let this = Self {
+            base: Widget::new_internal(),
+            items: RefCell::new(vec![]),
+            key,
+            row,
+            scope: Scope::current(),
+            rows: RefCell::new(HashMap::new()),
+            recycled: RefCell::new(VecDeque::new()),
+            shown: Cell::new((0, 0)),
+            row_height: Cell::new(None),
+            measured: Cell::new(None),
+            font: RefCell::new(None),
+        };
+        this.base.set_frame(themes::FrameType::Box.to_string());
+        this
+    }
+    /// Height of every row, instead of the height of the first.
+    pub fn row_height(self: Rc<Self>, height: u32) -> Rc<Self> {
+        self.row_height.set(Some(height));
+        self
+    }
+    /// Number of items.
+    pub fn len(&self) -> usize {
+        self.items.borrow().len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.items.borrow().is_empty()
+    }
+
+    /// Keep the rows whose item didn't change, the rest are built on the next layout. Items
+    /// whose key came earlier are skipped.
+    fn set_items(&self, mut items: Vec<T>) {
+        let mut seen = HashSet::with_capacity(items.len());
+        items.retain(|item| seen.insert((self.key)(item)));
+        let mut keyed: HashMap<_, _> = items.iter().map(|item| ((self.key)(item), item)).collect();
+        self.rows.borrow_mut().retain(|key, (_, scope, item)| {
+            let keep = keyed.remove(key) == Some(item);
+            if !keep {
+                scope.dispose();
+            }
+            keep
+        });
+        let rows = self.rows.borrow();
+        self.recycled
+            .borrow_mut()
+            .retain(|key| rows.contains_key(key));
+        drop(rows);
+        *self.items.borrow_mut() = items;
+        self.measured.set(None);
+        NEEDS_REDRAW.set(true);
+    }
+    fn build(&self, item: &T) -> Row<T> {
+        // Each row owns its effects, dropped along with it.
+        let scope = self.scope.create_child();
+        let row = with_scope(scope, || untrack(|| (self.row)(item)));
+        if let Some(font) = &*self.font.borrow() {
+            row.set_font(font.clone());
+        }
+        (row, scope, item.clone())
+    }
+    fn get_row_height(&self) -> u32 {
+        if let Some(height) = self.row_height.get().or(self.measured.get()) {
+            return height;
+        }
+        let Some(first) = self.items.borrow().first().cloned() else {
+            return 0;
+        };
+        let row = self
+            .rows
+            .borrow()
+            .get(&(self.key)(&first))
+            .map(|row| row.0.clone());
+        let (row, scope) = match row {
+            Some(row) => (row, None),
+            None => {
+                let (row, scope, _) = self.build(&first);
+                (row, Some(scope))
+            }
+        };
+        self.set_row_width(&row);
+        let height = row.height_bounds().0;
+        if let Some(scope) = scope {
+            scope.dispose();
+        }
+        self.measured.set(Some(height));
+        height
+    }
+    fn set_row_width(&self, row: &Rc<dyn WidgetBase>) {
+        let padding = self.get_padding();
+        let width = self
+            .get_computed_size()
+            .w
+            .saturating_sub(padding.1 + padding.3);
+        match row.get_size().w {
+            Sizing::Fixed(_) | Sizing::Stretch(0) => row.set_width(row.width_bounds().1.min(width)),
+            _ => row.set_width(width),
+        }
+    }
+    fn layout_row(&self, index: usize, row: &Rc<dyn WidgetBase>) {
+        self.set_row_width(row);
+        let padding = self.get_padding();
+        let height = self.get_row_height();
+        row.set_height(height);
+        row.set_offset(Offset::new(
+            padding.3 as i32,
+            padding.0 as i32 + (index as u32 * height) as i32,
+        ));
+    }
+    fn shown_rows(&self) -> Vec<(usize, Rc<dyn WidgetBase>)> {
+        let (first, last) = self.shown.get();
+        let items = self.items.borrow();
+        let rows = self.rows.borrow();
+        (first..last)
+            .filter_map(|i| Some((i, rows.get(&(self.key)(items.get(i)?))?.0.clone())))
+            .collect()
+    }
+}
+
+impl<T: Clone + Eq + 'static, K: Clone + Eq + Hash + 'static> WidgetBase for VirtualList<T, K> {
+    fn set_size(&self, size: Size) {
+        self.base.set_size(size);
+    }
+    fn get_size(&self) -> Size {
+        self.base.get_size()
+    }
+    fn set_pos(&self, pos: Offset) {
+        self.base.set_pos(pos);
+    }
+    fn get_pos(&self) -> Offset {
+        self.base.get_pos()
+    }
+    fn set_frame(&self, frame: String) {
+        self.base.set_frame(frame);
+    }
+    fn set_background_color(&self, color: Rgba) {
+        self.base.set_background_color(color);
+    }
+    fn get_background_color(&self) -> Rgba {
+        self.base.get_background_color()
+    }
+    fn set_padding(&self, padding: u32) {
+        self.base.set_padding(padding);
+    }
+    fn get_padding(&self) -> (u32, u32, u32, u32) {
+        self.base.get_padding()
+    }
+    fn set_padding_sides(&self, padding: (u32, u32, u32, u32)) {
+        self.base.set_padding_sides(padding);
+    }
+    fn set_margin(&self, margin: (u32, u32, u32, u32)) {
+        self.base.set_margin(margin);
+    }
+    fn get_margin(&self) -> (u32, u32, u32, u32) {
+        self.base.get_margin()
+    }
+    fn set_border_radius(&self, radius: u32) {
+        self.base.set_border_radius(radius);
+    }
+    fn get_border_radius(&self) -> u32 {
+        self.base.get_border_radius()
+    }
+    // No meaning here
+    fn set_color(&self, _color: Rgba) {}
+    // No meaning here
+    fn set_text(&self, _text: &str) {}
+    fn get_text(&self) -> String {
+        "".to_owned()
+    }
+    fn set_text_align(&self, _: core::fmt::Alignment) {}
+    fn set_disabled(&self, _: bool) {}
+    fn is_disabled(&self) -> bool {
+        false
+    }
+}
+
+impl<T: Clone + Eq + 'static, K: Clone + Eq + Hash + 'static> WidgetExt for VirtualList<T, K> {
+    fn new() -> Rc<Self> {
+        Rc::new(Self::new_internal(
+            Box::new(|_| unreachable!("no items without `virtual_list`")),
+            Box::new(|_| Widget::new()),
+        ))
+    }
+
+    fn on_hover<F: FnMut(&Self, Offset) + 'static>(self: Rc<Self>, _f: F) -> Rc<Self> {
+        self
+    }
+    fn on_click<F: FnMut(&Self, Offset) + 'static>(self: Rc<Self>, _f: F) -> Rc<Self> {
+        self
+    }
+}
+
+impl<T: Clone + Eq + 'static, K: Clone + Eq + Hash + 'static> WidgetInternal for VirtualList<T, K> {
+    fn set_font(&self, font: ab_glyph::FontArc) {
+        for (row, _, _) in self.rows.borrow().values() {
+            row.set_font(font.clone());
+        }
+        *self.font.borrow_mut() = Some(font);
+        self.measured.set(None);
+    }
+    fn width_bounds(&self) -> (u32, u32) {
+        let padding = self.get_padding();
+        let rows = self.shown_rows();
+        let min = rows
+            .iter()
+            .map(|(_, w)| w.width_bounds().0)
+            .max()
+            .unwrap_or(0)
+            + padding.1
+            + padding.3;
+        let max = rows
+            .iter()
+            .map(|(_, w)| w.width_bounds().1)
+            .max()
+            .unwrap_or(0);
+        let max = max.saturating_add(padding.1 + padding.3).max(min);
+        match self.get_size().w {
+            Sizing::Fixed(w) => (w.clamp(min, max), w.clamp(min, max)),
+            _ => (min, max),
+        }
+    }
+    fn set_width(&self, width: u32) {
+        self.base.set_width(width);
+        for (i, row) in self.shown_rows() {
+            self.layout_row(i, &row);
+        }
+    }
+    fn height_bounds(&self) -> (u32, u32) {
+        let padding = self.get_padding();
+        let height = (self.len() as u32)
+            .saturating_mul(self.get_row_height())
+            .saturating_add(padding.0 + padding.2);
+        match self.get_size().h {
+            Sizing::Fixed(h) => (h.max(height), h.max(height)),
+            _ => (height, height),
+        }
+    }
+    fn set_height(&self, height: u32) {
+        self.base.set_height(height);
+    }
+    fn get_computed_size(&self) -> ComputedSize {
+        self.base.get_computed_size()
+    }
+    fn get_offset(&self) -> Offset {
+        self.base.get_offset()
+    }
+    fn set_offset(&self, pos: Offset) {
+        self.base.set_offset(pos);
+        for (i, row) in self.shown_rows() {
+            self.layout_row(i, &row);
+        }
+    }
+    fn set_viewport(&self, rect: Rect) {
+        let padding = self.get_padding();
+        let height = self.get_row_height().max(1) as i32;
+        let len = self.len();
+        let top = (rect.y - padding.0 as i32).max(0);
+        let bottom = (rect.y + rect.h as i32 - padding.0 as i32).max(0);
+        let first = ((top / height) as usize).saturating_sub(OVERSCAN).min(len);
+        let last = ((bottom / height) as usize + 1 + OVERSCAN).min(len);
+
+        let items: Vec<_> = self.items.borrow()[first..last].to_vec();
+        let mut shown = HashSet::with_capacity(items.len());
+        for (i, item) in (first..).zip(&items) {
+            let key = (self.key)(item);
+            let row = self.rows.borrow().get(&key).map(|row| row.0.clone());
+            let row = row.unwrap_or_else(|| {
+                let row = self.build(item);
+                let widget = row.0.clone();
+                self.rows.borrow_mut().insert(key.clone(), row);
+                widget
+            });
+            self.layout_row(i, &row);
+            shown.insert(key);
+        }
+        self.shown.set((first, last));
+
+        // Rows gone out of view are kept for a while.
+        let mut recycled = self.recycled.borrow_mut();
+        recycled.retain(|key| !shown.contains(key));
+        let mut rows = self.rows.borrow_mut();
+        for key in rows.keys() {
+            if !shown.contains(key) && !recycled.contains(key) {
+                recycled.push_back(key.clone());
+            }
+        }
+        while recycled.len() > RECYCLED {
+            let key = recycled.pop_front().unwrap();
+            if let Some((_, scope, _)) = rows.remove(&key) {
+                scope.dispose();
+            }
+        }
+    }
+    fn get_frame(&self) -> themes::FrameFn {
+        self.base.get_frame()
+    }
+    fn draw_frame(&self, buf: &dyn Drawable) {
+        self.base.draw_frame(buf);
+    }
+    fn draw(&self, buf: &mut dyn Drawable) {
+        let bounds = Rect::new(self.get_offset(), self.get_computed_size());
+        buf.subregion(bounds);
+        self.draw_frame(buf);
+        for (_, row) in self.shown_rows() {
+            row.draw(buf);
+        }
+        buf.end_subregion();
+    }
+    fn draw_overlays(&self, buf: &mut Buffer) {
+        for (_, row) in self.shown_rows() {
+            row.draw_overlays(buf);
+        }
+    }
+    fn children(&self) -> Vec<Rc<dyn WidgetBase>> {
+        self.shown_rows().into_iter().map(|(_, row)| row).collect()
+    }
+
+    fn key_bindings(&self) -> Option<&KeyBindings> {
+        self.base.key_bindings()
+    }
+    fn handle_button(self: Rc<Self>, pos: Offset, pressed: Option<Rc<Window>>) {
+        let pos = pos - self.get_offset();
+        let size = self.get_computed_size();
+        if pos.x < 0 || pos.y < 0 || pos.x > size.w as i32 || pos.y > size.h as i32 {
+            return;
+        }
+        for row in self.children() {
+            row.handle_button(pos, pressed.clone());
+        }
+    }
+    fn handle_hover(self: Rc<Self>, pos: Offset) -> HoverResult {
+        let pos = pos - self.get_offset();
+        let size = self.get_computed_size();
+        let mut result = HoverResult {
+            redraw: false,
+            cursor: CursorType::Arrow,
+        };
+        if pos.x < 0 || pos.y < 0 || pos.x > size.w as i32 || pos.y > size.h as i32 {
+            return result;
+        }
+        for row in self.children() {
+            result |= row.handle_hover(pos);
+        }
+        result
+    }
+    fn handle_scroll(self: Rc<Self>, pos: Offset, dx: f32, dy: f32, discrete: bool) -> bool {
+        let pos = pos - self.get_offset();
+        let size = self.get_computed_size();
+        if pos.x < 0 || pos.y < 0 || pos.x > size.w as i32 || pos.y > size.h as i32 {
+            return false;
+        }
+        self.children()
+            .into_iter()
+            .any(|row| row.handle_scroll(pos, dx, dy, discrete))
+    }
+    fn handle_overlay_button(self: Rc<Self>, pos: Offset, pressed: Option<Rc<Window>>) -> bool {
+        let mut result = false;
+        for row in self.children() {
+            result |= row.handle_overlay_button(pos, pressed.clone());
+        }
+        result
+    }
+    fn handle_overlay_hover(self: Rc<Self>, pos: Offset) -> HoverResult {
+        let mut result = HoverResult {
+            redraw: false,
+            cursor: CursorType::Arrow,
+        };
+        for row in self.children() {
+            result |= row.handle_overlay_hover(pos);
+        }
+        result
+    }
+}
+
+/// Rows of `row` for each of `items`, kept in sync with them. Rows are matched by `key`, only
+/// new keys and changed items run `row`. Meant to go in a
+/// [`scroll_view`](super::scroll_view::scroll_view).
+pub fn virtual_list<T, K, W>(
+    items: impl Fn() -> Vec<T> + 'static,
+    key: impl Fn(&T) -> K + 'static,
+    row: impl Fn(&T) -> W + 'static,
+) -> Rc<VirtualList<T, K>>
+where
+    T: Clone + Eq + 'static,
+    K: Clone + Eq + Hash + 'static,
+    W: IntoWidget + 'static,
+{
+    let list = Rc::new(VirtualList::new_internal(
+        Box::new(key),
+        Box::new(move |item| row(item).into_widget()),
+    ));
+    let weak = Rc::downgrade(&list);
+    create_effect(move |_| {
+        let items = items();
+        if let Some(list) = weak.upgrade() {
+            list.set_items(items);
+        }
+    });
+    list
+}
@@ -9,6 +9,7 @@ use crate::{
     Sizing,
     app::{CursorType, FRAMES, HoverResult},
     reactive::{RwSignal, SignalGet, SignalUpdate},
+    shortcut::KeyBindings,
     themes,
     window::Window,
 };
@@ -18,12 +19,16 @@ use super::{ComputedSize, Size, WidgetBase, WidgetExt, WidgetInternal};
 pub struct Widget {
     size: Cell<Size>,
     computed_size: Cell<ComputedSize>,
+    /// From `set_pos`, layout doesn't touch it.
     pos: Cell<Offset>,
+    offset: Cell<Offset>,
     frame: RefCell<themes::FrameFn>,
     padding: Cell<(u32, u32, u32, u32)>,
+    margin: Cell<(u32, u32, u32, u32)>,
     bg_color: Cell<Rgba>,
     border_radius: Cell<u32>,
     disabled: RwSignal<bool>,
+    key_bindings: KeyBindings,
 }
 
 impl Widget {
@@ -32,11 +37,14 @@ impl Widget {
             size: Default::default(),
             computed_size: Default::default(),
             pos: Default::default(),
+            offset: Default::default(),
             frame: RefCell::new(themes::NONE_FN.with(Rc::clone)),
             padding: Cell::new((0, 0, 0, 0)),
+            margin: Cell::new((0, 0, 0, 0)),
             bg_color: Cell::new(Rgba::WHITE),
             border_radius: Default::default(),
             disabled: RwSignal::new(false),
+            key_bindings: KeyBindings::default(),
         }
     }
 }
@@ -51,6 +59,9 @@ impl WidgetBase for Widget {
     fn set_pos(&self, pos: Offset) {
         self.pos.set(pos);
     }
+    fn get_pos(&self) -> Offset {
+        self.pos.get()
+    }
     fn set_frame(&self, frame: String) {
         *self.frame.borrow_mut() = FRAMES.with_borrow(|map| {
             map.get(&frame)
@@ -71,6 +82,15 @@ impl WidgetBase for Widget {
     fn get_padding(&self) -> (u32, u32, u32, u32) {
         self.padding.get()
     }
+    fn set_padding_sides(&self, padding: (u32, u32, u32, u32)) {
+        self.padding.set(padding);
+    }
+    fn set_margin(&self, margin: (u32, u32, u32, u32)) {
+        self.margin.set(margin);
+    }
+    fn get_margin(&self) -> (u32, u32, u32, u32) {
+        self.margin.get()
+    }
     fn set_border_radius(&self, radius: u32) {
         self.border_radius.set(radius);
     }
@@ -108,10 +128,11 @@ impl WidgetInternal for Widget {
     fn set_font(&self, _: ab_glyph::FontArc) {}
     fn width_bounds(&self) -> (u32, u32) {
         let padding = self.get_padding();
-        match self.get_size().w {
+        let bounds = match self.get_size().w {
             Sizing::Fixed(w) => (w, w),
             _ => (padding.1 + padding.3, padding.1 + padding.3),
-        }
+        };
+        self.get_size().limit_width(bounds)
     }
     fn set_width(&self, width: u32) {
         self.computed_size
@@ -119,10 +140,12 @@ impl WidgetInternal for Widget {
     }
     fn height_bounds(&self) -> (u32, u32) {
         let padding = self.get_padding();
-        match self.get_size().h {
+        let bounds = match self.get_size().h {
             Sizing::Fixed(h) => (h, h),
             _ => (padding.0 + padding.2, padding.0 + padding.2),
-        }
+        };
+        self.get_size()
+            .limit_height(bounds, self.get_computed_size().w)
     }
     fn set_height(&self, height: u32) {
         self.computed_size
@@ -132,10 +155,10 @@ impl WidgetInternal for Widget {
         self.computed_size.get()
     }
     fn get_offset(&self) -> Offset {
-        self.pos.get()
+        self.offset.get()
     }
     fn set_offset(&self, pos: Offset) {
-        self.pos.set(pos);
+        self.offset.set(pos);
     }
     fn get_frame(&self) -> themes::FrameFn {
         self.frame.borrow().clone()
@@ -150,6 +173,9 @@ impl WidgetInternal for Widget {
         self.draw_frame(buf);
         buf.end_subregion();
     }
+    fn key_bindings(&self) -> Option<&KeyBindings> {
+        Some(&self.key_bindings)
+    }
     fn handle_button(self: Rc<Self>, _: Offset, _: Option<Rc<Window>>) {}
     fn handle_hover(self: Rc<Self>, _: Offset) -> HoverResult {
         HoverResult {
@@ -0,0 +1,383 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use lite_graphics::{Buffer, Drawable, Rect, color::Rgba};
+
+use crate::{
+    Align, Sizing,
+    app::{CursorType, HoverResult},
+    shortcut::KeyBindings,
+    themes,
+    window::Window,
+};
+
+use super::{
+    ComputedSize, Offset, Size, Widget, WidgetBase, WidgetExt, WidgetGroup, WidgetInternal,
+};
+
+struct Layer {
+    widget: Rc<dyn WidgetBase>,
+    align: Cell<(Align, Align)>,
+}
+
+/// Children on top of each other, the last one on top. Each is aligned in the stack, then moved
+/// by its [`pos`](WidgetExt::pos).
+pub struct ZStack {
+    base: Widget,
+    layers: RefCell<Vec<Layer>>,
+}
+
+impl ZStack {
+    fn new_internal(children: Vec<Rc<dyn WidgetBase>>) -> Self {
+        let this = Self {
+            base: Widget::new_internal(),
+            layers: RefCell::new(
+                children
+                    .into_iter()
+                    .map(|widget| Layer {
+                        widget,
+                        align: Cell::new((Align::Start, Align::Start)),
+                    })
+                    .collect(),
+            ),
+        };
+        this.base.set_frame(themes::FrameType::Box.to_string());
+        this
+    }
+    /// Anchor child `index` to a side, corner or the center.
+    pub fn align(self: Rc<Self>, index: usize, x: Align, y: Align) -> Rc<Self> {
+        if let Some(layer) = self.layers.borrow().get(index) {
+            layer.align.set((x, y));
+        }
+        self
+    }
+    /// The topmost child at `pos`, relative to the stack.
+    fn hit(&self, pos: Offset) -> Option<Rc<dyn WidgetBase>> {
+        self.layers.borrow().iter().rev().find_map(|layer| {
+            let offs = layer.widget.get_offset();
+            let size = layer.widget.get_computed_size();
+            let inside = pos.x >= offs.x
+                && pos.y >= offs.y
+                && pos.x < offs.x + size.w as i32
+                && pos.y < offs.y + size.h as i32;
+            inside.then(|| layer.widget.clone())
+        })
+    }
+}
+
+impl WidgetBase for ZStack {
+    fn set_size(&self, size: Size) {
+        self.base.set_size(size);
+    }
+    fn get_size(&self) -> Size {
+        self.base.get_size()
+    }
+    fn set_pos(&self, pos: Offset) {
+        self.base.set_pos(pos);
+    }
+    fn get_pos(&self) -> Offset {
+        self.base.get_pos()
+    }
+    fn set_frame(&self, frame: String) {
+        self.base.set_frame(frame);
+    }
+    fn set_background_color(&self, color: Rgba) {
+        self.base.set_background_color(color);
+    }
+    fn get_background_color(&self) -> Rgba {
+        self.base.get_background_color()
+    }
+    fn set_padding(&self, padding: u32) {
+        self.base.set_padding(padding);
+    }
+    fn get_padding(&self) -> (u32, u32, u32, u32) {
+        self.base.get_padding()
+    }
+    fn set_padding_sides(&self, padding: (u32, u32, u32, u32)) {
+        self.base.set_padding_sides(padding);
+    }
+    fn set_margin(&self, margin: (u32, u32, u32, u32)) {
+        self.base.set_margin(margin);
+    }
+    fn get_margin(&self) -> (u32, u32, u32, u32) {
+        self.base.get_margin()
+    }
+    fn set_border_radius(&self, radius: u32) {
+        self.base.set_border_radius(radius);
+    }
+    fn get_border_radius(&self) -> u32 {
+        self.base.get_border_radius()
+    }
+    // No meaning here
+    fn set_color(&self, _color: Rgba) {}
+    // No meaning here
+    fn set_text(&self, _text: &str) {}
+    fn get_text(&self) -> String {
+        "".to_owned()
+    }
+    fn set_text_align(&self, _: core::fmt::Alignment) {}
+    fn set_disabled(&self, _: bool) {}
+    fn is_disabled(&self) -> bool {
+        false
+    }
+}
+
+impl WidgetExt for ZStack {
+    fn new() -> Rc<Self> {
+        Rc::new(Self::new_internal(vec![]))
+    }
+
+    fn on_hover<F: FnMut(&Self, Offset) + 'static>(self: Rc<Self>, _f: F) -> Rc<Self> {
+        self
+    }
+    fn on_click<F: FnMut(&Self, Offset) + 'static>(self: Rc<Self>, _f: F) -> Rc<Self> {
+        self
+    }
+}
+
+impl WidgetInternal for ZStack {
+    fn set_font(&self, font: ab_glyph::FontArc) {
+        for layer in &*self.layers.borrow() {
+            layer.widget.set_font(font.clone());
+        }
+    }
+    fn width_bounds(&self) -> (u32, u32) {
+        let padding = self.get_padding();
+        let (mut min, mut max) = (0u32, 0u32);
+        for layer in &*self.layers.borrow() {
+            let bounds = layer.widget.width_bounds();
+            let pos = layer.widget.get_pos().x.max(0) as u32;
+            min = min.max(bounds.0 + pos);
+            max = max.max(bounds.1.saturating_add(pos));
+        }
+        let min = min + padding.1 + padding.3;
+        let max = max.saturating_add(padding.1 + padding.3).max(min);
+        match self.get_size().w {
+            Sizing::Fixed(w) => (w.clamp(min, max), w.clamp(min, max)),
+            Sizing::Stretch(0) => (min, min),
+            _ => (min, max),
+        }
+    }
+    fn set_width(&self, width: u32) {
+        let padding = self.get_padding();
+        self.base.set_width(width);
+        let inner = width.saturating_sub(padding.1 + padding.3);
+        for layer in &*self.layers.borrow() {
+            let bounds = layer.widget.width_bounds();
+            match layer.widget.get_size().w {
+                Sizing::Fill | Sizing::Stretch(1..) => layer.widget.set_width(
+                    inner
+                        .saturating_sub(layer.widget.get_pos().x.unsigned_abs())
+                        .max(bounds.0),
+                ),
+                _ => layer.widget.set_width(bounds.0),
+            }
+        }
+    }
+    fn height_bounds(&self) -> (u32, u32) {
+        let padding = self.get_padding();
+        let (mut min, mut max) = (0u32, 0u32);
+        for layer in &*self.layers.borrow() {
+            let bounds = layer.widget.height_bounds();
+            let pos = layer.widget.get_pos().y.max(0) as u32;
+            min = min.max(bounds.0 + pos);
+            max = max.max(bounds.1.saturating_add(pos));
+        }
+        let min = min + padding.0 + padding.2;
+        let max = max.saturating_add(padding.0 + padding.2).max(min);
+        match self.get_size().h {
+            Sizing::Fixed(h) => (h.clamp(min, max), h.clamp(min, max)),
+            Sizing::Stretch(0) => (min, min),
+            _ => (min, max),
+        }
+    }
+    fn set_height(&self, height: u32) {
+        let padding = self.get_padding();
+        self.base.set_height(height);
+        let inner = height.saturating_sub(padding.0 + padding.2);
+        for layer in &*self.layers.borrow() {
+            let bounds = layer.widget.height_bounds();
+            match layer.widget.get_size().h {
+                Sizing::Fill | Sizing::Stretch(1..) => layer.widget.set_height(
+                    inner
+                        .saturating_sub(layer.widget.get_pos().y.unsigned_abs())
+                        .max(bounds.0),
+                ),
+                _ => layer.widget.set_height(bounds.0),
+            }
+        }
+    }
+    fn get_computed_size(&self) -> ComputedSize {
+        self.base.get_computed_size()
+    }
+    fn get_offset(&self) -> Offset {
+        self.base.get_offset()
+    }
+    fn set_offset(&self, pos: Offset) {
+        self.base.set_offset(pos);
+        let padding = self.get_padding();
+        let size = self.get_computed_size();
+        let inner = ComputedSize::new(
+            size.w.saturating_sub(padding.1 + padding.3),
+            size.h.saturating_sub(padding.0 + padding.2),
+        );
+        for layer in &*self.layers.borrow() {
+            let child = layer.widget.get_computed_size();
+            let (x, y) = layer.align.get();
+            let pos = layer.widget.get_pos();
+            layer.widget.set_offset(Offset::new(
+                padding.3 as i32 + x.place(child.w, inner.w) + pos.x,
+                padding.0 as i32 + y.place(child.h, inner.h) + pos.y,
+            ));
+        }
+    }
+    fn set_viewport(&self, rect: Rect) {
+        for layer in &*self.layers.borrow() {
+            let offs = layer.widget.get_offset();
+            layer.widget.set_viewport(Rect::new(
+                Offset::new(rect.x - offs.x, rect.y - offs.y),
+                ComputedSize::new(rect.w, rect.h),
+            ));
+        }
+    }
+    fn get_frame(&self) -> themes::FrameFn {
+        self.base.get_frame()
+    }
+    fn draw_frame(&self, buf: &dyn Drawable) {
+        self.base.draw_frame(buf);
+    }
+    fn draw(&self, buf: &mut dyn Drawable) {
+        let bounds = Rect::new(self.get_offset(), self.get_computed_size());
+        buf.subregion(bounds);
+        self.draw_frame(buf);
+        for layer in &*self.layers.borrow() {
+            layer.widget.draw(buf);
+        }
+        buf.end_subregion();
+    }
+    fn draw_overlays(&self, buf: &mut Buffer) {
+        for layer in &*self.layers.borrow() {
+            layer.widget.draw_overlays(buf);
+        }
+    }
+    fn children(&self) -> Vec<Rc<dyn WidgetBase>> {
+        self.layers
+            .borrow()
+            .iter()
+            .map(|l| l.widget.clone())
+            .collect()
+    }
+
+    fn key_bindings(&self) -> Option<&KeyBindings> {
+        self.base.key_bindings()
+    }
+    fn handle_button(self: Rc<Self>, pos: Offset, pressed: Option<Rc<Window>>) {
+        let pos = pos - self.get_offset();
+        // Releases go everywhere, a press may have started under another layer.
+        if pressed.is_none() {
+            for child in self.children() {
+                child.handle_button(pos, None);
+            }
+        } else if let Some(child) = self.hit(pos) {
+            child.handle_button(pos, pressed);
+        }
+    }
+    fn handle_hover(self: Rc<Self>, pos: Offset) -> HoverResult {
+        let pos = pos - self.get_offset();
+        let top = self.hit(pos);
+        let mut result = HoverResult {
+            redraw: false,
+            cursor: CursorType::Arrow,
+        };
+        // Layers under the top one lose their hover.
+        for child in self.children() {
+            let pos = match &top {
+                Some(top) if Rc::ptr_eq(top, &child) => pos,
+                _ => Offset::new(i32::MIN / 2, i32::MIN / 2),
+            };
+            result |= child.handle_hover(pos);
+        }
+        result
+    }
+    fn handle_scroll(self: Rc<Self>, pos: Offset, dx: f32, dy: f32, discrete: bool) -> bool {
+        let pos = pos - self.get_offset();
+        self.hit(pos)
+            .is_some_and(|child| child.handle_scroll(pos, dx, dy, discrete))
+    }
+    fn handle_overlay_button(self: Rc<Self>, pos: Offset, pressed: Option<Rc<Window>>) -> bool {
+        let mut result = false;
+        for child in self.children() {
+            result |= child.handle_overlay_button(pos, pressed.clone());
+        }
+        result
+    }
+    fn handle_overlay_hover(self: Rc<Self>, pos: Offset) -> HoverResult {
+        let mut result = HoverResult {
+            redraw: false,
+            cursor: CursorType::Arrow,
+        };
+        for child in self.children() {
+            result |= child.handle_overlay_hover(pos);
+        }
+        result
+    }
+}
+
+/// Stack `widgets` on top of each other, the last one on top.
+pub fn zstack<G: WidgetGroup>(widgets: G) -> Rc<ZStack> {
+    Rc::new(ZStack::new_internal(widgets.create_group()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn boxed(w: u32, h: u32) -> Rc<Widget> {
+        Widget::new().size(Size::fixed(w, h))
+    }
+
+    fn lay_out(widget: &dyn WidgetBase, w: u32, h: u32) {
+        widget.set_width(w);
+        widget.set_height(h);
+        widget.set_offset(Offset::default());
+    }
+
+    #[test]
+    fn anchor_and_pos() {
+        let (a, b) = (boxed(10, 10), boxed(10, 10).pos(Offset::new(-2, 3)));
+        let stack = zstack((boxed(100, 50), a.clone(), b.clone()))
+            .align(1, Align::Center, Align::End)
+            .align(2, Align::End, Align::Start);
+        lay_out(&*stack, 100, 50);
+        assert_eq!(a.get_offset(), Offset::new(45, 40));
+        assert_eq!(b.get_offset(), Offset::new(88, 3));
+
+        // Layout doesn't move it further, a new pos does.
+        lay_out(&*stack, 100, 50);
+        assert_eq!(b.get_offset(), Offset::new(88, 3));
+        b.set_pos(Offset::new(-5, 4));
+        lay_out(&*stack, 100, 50);
+        assert_eq!(
+            (b.get_pos(), b.get_offset()),
+            (Offset::new(-5, 4), Offset::new(85, 4))
+        );
+    }
+
+    #[test]
+    fn hit_topmost_first() {
+        let (bottom, top) = (boxed(100, 50), boxed(20, 20).pos(Offset::new(10, 10)));
+        let stack = zstack((bottom.clone(), top.clone()));
+        lay_out(&*stack, 100, 50);
+        let hit = |x, y| stack.hit(Offset::new(x, y));
+        let is = |hit: Option<Rc<dyn WidgetBase>>, widget: &Rc<Widget>| {
+            hit.is_some_and(|hit| Rc::ptr_eq(&hit, &(widget.clone() as Rc<dyn WidgetBase>)))
+        };
+        assert!(is(hit(15, 15), &top));
+        assert!(is(hit(29, 29), &top));
+        assert!(is(hit(30, 30), &bottom));
+        assert!(is(hit(5, 5), &bottom));
+        assert!(hit(100, 20).is_none());
+    }
+}
@@ -1,50 +1,151 @@
 use std::{
+    any::Any,
     cell::{Cell, RefCell},
-    rc::Rc,
+    rc::{Rc, Weak},
 };
 
 use lite_graphics::{Offset, Overlay, Rect, color::Rgba, draw::Buffer};
 
 use crate::{
-    ComputedSize, Size, WidgetBase, WidgetExt,
-    app::CursorType,
-    button, sys, vstack,
-    widgets::{IntoWidget, Widget, input::InputBase},
+    ComputedSize, FrameType, Size, WidgetBase, WidgetExt,
+    app::{COLORS, CursorType, FRAMES},
+    button,
+    event::{Event, Key, Modifiers},
+    shortcut::{Shortcut, Shortcuts},
+    sys, vstack,
+    widgets::{IntoWidget, Widget, input::InputBase, scroll_view::ScrollView},
 };
 
+/// Settings used when creating a window.
+#[derive(Clone)]
+pub struct WindowOptions {
+    pub(crate) title: String,
+    pub(crate) app_id: String,
+    pub(crate) size: ComputedSize,
+    pub(crate) min_size: Option<ComputedSize>,
+    pub(crate) max_size: Option<ComputedSize>,
+    pub(crate) position: Option<Offset>,
+    pub(crate) resizable: bool,
+    pub(crate) decorations: bool,
+}
+
+impl Default for WindowOptions {
+    fn default() -> Self {
+        let name = std::env::current_exe()
+            .ok()
+            .and_then(|p| Some(p.file_stem()?.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| env!("CARGO_PKG_NAME").to_owned());
+        Self {
+            title: name.clone(),
+            app_id: name,
+            size: ComputedSize::new(800, 600),
+            min_size: None,
+            max_size: None,
+            position: None,
+            resizable: true,
+            decorations: true,
+        }
+    }
+}
+
+impl WindowOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn title<S: AsRef<str>>(mut self, title: S) -> Self {
+        self.title = title.as_ref().to_owned();
+        self
+    }
+    /// Application ID on Wayland, `WM_CLASS` on X11. Defaults to the executable name.
+    pub fn app_id<S: AsRef<str>>(mut self, app_id: S) -> Self {
+        self.app_id = app_id.as_ref().to_owned();
+        self
+    }
+    /// Initial size of the content area, in pixels.
+    pub fn size(mut self, w: u32, h: u32) -> Self {
+        self.size = ComputedSize::new(w, h);
+        self
+    }
+    pub fn min_size(mut self, w: u32, h: u32) -> Self {
+        self.min_size = Some(ComputedSize::new(w, h));
+        self
+    }
+    pub fn max_size(mut self, w: u32, h: u32) -> Self {
+        self.max_size = Some(ComputedSize::new(w, h));
+        self
+    }
+    /// Initial position on screen. Ignored on Wayland, where the compositor decides.
+    pub fn position(mut self, x: i32, y: i32) -> Self {
+        self.position = Some(Offset::new(x, y));
+        self
+    }
+    pub fn resizable(mut self, resizable: bool) -> Self {
+        self.resizable = resizable;
+        self
+    }
+    /// Whether to draw a title bar and borders.
+    pub fn decorations(mut self, decorations: bool) -> Self {
+        self.decorations = decorations;
+        self
+    }
+}
+
 #[derive(Clone)]
 pub struct Window {
     pub(crate) inner: sys::window::Window,
+    app: Weak<crate::App>,
     pub(crate) font: ab_glyph::FontArc,
     pub(crate) widget: RefCell<Rc<dyn WidgetBase>>,
     pub(crate) focus: RefCell<Option<Rc<dyn InputBase>>>,
+    /// Widget with the focus ring, moved with Tab and Shift+Tab.
+    pub(crate) focused: RefCell<Option<Rc<dyn WidgetBase>>>,
+    /// The focused widget and its parents, outermost first.
+    focus_path: RefCell<Vec<Rc<dyn WidgetBase>>>,
+    /// Bounds of the focused widget as of the last layout, for the focus ring.
+    focus_rect: Cell<Option<Rect>>,
+    pub(crate) shortcuts: Rc<Shortcuts>,
     pub(crate) size: RefCell<ComputedSize>,
     pub(crate) rclick_widget: RefCell<Rc<dyn WidgetBase>>,
     pub(crate) rclick_offset: Cell<Option<Offset>>,
+    pub(crate) closed: Cell<bool>,
 }
 
 impl Window {
     pub fn new(app: &Rc<crate::App>) -> crate::Result<Rc<Self>> {
-        let inner = sys::window::Window::new(&app.inner)?;
+        Self::with_options(app, WindowOptions::default())
+    }
+    pub fn with_options(app: &Rc<crate::App>, options: WindowOptions) -> crate::Result<Rc<Self>> {
+        let inner = sys::window::Window::new(&app.inner, &options)?;
 
         let font = app.font.clone();
 
         let this = Rc::new(Self {
             inner,
+            app: Rc::downgrade(app),
             font,
             widget: RefCell::new(Widget::new()),
             focus: RefCell::new(None),
-            size: RefCell::new(ComputedSize::new(800, 600)),
+            focused: RefCell::new(None),
+            focus_path: RefCell::new(vec![]),
+            focus_rect: Cell::new(None),
+            shortcuts: Rc::default(),
+            size: RefCell::new(options.size),
             rclick_widget: RefCell::new(Widget::new()),
             rclick_offset: Cell::new(None),
+            closed: Cell::new(false),
         });
         let win = Rc::downgrade(&this);
+        let app_ref = Rc::downgrade(app);
         *this.rclick_widget.borrow_mut() = vstack(
             4,
             button("Quit...")
                 .padding(4)
                 .background_color(Rgba::SILVER)
-                .on_click(move |_, _| win.upgrade().unwrap().destroy())
+                .on_click(move |_, _| {
+                    if let (Some(app), Some(win)) = (app_ref.upgrade(), win.upgrade()) {
+                        app.request_close(&win);
+                    }
+                })
                 .size(Size::stretch(1, 0)),
         )
         .padding(4)
@@ -59,11 +160,18 @@ impl Window {
         self.widget.borrow().set_font(self.font.clone());
         self.rclick_widget.borrow().set_font(self.font.clone());
     }
+    /// The app this window belongs to, `None` once it's dropped.
+    pub fn app(&self) -> Option<Rc<crate::App>> {
+        self.app.upgrade()
+    }
     pub fn resize(&self, w: u32, h: u32) {
         *self.size.borrow_mut() = ComputedSize::new(w, h);
         let _ = self.redraw();
     }
     pub fn redraw(&self) -> crate::Result<()> {
+        if self.closed.get() {
+            return Ok(());
+        }
         let size = self.size.borrow();
         let mut buffer = Buffer::new(size.w as _, size.h as _);
         let widget = self.widget.borrow();
@@ -71,8 +179,23 @@ impl Window {
         widget.set_width(size.w);
         widget.set_height(size.h);
         widget.set_offset(Offset::default());
+        widget.set_viewport(Rect::new(Offset::default(), *size));
+        drop(widget);
+        self.drop_lost_focus();
+        let widget = self.widget.borrow();
         widget.draw(&mut buffer);
-        widget.draw_overlays(&mut buffer);
+        drop(widget);
+        if let Some(rect) = self.focus_rect.get() {
+            let name = FrameType::FocusRing.to_string();
+            let ring = FRAMES.with_borrow(|f| f.get(&name).cloned());
+            let color = COLORS.with_borrow(|c| c.get(&name).copied());
+            if let (Some(ring), Some(color)) = (ring, color) {
+                let overlay = Overlay::new(buffer, rect);
+                ring(&overlay, ComputedSize::new(rect.w, rect.h), color);
+                buffer = overlay.write();
+            }
+        }
+        self.widget.borrow().draw_overlays(&mut buffer);
 
         if let Some(offs) = self.rclick_offset.get() {
             let rclick_widget = self.rclick_widget.borrow();
@@ -85,11 +208,10 @@ impl Window {
                 Overlay::new(buffer, Rect::new(offs, rclick_widget.get_computed_size()));
             rclick_widget.draw(&mut rclick_overlay);
 
-            let buffer = rclick_overlay.write();
-            self.inner.draw(buffer)
-        } else {
-            self.inner.draw(buffer)
+            buffer = rclick_overlay.write();
         }
+        self.inner.set_ime_area(self.caret_rect());
+        self.inner.draw(buffer)
     }
     pub fn set_cursor(&self, cursor: CursorType) {
         self.inner.set_cursor(cursor);
@@ -97,7 +219,269 @@ impl Window {
     pub fn hide_menu(&self) {
         self.rclick_offset.set(None);
     }
+    /// Queue an event as if the system sent it. Only works on the headless backend.
+    ///
+    /// Returns `false` if the backend doesn't accept injected events.
+    pub fn push_event(&self, event: Event) -> bool {
+        self.inner.push_event(event)
+    }
+    /// Last frame drawn by the headless backend.
+    pub fn frame(&self) -> Option<Buffer> {
+        self.inner.frame()
+    }
+    pub fn set_title(&self, title: &str) -> crate::Result<()> {
+        self.inner.set_title(title)
+    }
+    /// Ask the system to resize the content area. A resize event follows if it agrees.
+    pub fn set_size(&self, w: u32, h: u32) -> crate::Result<()> {
+        self.inner.set_size(ComputedSize::new(w, h))
+    }
+    pub fn set_min_size(&self, size: Option<ComputedSize>) -> crate::Result<()> {
+        self.inner.set_min_size(size)
+    }
+    pub fn set_max_size(&self, size: Option<ComputedSize>) -> crate::Result<()> {
+        self.inner.set_max_size(size)
+    }
+    /// Current size of the content area.
+    pub fn size(&self) -> ComputedSize {
+        *self.size.borrow()
+    }
+    /// Close this window. [`App::run`](crate::app::App::run) returns once every window is closed.
+    pub fn close(&self) {
+        if !self.closed.replace(true) {
+            self.inner.destroy();
+        }
+    }
+    #[deprecated = "use `close`"]
     pub fn destroy(&self) {
-        self.inner.destroy();
+        self.close();
+    }
+    pub fn is_closed(&self) -> bool {
+        self.closed.get()
+    }
+
+    /// Every widget with its bounds in window coordinates, in tree order.
+    pub fn widgets(&self) -> Vec<(Rc<dyn WidgetBase>, Rect)> {
+        fn walk(
+            widget: Rc<dyn WidgetBase>,
+            parent: Offset,
+            out: &mut Vec<(Rc<dyn WidgetBase>, Rect)>,
+        ) {
+            let offs = parent + widget.get_offset();
+            out.push((widget.clone(), Rect::new(offs, widget.get_computed_size())));
+            for child in widget.children() {
+                walk(child, offs, out);
+            }
+        }
+        let mut out = vec![];
+        walk(self.widget.borrow().clone(), Offset::default(), &mut out);
+        out
+    }
+    /// Caret of the focused input in window coordinates, `None` if nothing takes text.
+    fn caret_rect(&self) -> Option<Rect> {
+        let input = self.focus.borrow().clone()?;
+        let caret = input.caret_rect()?;
+        let (_, rect) = self
+            .widgets()
+            .into_iter()
+            .find(|(w, _)| std::ptr::addr_eq(Rc::as_ptr(w), Rc::as_ptr(&input)))?;
+        Some(Rect::new(
+            Offset::new(rect.x + caret.x, rect.y + caret.y),
+            ComputedSize::new(caret.w, caret.h),
+        ))
+    }
+    fn focus_chain(&self) -> Vec<(Rc<dyn WidgetBase>, Rect)> {
+        self.widgets()
+            .into_iter()
+            .filter(|(w, _)| w.focusable() && !w.is_disabled())
+            .collect()
+    }
+    /// Follow the focused widget after a layout. Its parents are searched again only if it moved
+    /// out of them.
+    fn locate_focus(&self) {
+        let Some(focused) = self.focused() else {
+            self.focus_path.take();
+            self.focus_rect.set(None);
+            return;
+        };
+        let mut path = self.focus_path.take();
+        let root = self.widget.borrow().clone();
+        let same = path.first().is_some_and(|w| Rc::ptr_eq(w, &root))
+            && path.last().is_some_and(|w| Rc::ptr_eq(w, &focused))
+            && path
+                .windows(2)
+                .all(|p| p[0].children().iter().any(|c| Rc::ptr_eq(c, &p[1])));
+        if !same {
+            path = self.path_to(&focused);
+            path.reverse();
+        }
+        let offs = path
+            .iter()
+            .fold(Offset::default(), |offs, w| offs + w.get_offset());
+        self.focus_rect.set(
+            (!path.is_empty() && !focused.is_disabled())
+                .then(|| Rect::new(offs, focused.get_computed_size())),
+        );
+        *self.focus_path.borrow_mut() = path;
+    }
+    /// Widget with keyboard focus.
+    pub fn focused(&self) -> Option<Rc<dyn WidgetBase>> {
+        self.focused.borrow().clone()
+    }
+    /// Give `widget` the keyboard focus, or take it away from everything.
+    pub fn set_focused(&self, widget: Option<Rc<dyn WidgetBase>>) {
+        self.move_focus_ring(widget.clone());
+        *self.focus.borrow_mut() = widget.and_then(|w| w.as_input());
+    }
+    /// Focus the next widget in tree order, or the previous one if `forward` is false. Wraps around.
+    pub fn focus_next(&self, forward: bool) {
+        let chain = self.focus_chain();
+        if chain.is_empty() {
+            return self.set_focused(None);
+        }
+        let current = self
+            .focused
+            .borrow()
+            .clone()
+            .and_then(|focused| chain.iter().position(|(w, _)| Rc::ptr_eq(w, &focused)));
+        let next = match (current, forward) {
+            (Some(idx), true) => (idx + 1) % chain.len(),
+            (Some(idx), false) => (idx + chain.len() - 1) % chain.len(),
+            (None, true) => 0,
+            (None, false) => chain.len() - 1,
+        };
+        self.set_focused(Some(chain[next].0.clone()));
+    }
+    /// Shortcuts of this window.
+    pub fn shortcuts(&self) -> &Shortcuts {
+        &self.shortcuts
+    }
+    /// Shortcuts of the focused widget and its parents, then of this window.
+    pub fn active_shortcuts(&self) -> Vec<(Shortcut, String)> {
+        let mut list: Vec<_> = self
+            .focus_path()
+            .iter()
+            .filter_map(|w| w.key_bindings().map(|b| b.shortcuts().list()))
+            .flatten()
+            .collect();
+        list.extend(self.shortcuts.list());
+        list
+    }
+    /// Return: If a binding ran
+    pub(crate) fn handle_shortcut(&self, shortcut: Shortcut) -> bool {
+        if self.focus_path().iter().any(|w| {
+            w.key_bindings()
+                .is_some_and(|b| b.shortcuts().trigger(shortcut))
+        }) {
+            return true;
+        }
+        // Typing into an input wins over window shortcuts.
+        if shortcut.is_text() && self.focus.borrow().is_some() {
+            return false;
+        }
+        self.shortcuts.trigger(shortcut)
+    }
+    /// Run the key handlers of the focused widget and its parents.
+    ///
+    /// Return: If any ran
+    pub(crate) fn run_key_handlers(&self, pressed: bool, mods: Modifiers, key: Key) -> bool {
+        self.focus_path().iter().fold(false, |ran, w| {
+            w.key_bindings()
+                .is_some_and(|b| b.trigger_key(pressed, mods, key))
+                | ran
+        })
+    }
+    /// The focused widget and its parents, innermost first. Just the root if nothing is focused.
+    fn focus_path(&self) -> Vec<Rc<dyn WidgetBase>> {
+        match self.focused() {
+            Some(focused) => self.path_to(&focused),
+            None => vec![self.widget.borrow().clone()],
+        }
+    }
+    /// `target` and its parents, innermost first. Empty if it isn't in this window.
+    fn path_to(&self, target: &Rc<dyn WidgetBase>) -> Vec<Rc<dyn WidgetBase>> {
+        fn find(
+            widget: Rc<dyn WidgetBase>,
+            target: &Rc<dyn WidgetBase>,
+            path: &mut Vec<Rc<dyn WidgetBase>>,
+        ) -> bool {
+            if Rc::ptr_eq(&widget, target)
+                || widget.children().into_iter().any(|c| find(c, target, path))
+            {
+                path.push(widget);
+                return true;
+            }
+            false
+        }
+        let mut path = vec![];
+        find(self.widget.borrow().clone(), target, &mut path);
+        path
+    }
+    /// Follow the focus after a layout, taking it away from widgets that left the tree, e.g.
+    /// swapped out by a switch.
+    pub(crate) fn drop_lost_focus(&self) {
+        self.locate_focus();
+        if self.focused().is_some() && self.focus_path.borrow().is_empty() {
+            self.move_focus_ring(None);
+        }
+        let input = self.focus.borrow().clone();
+        let Some(input) = input else {
+            return;
+        };
+        let is_input =
+            |w: &Rc<dyn WidgetBase>| std::ptr::addr_eq(Rc::as_ptr(w), Rc::as_ptr(&input));
+        // Usually the input has the ring too.
+        if !self.focus_path.borrow().iter().any(is_input)
+            && !self.widgets().iter().any(|(w, _)| is_input(w))
+        {
+            *self.focus.borrow_mut() = None;
+        }
+    }
+    /// Put the ring on the innermost focusable widget at `pos`, after a click.
+    pub(crate) fn focus_at(&self, pos: Offset) {
+        fn find(
+            widget: Rc<dyn WidgetBase>,
+            parent: Offset,
+            pos: Offset,
+        ) -> Option<Rc<dyn WidgetBase>> {
+            let r = Rect::new(parent + widget.get_offset(), widget.get_computed_size());
+            if pos.x < r.x || pos.y < r.y || pos.x >= r.x + r.w as i32 || pos.y >= r.y + r.h as i32
+            {
+                return None;
+            }
+            let offs = Offset::new(r.x, r.y);
+            widget
+                .children()
+                .into_iter()
+                .rev()
+                .find_map(|c| find(c, offs, pos))
+                .or_else(|| (widget.focusable() && !widget.is_disabled()).then_some(widget))
+        }
+        let target = find(self.widget.borrow().clone(), Offset::default(), pos);
+        self.move_focus_ring(target);
+    }
+    /// Scroll every scroll view around `widget` so it shows.
+    fn reveal(&self, widget: &Rc<dyn WidgetBase>) {
+        for parent in self.path_to(widget) {
+            if let Ok(view) = (parent as Rc<dyn Any>).downcast::<ScrollView>() {
+                view.scroll_to_widget(widget);
+            }
+        }
+    }
+    fn move_focus_ring(&self, widget: Option<Rc<dyn WidgetBase>>) {
+        let old = self.focused.replace(widget.clone());
+        match (old, widget) {
+            (Some(old), Some(new)) if Rc::ptr_eq(&old, &new) => {}
+            (old, new) => {
+                if let Some(old) = old {
+                    old.set_focused(false);
+                }
+                if let Some(new) = new {
+                    new.set_focused(true);
+                    self.reveal(&new);
+                }
+                self.locate_focus();
+            }
+        }
     }
 }
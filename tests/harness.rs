@@ -0,0 +1,383 @@
+#![cfg(feature = "testing")]
+
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use lite_graphics::Offset;
+use parslay::{
+    Size,
+    event::{Button, Key, Modifiers},
+    prelude::*,
+    reactive::{SignalGet as _, SignalUpdate as _},
+    testing::Harness,
+    widgets::scroll_view::ScrollView,
+};
+
+const BACKGROUND: [u8; 4] = [200, 40, 40, 255];
+
+/// The flight booker from 7GUIs, cut down to a date and the Book button.
+fn booker() -> Harness {
+    Harness::new(|| {
+        let start = RwSignal::new("2026.03.28".to_owned());
+        let result = RwSignal::new(String::new());
+        vstack(
+            4,
+            (
+                dyn_input(move || start.get()),
+                button("Book")
+                    .on_click(move |_, _| result.set(format!("Flight booked on {}", start.get()))),
+                dyn_label(move || result.get()),
+            ),
+        )
+        .background_color(Rgba::from(BACKGROUND))
+    })
+    .unwrap()
+}
+
+fn is_background(color: Rgba) -> bool {
+    [color.r, color.g, color.b] == BACKGROUND[..3]
+}
+
+#[test]
+fn book_sets_result() {
+    let ui = booker();
+    ui.step().unwrap();
+    assert!(!ui.has_text("Flight booked on 2026.03.28"));
+
+    assert!(ui.click_text("Book", Button::Left));
+    ui.step().unwrap();
+    assert!(ui.has_text("Flight booked on 2026.03.28"));
+    assert!(is_background(ui.pixel(799, 599)));
+}
+
+#[test]
+fn book_with_keyboard() {
+    let ui = booker();
+    ui.step().unwrap();
+
+    // The date, then the button.
+    ui.key(Modifiers::NONE, Key::Tab);
+    ui.key(Modifiers::NONE, Key::Tab);
+    ui.key(Modifiers::NONE, Key::Space);
+    ui.step().unwrap();
+    assert!(ui.has_text("Flight booked on 2026.03.28"));
+}
+
+#[test]
+fn resize_redraws() {
+    let ui = booker();
+    ui.resize(320, 240);
+    ui.step().unwrap();
+    assert_eq!(ui.frame().data().len(), 320 * 240 * 3);
+    assert!(is_background(ui.pixel(319, 239)));
+}
+
+#[test]
+fn dyn_stack_skips_duplicate_keys() {
+    let items = RwSignal::new(vec!["a", "b", "a"]);
+    let ui = Harness::new(move || dyn_stack(4, move || items.get(), |item| *item, label)).unwrap();
+    ui.step().unwrap();
+    let count = |text: &str| {
+        ui.widgets()
+            .iter()
+            .filter(|(widget, _)| widget.get_text() == text)
+            .count()
+    };
+    assert_eq!((count("a"), count("b")), (1, 1));
+
+    items.set(vec!["b", "b"]);
+    ui.step().unwrap();
+    assert_eq!((count("a"), count("b")), (0, 1));
+}
+
+#[test]
+fn hiding_the_focused_widget_drops_focus() {
+    let shown = RwSignal::new(true);
+    let ui = Harness::new(move || vstack(4, (show(move || shown.get(), input), button("Other"))))
+        .unwrap();
+    ui.step().unwrap();
+    ui.key(Modifiers::NONE, Key::Tab);
+    ui.step().unwrap();
+    assert!(ui.window().focused().is_some());
+
+    shown.set(false);
+    ui.step().unwrap();
+    assert!(ui.window().focused().is_none());
+    ui.key(Modifiers::NONE, Key::Tab);
+    ui.step().unwrap();
+    assert_eq!(ui.window().focused().unwrap().get_text(), "Other");
+}
+
+#[test]
+fn switch_keeps_size_and_margin() {
+    let shown = RwSignal::new(true);
+    let ui = Harness::new(move || {
+        vstack(
+            0,
+            (
+                either(move || shown.get(), || label("A"), || label("B"))
+                    .size(Size::fixed(120, 30))
+                    .margin(5, 0, 0, 10),
+                label("Below"),
+            ),
+        )
+    })
+    .unwrap();
+    ui.step().unwrap();
+    for text in ["A", "B"] {
+        let rect = ui.locate(text).unwrap();
+        assert_eq!((rect.x, rect.y, rect.w, rect.h), (10, 5, 120, 30), "{text}");
+        shown.set(false);
+        ui.step().unwrap();
+    }
+}
+
+#[test]
+fn page_keys_move_by_visible_lines() {
+    // Inside a stack, the root would fill the window.
+    let ui = Harness::new(|| {
+        vstack(
+            0,
+            (
+                dyn_input(|| "0\n1\n2\n3\n4\n5\n6\n7\n8\n9").size(Size::fixed(200, 80)),
+                label("Below"),
+            ),
+        )
+    })
+    .unwrap();
+    ui.step().unwrap();
+    ui.key(Modifiers::NONE, Key::Tab);
+    ui.step().unwrap();
+    let input = ui.window().focused().unwrap();
+    // The line the cursor is on, from where typing lands.
+    let line = || {
+        ui.type_text("|");
+        ui.step().unwrap();
+        let text = input.get_text();
+        ui.key(Modifiers::NONE, Key::Backspace);
+        ui.step().unwrap();
+        text.find('|').map(|i| text[..i].matches('\n').count())
+    };
+
+    ui.key(Modifiers::CONTROL, Key::Home);
+    ui.key(Modifiers::NONE, Key::PgDn);
+    let page = line().unwrap();
+    assert!((2..10).contains(&page), "{page} lines in a page");
+    ui.key(Modifiers::NONE, Key::PgUp);
+    assert_eq!(line(), Some(0));
+    ui.key(Modifiers::CONTROL, Key::End);
+    ui.key(Modifiers::NONE, Key::PgUp);
+    assert_eq!(line(), Some(9 - page));
+
+    // Past the ends to the start and the end of the text.
+    ui.key(Modifiers::NONE, Key::PgDn);
+    ui.key(Modifiers::NONE, Key::PgDn);
+    ui.type_text("|");
+    ui.step().unwrap();
+    assert!(input.get_text().ends_with("9|"));
+    ui.key(Modifiers::NONE, Key::PgUp);
+    ui.key(Modifiers::NONE, Key::PgUp);
+    ui.type_text("|");
+    ui.step().unwrap();
+    assert!(input.get_text().starts_with("|0"));
+}
+
+/// A 200x100 scroll view over 20 rows 40 high, 700 to scroll.
+fn scrolled() -> (Harness, Rc<ScrollView>) {
+    let view = Rc::new(RefCell::new(None));
+    let ui = Harness::new({
+        let view = view.clone();
+        move || {
+            let rows: Vec<_> = (0..20)
+                .map(|i| label(format!("Row {i}")).size(Size::fixed(180, 40)))
+                .collect();
+            let scroll = scroll_view(vstack(0, rows)).size(Size::fixed(200, 100));
+            *view.borrow_mut() = Some(scroll.clone());
+            vstack(0, (scroll, label("Below")))
+        }
+    })
+    .unwrap();
+    ui.step().unwrap();
+    let view = view.borrow_mut().take().unwrap();
+    (ui, view)
+}
+
+#[test]
+fn scroll_to_widget_reveals_it() {
+    let (ui, view) = scrolled();
+    let row = |n: usize| {
+        let text = format!("Row {n}");
+        let widgets = ui.widgets();
+        widgets
+            .into_iter()
+            .find(|(w, _)| w.get_text() == text)
+            .unwrap()
+    };
+
+    // Below the view, to its bottom edge.
+    assert!(view.scroll_to_widget(&row(10).0));
+    assert_eq!(view.scroll_offset(), Offset::new(0, 10 * 40 + 40 - 100));
+    ui.step().unwrap();
+    let rect = row(10).1;
+    assert_eq!(rect.y + rect.h as i32, 100);
+
+    // Shown already, nothing moves.
+    assert!(view.scroll_to_widget(&row(9).0));
+    assert_eq!(view.scroll_offset().y, 340);
+    // Above it, to its top edge.
+    assert!(view.scroll_to_widget(&row(2).0));
+    assert_eq!(view.scroll_offset().y, 80);
+
+    let outside = ui
+        .widgets()
+        .into_iter()
+        .find(|(w, _)| w.get_text() == "Below");
+    assert!(!view.scroll_to_widget(&outside.unwrap().0));
+    assert_eq!(view.scroll_offset().y, 80);
+}
+
+#[test]
+fn scroll_is_clamped() {
+    let (ui, view) = scrolled();
+    view.scroll_to(Offset::new(50, 10_000));
+    assert_eq!(view.scroll_offset(), Offset::new(0, 700));
+    view.scroll_to(Offset::new(0, -50));
+    assert_eq!(view.scroll_offset(), Offset::new(0, 0));
+
+    ui.scroll(50, 50, 0.0, -3.0);
+    ui.step().unwrap();
+    assert_eq!(view.scroll_offset().y, 0);
+    ui.scroll(50, 50, 0.0, 2.0);
+    ui.step().unwrap();
+    assert_eq!(view.scroll_offset().y, 80);
+    ui.scroll(50, 50, 0.0, 100.0);
+    ui.step().unwrap();
+    assert_eq!(view.scroll_offset().y, 700);
+    // Outside the view.
+    ui.scroll(50, 150, 0.0, -1.0);
+    ui.step().unwrap();
+    assert_eq!(view.scroll_offset().y, 700);
+}
+
+#[test]
+fn drag_scrollbar_thumb() {
+    let (ui, view) = scrolled();
+    // The track is the right 8 pixels, the thumb its smallest, 16 long, at the top.
+    ui.move_to(196, 5);
+    ui.press(Button::Left, 196, 5);
+    ui.move_to(196, 5 + 42);
+    ui.step().unwrap();
+    assert_eq!(view.scroll_offset().y, 350);
+    ui.move_to(196, 5 + 84);
+    ui.step().unwrap();
+    assert_eq!(view.scroll_offset().y, 700);
+    ui.move_to(196, 300);
+    ui.step().unwrap();
+    assert_eq!(view.scroll_offset().y, 700);
+
+    ui.release(Button::Left, 196, 300);
+    ui.move_to(196, 5);
+    ui.step().unwrap();
+    assert_eq!(view.scroll_offset().y, 700);
+
+    // Clicking the track pages towards the click.
+    ui.click(Button::Left, 196, 5);
+    ui.step().unwrap();
+    assert_eq!(view.scroll_offset().y, 700 - 60);
+}
+
+#[test]
+fn keys_scroll_the_focused_view() {
+    let (ui, view) = scrolled();
+    ui.click(Button::Left, 50, 50);
+    ui.step().unwrap();
+    let scrolls = [
+        (Modifiers::NONE, Key::ArrowDown, 40),
+        (Modifiers::NONE, Key::PgDn, 100),
+        (Modifiers::NONE, Key::ArrowUp, 60),
+        (Modifiers::NONE, Key::End, 700),
+        (Modifiers::NONE, Key::PgDn, 700),
+        (Modifiers::NONE, Key::Home, 0),
+        (Modifiers::NONE, Key::Space, 60),
+        (Modifiers::SHIFT, Key::Space, 0),
+        (Modifiers::NONE, Key::ArrowUp, 0),
+    ];
+    for (mods, key, y) in scrolls {
+        ui.key(mods, key);
+        ui.step().unwrap();
+        assert_eq!(view.scroll_offset().y, y, "after {key:?}");
+    }
+    // Not sideways, everything fits.
+    ui.key(Modifiers::NONE, Key::ArrowRight);
+    ui.step().unwrap();
+    assert_eq!(view.scroll_offset().x, 0);
+}
+
+#[test]
+fn virtual_list_follows_items() {
+    let items = RwSignal::new((0..1000).map(|i| format!("Item {i}")).collect::<Vec<_>>());
+    let built = Rc::new(Cell::new(0));
+    let view = Rc::new(RefCell::new(None));
+    let ui = Harness::new({
+        let (built, view) = (built.clone(), view.clone());
+        move || {
+            let list = virtual_list(
+                move || items.get(),
+                |item| item.clone(),
+                move |item| {
+                    built.set(built.get() + 1);
+                    label(item.clone())
+                },
+            )
+            .row_height(20);
+            let scroll = scroll_view(list).size(Size::fixed(200, 100));
+            *view.borrow_mut() = Some(scroll.clone());
+            vstack(0, (scroll, label("Below")))
+        }
+    })
+    .unwrap();
+    ui.step().unwrap();
+    let view = view.borrow_mut().take().unwrap();
+    let row = |text: &str| {
+        ui.widgets()
+            .into_iter()
+            .find(|(w, _)| w.get_text() == text)
+            .map(|(w, _)| w)
+    };
+    assert!(row("Item 0").is_some());
+    assert!(row("Item 500").is_none());
+    let shown = built.get();
+
+    // Only the changed row is built again.
+    let first = row("Item 0").unwrap();
+    items.update(|items| items[1] = "Changed".to_owned());
+    ui.step().unwrap();
+    assert!(row("Changed").is_some());
+    assert!(row("Item 1").is_none());
+    assert_eq!(built.get(), shown + 1);
+    assert!(Rc::ptr_eq(&first, &row("Item 0").unwrap()));
+
+    // Rows scrolled away are taken again when they come back.
+    view.scroll_to(Offset::new(0, 100_000));
+    ui.step().unwrap();
+    assert!(row("Item 0").is_none());
+    assert!(row("Item 999").is_some());
+    view.scroll_to(Offset::new(0, 0));
+    ui.step().unwrap();
+    assert!(Rc::ptr_eq(&first, &row("Item 0").unwrap()));
+
+    items.set(vec!["Only".to_owned()]);
+    ui.step().unwrap();
+    assert!(row("Only").is_some());
+    assert!(row("Item 0").is_none());
+}
+
+#[test]
+#[should_panic(expected = "No golden image")]
+fn missing_golden_image_fails() {
+    let ui = booker();
+    ui.step().unwrap();
+    ui.assert_png("tests/snapshots/missing.png", 0);
+}